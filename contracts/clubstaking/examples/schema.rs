@@ -10,7 +10,7 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 //use cw20_base::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 
-use clubstaking::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceivedMsg};
+use clubstaking::msg::{ExecuteMsg, InstantiateMsg, OwnershipResponse, QueryMsg, ReceivedMsg, RoleMembersResponse};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -22,6 +22,8 @@ fn main() {
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ReceivedMsg), &out_dir);
+    export_schema(&schema_for!(OwnershipResponse), &out_dir);
+    export_schema(&schema_for!(RoleMembersResponse), &out_dir);
 /*
     export_schema(&schema_for!(AllowanceResponse), &out_dir);
     export_schema(&schema_for!(BalanceResponse), &out_dir);
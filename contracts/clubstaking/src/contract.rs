@@ -17,7 +17,9 @@ use crate::state::{
     ClubPreviousOwnerDetails, ClubStakingDetails, Config, CONFIG, REWARD, REWARD_GIVEN_IN_CURRENT_TIMESTAMP,
     WINNING_CLUB_DETAILS_SNAPSHOT, WinningClubDetails,
 };
-use wasmswap::msg::{QueryMsg as WasmswapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse};
+use fanfury_common::access::{check_role, execute_access_msg, query_ownership, query_role_members, Role};
+use fanfury_common::migrate::check_migration_version;
+use fanfury_common::swap::{SwapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse};
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:clubstaking";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -172,12 +174,32 @@ pub fn execute(
         } => {
             increase_reward_amount(deps, env, info, reward_from, amount)
         }
+        ExecuteMsg::Access(msg) => {
+            let config = CONFIG.load(deps.storage)?;
+            Ok(execute_access_msg(deps, &env, &info, &config.admin_address, msg, |storage, new_owner| {
+                CONFIG.update(storage, |mut config| -> StdResult<_> {
+                    config.admin_address = new_owner.clone();
+                    Ok(config)
+                })?;
+                Ok(())
+            })?)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+    let previous_version = check_migration_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    match msg {
+        MigrateMsg::Upgrade {} => {}
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
 }
 pub fn uusd(
     deps: &DepsMut,
@@ -1417,10 +1439,8 @@ fn increase_reward_amount(
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    // For SECURITY This message MUST only come from the Admin
-    if info.sender != config.admin_address {
-        return Err(ContractError::Unauthorized {});
-    }
+    // For SECURITY This message MUST only come from the Admin or an operator
+    check_role(deps.storage, &config.admin_address, &info.sender, Role::Operator)?;
     let existing_reward = REWARD.may_load(deps.storage)?.unwrap_or_default();
     let new_reward = existing_reward + amount;
     REWARD.save(deps.storage, &new_reward)?;
@@ -1542,9 +1562,9 @@ fn calculate_and_distribute_rewards(
     is_first_batch: bool,
     is_final_batch: bool,
 ) -> Result<Response, ContractError> {
-    // Check if this is executed by main/transaction wallet
+    // Check if this is executed by main/transaction wallet or an operator
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin_address {
+    if check_role(deps.storage, &config.admin_address, &info.sender, Role::Operator).is_err() {
         return Err(ContractError::Std(StdError::GenericErr {
             msg: String::from("not authorised"),
         }));
@@ -1949,6 +1969,14 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             staker,
             club_name,
         } => to_binary(&query_staker_rewards(deps, staker, club_name)?),
+        QueryMsg::Ownership {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&query_ownership(deps.storage, config.admin_address)?)
+        }
+        QueryMsg::RoleMembers { role, start_after, limit } => {
+            let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+            to_binary(&query_role_members(deps.storage, role, start_after, limit)?)
+        }
     }
 }
 
@@ -2025,6 +2053,9 @@ pub fn query_platform_fees(deps: Deps, msg: Binary) -> StdResult<Uint128> {
             fury_amount_provided = query_staker_rewards(deps, staker, club_name)?;
             platform_fees_percentage = config.platform_fees + config.transaction_fees;
         }
+        Ok(ExecuteMsg::Access(_)) => {
+            return Ok(Uint128::zero());
+        }
         Err(err) => {
             return Err(StdError::generic_err(format!("{:?}", err)));
         }
@@ -2033,7 +2064,8 @@ pub fn query_platform_fees(deps: Deps, msg: Binary) -> StdResult<Uint128> {
 
     let ust_equiv_for_fury_response: Token2ForToken1PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: config.pool_address.clone().to_string(),
-        msg: to_binary(&WasmswapQueryMsg::Token2ForToken1Price {
+        msg: to_binary(&SwapQueryMsg::Token2ForToken1Price {
+            pair_id: None,
             token2_amount: fury_amount_provided
         })?,
     }))?;
@@ -2255,7 +2287,9 @@ mod tests {
     use cosmwasm_std::{Addr, coins, CosmosMsg, from_binary, StdError, SubMsg, WasmMsg};
     use cosmwasm_std::coin;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use fanfury_common::CommonError;
 
+    use crate::msg::AccessMsg;
     use super::*;
 
     #[test]
@@ -4274,4 +4308,109 @@ mod tests {
             club_name3, false, true).unwrap_err();
         assert_eq!(res3, (ContractError::Std(StdError::GenericErr {msg: String::from("Time for Reward not yet arrived")})));
     }
+
+    #[test]
+    fn test_operator_runs_the_reward_jobs() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+
+        let instantiate_msg = InstantiateMsg {
+            admin_address: "admin11111".to_string(),
+            fury_token_address: "minting_admin11111".to_string(),
+            pool_address: "pool_address1111".to_string(),
+            club_fee_collector_wallet: "club_fee_collector_wallet11111".to_string(),
+            club_reward_next_timestamp: now.minus_seconds(1),
+            reward_periodicity: 5 * 60 * 60u64,
+            club_price: Uint128::from(1000000u128),
+            bonding_duration: 5 * 60u64,
+            owner_release_locking_duration: 24 * 60 * 60u64,
+            platform_fees_collector_wallet: "platform_fee_collector_wallet_1111".to_string(),
+            platform_fees: Uint128::from(100u128),
+            transaction_fees: Uint128::from(30u128),
+            control_fees: Uint128::from(50u128),
+            max_bonding_limit_per_user: 10u64,
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let operatorInfo = mock_info("operator001", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+        buy_a_club(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner001", &[coin(1000, "stake")]),
+            "owner001".to_string(),
+            Some(String::default()),
+            "CLUB001".to_string(),
+            Uint128::from(1000000u128),
+            SET_AUTO_STAKE,
+        ).unwrap();
+
+        let err = increase_reward_amount(deps.as_mut(), mock_env(), operatorInfo.clone(), "reward_from abc".to_string(), Uint128::from(1000u128)).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::Unauthorized {}));
+        let err = calculate_and_distribute_rewards(deps.as_mut(), mock_env(), operatorInfo.clone(), vec![], "CLUB001".to_string(), true, true).unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::GenericErr { msg: String::from("not authorised") }));
+
+        let grant_operator = ExecuteMsg::Access(AccessMsg::GrantRole {
+            role: Role::Operator,
+            address: "operator001".to_string(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), operatorInfo.clone(), grant_operator.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), adminInfo.clone(), grant_operator).unwrap();
+
+        increase_reward_amount(deps.as_mut(), mock_env(), operatorInfo.clone(), "reward_from abc".to_string(), Uint128::from(1000u128)).unwrap();
+        assert_eq!(query_reward_amount(&deps.storage).unwrap(), Uint128::from(1000u128));
+        calculate_and_distribute_rewards(deps.as_mut(), mock_env(), operatorInfo.clone(), vec![], "CLUB001".to_string(), true, true).unwrap();
+        assert_eq!(CLUB_REWARD_NEXT_TIMESTAMP.load(&deps.storage).unwrap(), now.minus_seconds(1).plus_seconds(5 * 60 * 60u64));
+
+        // revoking the role takes the jobs away again
+        execute(deps.as_mut(), mock_env(), adminInfo, ExecuteMsg::Access(AccessMsg::RevokeRole {
+            role: Role::Operator,
+            address: "operator001".to_string(),
+        })).unwrap();
+        let err = increase_reward_amount(deps.as_mut(), mock_env(), operatorInfo, "reward_from abc".to_string(), Uint128::from(1000u128)).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_migrate_refuses_downgrade() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            admin_address: "admin11111".to_string(),
+            fury_token_address: "minting_admin11111".to_string(),
+            pool_address: "pool_address1111".to_string(),
+            club_fee_collector_wallet: "club_fee_collector_wallet11111".to_string(),
+            club_reward_next_timestamp: mock_env().block.time,
+            reward_periodicity: 5 * 60 * 60u64,
+            club_price: Uint128::from(1000000u128),
+            bonding_duration: 5 * 60u64,
+            owner_release_locking_duration: 24 * 60 * 60u64,
+            platform_fees_collector_wallet: "platform_fee_collector_wallet_1111".to_string(),
+            platform_fees: Uint128::from(100u128),
+            transaction_fees: Uint128::from(30u128),
+            control_fees: Uint128::from(50u128),
+            max_bonding_limit_per_user: 10u64,
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("admin11111", &[]), instantiate_msg).unwrap();
+
+        cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "1.0.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+        assert_eq!(res.attributes[1].value, "1.0.0");
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::CannotMigrateVersion {
+            previous_version: "99.0.0".to_string(),
+            new_version: CONTRACT_VERSION.to_string(),
+        }));
+
+        cw2::set_contract_version(&mut deps.storage, "crates.io:gamingpool", "1.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::CannotMigrate {
+            previous_contract: "crates.io:gamingpool".to_string(),
+        }));
+    }
 }
@@ -1,11 +1,16 @@
 use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
+use fanfury_common::CommonError;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error(transparent)]
+    Common(#[from] CommonError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -7,6 +7,8 @@ use cw20::Cw20ReceiveMsg;
 
 use crate::state::ClubStakingDetails;
 
+pub use fanfury_common::access::{AccessMsg, OwnershipResponse, Role, RoleMembersResponse};
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     /// Administrator privilege wallet address
@@ -105,7 +107,8 @@ pub enum ExecuteMsg {
         reward_from: String,
         amount: Uint128,
     },
-
+    /// Ownership transfer and role management
+    Access(AccessMsg),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -150,6 +153,14 @@ pub enum QueryMsg {
         staker: String,
         club_name: String,
     },
+    /// Owner and the pending ownership offer
+    Ownership {},
+    /// Addresses granted the role, the owner not included
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -165,5 +176,8 @@ pub struct IncreaseRewardAmountCommand {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
-
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    /// Records the new contract version, for releases keeping the stored state as it is
+    Upgrade {},
+}
@@ -4,7 +4,7 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 use cosmwasm_std::Coin;
 
-use fanfurybonding::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, BondStateResponse, AllBondStateResponse};
+use fanfurybonding::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, BondStateResponse, AllBondStateResponse, BondPriceResponse, BondMarketResponse, BondMarketsResponse, SolvencyResponse, RemainingCapacityResponse, OwnershipResponse, RoleMembersResponse};
 
 fn main() {
   let mut out_dir = current_dir().unwrap();
@@ -18,4 +18,11 @@ fn main() {
   export_schema(&schema_for!(BondStateResponse), &out_dir);
   export_schema(&schema_for!(ConfigResponse), &out_dir);
   export_schema(&schema_for!(AllBondStateResponse), &out_dir);
+  export_schema(&schema_for!(BondPriceResponse), &out_dir);
+  export_schema(&schema_for!(BondMarketResponse), &out_dir);
+  export_schema(&schema_for!(BondMarketsResponse), &out_dir);
+  export_schema(&schema_for!(SolvencyResponse), &out_dir);
+  export_schema(&schema_for!(RemainingCapacityResponse), &out_dir);
+  export_schema(&schema_for!(OwnershipResponse), &out_dir);
+  export_schema(&schema_for!(RoleMembersResponse), &out_dir);
 }
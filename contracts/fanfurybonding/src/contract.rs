@@ -1,28 +1,36 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128, from_binary,
+    attr, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128, from_binary,
     WasmMsg, WasmQuery, QueryRequest, Order, Addr, CosmosMsg, QuerierWrapper, Storage
 };
-use cw2::{get_contract_version, set_contract_version};
+use cw2::set_contract_version;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
 use cw_storage_plus::Bound;
 use cw_utils::{maybe_addr};
 use crate::error::ContractError;
 use crate::msg::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, BondStateResponse, BondingRecord, AllBondStateResponse
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, BondStateResponse, BondingRecord, AllBondStateResponse, Role,
+    BondPriceResponse, BondMarketResponse, BondMarketsResponse, SolvencyResponse,
+    RemainingCapacityResponse
 };
 
 use crate::state::{
-    Config, CONFIG, BONDING
+    Config, CONFIG, BONDING, BondPricing, BondMarket, BOND_MARKETS, DEFAULT_MARKET_ID, NEXT_MARKET_ID,
+    TOTAL_LIABILITIES, BondCapacity, BOND_CAPACITY, CONFIG_V1
 };
 use cw20::Balance;
-use crate::util;
-use crate::util::{NORMAL_DECIMAL, THOUSAND};
-use wasmswap::msg::{QueryMsg as WasmswapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse};
+use fanfury_common::{access, CommonError};
+use fanfury_common::migrate::check_migration_version;
+use fanfury_common::token::{self, NORMAL_DECIMAL, THOUSAND};
+use fanfury_common::swap::{SwapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse};
 // Version info, for migration info
-const CONTRACT_NAME: &str = "fanfurybonding";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const CONTRACT_NAME: &str = "fanfurybonding";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_WINDOW_SECONDS: u64 = 86400;
+// First release with bond markets and the rolling capacity window
+const ROLLING_CAPACITY_VERSION: &str = "1.1.0";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -45,12 +53,20 @@ pub fn instantiate(
         tx_fee: msg.tx_fee,
         platform_fee: msg.platform_fee,
         enabled: true,
-        daily_vesting_amount: msg.daily_vesting_amount,
-        cumulated_amount: Uint128::zero(),
-        daily_current_bond_amount: Uint128::zero(),
-        last_timestamp: env.block.time.seconds()
+        daily_vesting_amount: msg.daily_vesting_amount
     };
     CONFIG.save(deps.storage, &config)?;
+    BOND_MARKETS.save(deps.storage, DEFAULT_MARKET_ID, &default_bond_market(&config, env.block.time.seconds()))?;
+    NEXT_MARKET_ID.save(deps.storage, &(DEFAULT_MARKET_ID + 1))?;
+    TOTAL_LIABILITIES.save(deps.storage, &Uint128::zero())?;
+    BOND_CAPACITY.save(deps.storage, &BondCapacity {
+        window_seconds: DEFAULT_WINDOW_SECONDS,
+        max_carry_over: Uint128::zero(),
+        in_usdc: false,
+        usdc_window_amount: Uint128::zero(),
+        remaining: config.daily_vesting_amount,
+        last_refill: env.block.time.seconds()
+    })?;
 
     Ok(Response::default())
 }
@@ -63,13 +79,26 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateOwner{owner} => execute_update_owner(deps, env, info, owner),
         ExecuteMsg::UpdateEnabled{enabled} => execute_update_enabled(deps, env, info, enabled),
         ExecuteMsg::UpdateConfig{treasury_address, lock_seconds, discount, tx_fee, platform_fee, daily_vesting_amount} => execute_update_config(deps, env, info, treasury_address, lock_seconds, discount, tx_fee, platform_fee, daily_vesting_amount),
-        ExecuteMsg::Bond { amount } => execute_bond(deps, env, info, amount),
-        ExecuteMsg::LpBond {address, amount} => execute_lp_bond(deps, env, info, address, amount),
+        ExecuteMsg::Bond { market_id, amount } => execute_bond(deps, env, info, market_id.unwrap_or(DEFAULT_MARKET_ID), amount),
+        ExecuteMsg::LpBond { market_id, address, amount } => execute_lp_bond(deps, env, info, market_id.unwrap_or(DEFAULT_MARKET_ID), address, amount),
         ExecuteMsg::Unbond { } => execute_unbond(deps, env, info),
-        ExecuteMsg::Withdraw { amount } => execute_withdraw(deps, env, info, amount)
+        ExecuteMsg::Withdraw { amount } => execute_withdraw(deps, env, info, amount),
+        ExecuteMsg::UpdateCapacity { window_seconds, max_carry_over, in_usdc, usdc_window_amount } => execute_update_capacity(deps, env, info, window_seconds, max_carry_over, in_usdc, usdc_window_amount),
+        ExecuteMsg::CreateBondMarket { lock_seconds, discount, capacity, open_time, close_time } => execute_create_bond_market(deps, env, info, lock_seconds, discount, capacity, open_time, close_time),
+        ExecuteMsg::UpdateBondMarket { market_id, lock_seconds, discount, capacity, open_time, close_time, enabled } => execute_update_bond_market(deps, info, market_id, lock_seconds, discount, capacity, open_time, close_time, enabled),
+        ExecuteMsg::UpdateBondPricing { market_id, control_variable, min_discount, max_discount, decay_seconds } => execute_update_bond_pricing(deps, env, info, market_id.unwrap_or(DEFAULT_MARKET_ID), control_variable, min_discount, max_discount, decay_seconds),
+        ExecuteMsg::Access(msg) => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(access::execute_access_msg(deps, &env, &info, &cfg.owner, msg, |storage, new_owner| {
+                CONFIG.update(storage, |mut cfg| -> StdResult<_> {
+                    cfg.owner = new_owner.clone();
+                    Ok(cfg)
+                })?;
+                Ok(())
+            })?)
+        }
     }
 }
 
@@ -77,9 +106,7 @@ pub fn check_enabled(
     storage: &mut dyn Storage,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(storage)?;
-    if !cfg.enabled {
-        return Err(ContractError::Disabled {})
-    }
+    access::check_enabled(cfg.enabled)?;
     Ok(Response::new().add_attribute("action", "check_enabled"))
 }
 
@@ -94,37 +121,14 @@ pub fn check_owner(
     Ok(Response::new().add_attribute("action", "check_owner"))
 }
 
-pub fn execute_update_owner(
-    deps: DepsMut, 
-    env: Env,
-    info: MessageInfo, 
-    owner: Addr
-) -> Result<Response, ContractError> {
-    
-    check_owner(deps.storage, info.sender.clone())?;
-
-    let mut cfg = CONFIG.load(deps.storage)?;
-    
-    CONFIG.save(deps.storage, &cfg)?;
-
-    return Ok(Response::new()
-        .add_attributes(vec![
-            attr("action", "update_owner"),
-            attr("owner", owner),
-        ]));
-}
-
-
 pub fn execute_update_enabled(
     deps: DepsMut, 
     env: Env,
     info: MessageInfo, 
     enabled: bool
 ) -> Result<Response, ContractError> {
-    
-    check_owner(deps.storage, info.sender.clone())?;
-
     let mut cfg = CONFIG.load(deps.storage)?;
+    access::check_update_enabled(deps.storage, &cfg.owner, &info.sender, enabled)?;
 
     cfg.enabled = enabled;
     CONFIG.save(deps.storage, &cfg)?;
@@ -137,6 +141,7 @@ pub fn execute_update_enabled(
 }
 
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_config(
     deps: DepsMut, 
     env: Env,
@@ -148,20 +153,30 @@ pub fn execute_update_config(
     platform_fee: u64,
     daily_vesting_amount: Uint128
 ) -> Result<Response, ContractError> {
-    check_owner(deps.storage, info.sender.clone())?;
-
     let mut cfg = CONFIG.load(deps.storage)?;
+    // the capacity refilled so far is kept at the old rate
+    refill_capacity(deps.storage, &cfg, env.block.time.seconds())?;
+    // the pool forwards its own config updates, a fee manager only changes the fees
+    if info.sender != cfg.owner && info.sender != cfg.pool_address {
+        access::check_role(deps.storage, &cfg.owner, &info.sender, Role::FeeManager)?;
+        if treasury_address != cfg.treasury_address
+            || lock_seconds != cfg.lock_seconds
+            || discount != cfg.discount
+            || daily_vesting_amount != cfg.daily_vesting_amount
+        {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+
     cfg.treasury_address = treasury_address.clone();
     cfg.lock_seconds = lock_seconds;
     cfg.discount = discount;
     cfg.tx_fee = tx_fee;
     cfg.platform_fee = platform_fee;
-    if cfg.daily_vesting_amount != daily_vesting_amount {
-        cfg.daily_vesting_amount = daily_vesting_amount;
-        //cfg.cumulated_amount = Uint128::zero();
-    }
+    cfg.daily_vesting_amount = daily_vesting_amount;
     
     CONFIG.save(deps.storage, &cfg)?;
+    update_default_market_terms(deps.storage, &cfg)?;
 
     return Ok(Response::new()
         .add_attributes(vec![
@@ -176,36 +191,406 @@ pub fn execute_update_config(
 }
 
 
-pub fn check_daily_vesting_amount(
+#[allow(clippy::too_many_arguments)]
+pub fn execute_update_bond_pricing(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: u64,
+    control_variable: u64,
+    min_discount: u64,
+    max_discount: Option<u64>,
+    decay_seconds: u64
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if decay_seconds == 0 {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    // settle the decay with the old term before switching to the new one
+    let mut market = load_bond_market(deps.storage, market_id)?;
+    market.pricing.total_debt = get_decayed_debt(&market.pricing, env.block.time.seconds());
+    market.pricing.last_decay = env.block.time.seconds();
+    market.pricing.control_variable = control_variable;
+    market.pricing.min_discount = min_discount;
+    market.pricing.max_discount = max_discount;
+    market.pricing.decay_seconds = decay_seconds;
+    check_bond_market(&market)?;
+    BOND_MARKETS.save(deps.storage, market_id, &market)?;
+
+    return Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_bond_pricing"),
+            attr("market_id", market_id.to_string()),
+            attr("control_variable", control_variable.to_string()),
+            attr("min_discount", min_discount.to_string()),
+            attr("max_discount", max_discount.map_or("none".to_string(), |max_discount| max_discount.to_string())),
+            attr("decay_seconds", decay_seconds.to_string()),
+        ]));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_bond_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    lock_seconds: u64,
+    discount: u64,
+    capacity: Option<Uint128>,
+    open_time: Option<u64>,
+    close_time: Option<u64>
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let market = BondMarket {
+        lock_seconds,
+        discount,
+        capacity,
+        sold_amount: Uint128::zero(),
+        open_time: open_time.unwrap_or(env.block.time.seconds()),
+        close_time,
+        enabled: true,
+        pricing: default_bond_pricing(lock_seconds, env.block.time.seconds())
+    };
+    check_bond_market(&market)?;
+
+    let market_id = NEXT_MARKET_ID.load(deps.storage)?;
+    BOND_MARKETS.save(deps.storage, market_id, &market)?;
+    NEXT_MARKET_ID.save(deps.storage, &(market_id + 1))?;
+
+    return Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "create_bond_market"),
+            attr("market_id", market_id.to_string()),
+            attr("lock_seconds", lock_seconds.to_string()),
+            attr("discount", discount.to_string()),
+        ]));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_update_bond_market(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: u64,
+    lock_seconds: u64,
+    discount: u64,
+    capacity: Option<Uint128>,
+    open_time: u64,
+    close_time: Option<u64>,
+    enabled: bool
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut market = load_bond_market(deps.storage, market_id)?;
+    market.lock_seconds = lock_seconds;
+    market.discount = discount;
+    market.capacity = capacity;
+    market.open_time = open_time;
+    market.close_time = close_time;
+    market.enabled = enabled;
+    check_bond_market(&market)?;
+    BOND_MARKETS.save(deps.storage, market_id, &market)?;
+
+    // the config keeps reporting the terms of the default market
+    if market_id == DEFAULT_MARKET_ID {
+        cfg.lock_seconds = lock_seconds;
+        cfg.discount = discount;
+        CONFIG.save(deps.storage, &cfg)?;
+    }
+
+    return Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_bond_market"),
+            attr("market_id", market_id.to_string()),
+            attr("lock_seconds", lock_seconds.to_string()),
+            attr("discount", discount.to_string()),
+            attr("enabled", enabled.to_string()),
+        ]));
+}
+
+fn check_bond_market(
+    market: &BondMarket
+) -> Result<(), ContractError> {
+    if market.discount >= THOUSAND {
+        return Err(ContractError::InvalidDiscount { discount: market.discount });
+    }
+    if let Some(max_discount) = market.pricing.max_discount {
+        if max_discount >= THOUSAND {
+            return Err(ContractError::InvalidDiscount { discount: max_discount });
+        }
+    }
+    // a floor above the discount of the market would never apply
+    if market.pricing.min_discount > get_max_discount(market) {
+        return Err(ContractError::InvalidDiscount { discount: market.pricing.min_discount });
+    }
+    if let Some(close_time) = market.close_time {
+        if close_time <= market.open_time {
+            return Err(ContractError::InvalidInput {});
+        }
+    }
+    Ok(())
+}
+
+pub fn load_bond_market(
+    storage: &dyn Storage,
+    market_id: u64
+) -> Result<BondMarket, ContractError> {
+    BOND_MARKETS.may_load(storage, market_id)?.ok_or(ContractError::UnknownBondMarket { market_id })
+}
+
+/// Default market matching the single set of terms of the contracts made before bond markets
+pub fn default_bond_market(
+    cfg: &Config,
+    current_timestamp: u64
+) -> BondMarket {
+    BondMarket {
+        lock_seconds: cfg.lock_seconds,
+        discount: cfg.discount,
+        capacity: None,
+        sold_amount: Uint128::zero(),
+        open_time: 0,
+        close_time: None,
+        enabled: true,
+        pricing: default_bond_pricing(cfg.lock_seconds, current_timestamp)
+    }
+}
+
+fn update_default_market_terms(
+    storage: &mut dyn Storage,
+    cfg: &Config
+) -> Result<(), ContractError> {
+    let mut market = load_bond_market(storage, DEFAULT_MARKET_ID)?;
+    market.lock_seconds = cfg.lock_seconds;
+    market.discount = cfg.discount;
+    check_bond_market(&market)?;
+    BOND_MARKETS.save(storage, DEFAULT_MARKET_ID, &market)?;
+    Ok(())
+}
+
+/// Pricing keeping the market discount fixed, as in the contracts made before control-variable pricing
+pub fn default_bond_pricing(
+    lock_seconds: u64,
+    current_timestamp: u64
+) -> BondPricing {
+    BondPricing {
+        control_variable: 0,
+        min_discount: 0,
+        max_discount: None,
+        decay_seconds: lock_seconds,
+        total_debt: Uint128::zero(),
+        last_decay: current_timestamp
+    }
+}
+
+/// Bond debt left at `current_timestamp`, decaying linearly over `decay_seconds`
+pub fn get_decayed_debt(
+    pricing: &BondPricing,
+    current_timestamp: u64
+) -> Uint128 {
+    let elapsed = current_timestamp.saturating_sub(pricing.last_decay);
+    if elapsed >= pricing.decay_seconds {
+        return Uint128::zero();
+    }
+    pricing.total_debt - pricing.total_debt.multiply_ratio(elapsed, pricing.decay_seconds)
+}
+
+/// Permille of the debt against the daily vesting amount
+pub fn get_debt_ratio(
+    cfg: &Config,
+    total_debt: Uint128
+) -> Uint128 {
+    if cfg.daily_vesting_amount.is_zero() {
+        return Uint128::zero();
+    }
+    total_debt.multiply_ratio(THOUSAND, cfg.daily_vesting_amount)
+}
+
+/// Market discount, capped at the `max_discount` of its pricing
+pub fn get_max_discount(
+    market: &BondMarket
+) -> u64 {
+    market.pricing.max_discount.map_or(market.discount, |max_discount| max_discount.min(market.discount))
+}
+
+/// Discount shrinking from the maximum discount by the control variable times the debt ratio of the market, down to its `min_discount`
+pub fn get_bond_discount(
+    cfg: &Config,
+    market: &BondMarket,
+    total_debt: Uint128
+) -> u64 {
+    let max_discount = get_max_discount(market);
+    let reduction = get_debt_ratio(cfg, total_debt).multiply_ratio(market.pricing.control_variable, THOUSAND);
+    let discount = Uint128::from(max_discount).saturating_sub(reduction).u128() as u64;
+    discount.max(market.pricing.min_discount).min(max_discount)
+}
+
+/// Prices a bond of `amount` FURY at market, adds it to the debt and the sales of the market, and returns the FURY owed with the discount applied
+pub fn take_bond_debt(
     storage: &mut dyn Storage,
+    cfg: &Config,
+    market_id: u64,
     timestamp: u64,
-    receiving_amount: Uint128
+    amount: Uint128
+) -> Result<(Uint128, BondMarket, u64), ContractError> {
+    let mut market = load_bond_market(storage, market_id)?;
+    if !market.enabled
+        || timestamp < market.open_time
+        || market.close_time.is_some_and(|close_time| timestamp >= close_time)
+    {
+        return Err(ContractError::BondMarketClosed { market_id });
+    }
+
+    let total_debt = get_decayed_debt(&market.pricing, timestamp);
+    let discount = get_bond_discount(cfg, &market, total_debt);
+    let receiving_amount = amount * Uint128::from(THOUSAND) / Uint128::from(THOUSAND - discount);
+
+    market.sold_amount += receiving_amount;
+    if market.capacity.is_some_and(|capacity| market.sold_amount > capacity) {
+        return Err(ContractError::BondMarketCapacityExceeded { market_id });
+    }
+    market.pricing.total_debt = total_debt + receiving_amount;
+    market.pricing.last_decay = timestamp;
+    BOND_MARKETS.save(storage, market_id, &market)?;
+
+    Ok((receiving_amount, market, discount))
+}
+
+/// Adds `amount` to the FURY owed to bonders, refusing it if the FURY balance of the contract cannot cover it
+pub fn add_liabilities(
+    deps: DepsMut,
+    env: &Env,
+    cfg: &Config,
+    amount: Uint128
 ) -> Result<Response, ContractError> {
-    let mut cfg = CONFIG.load(storage)?;
+    let liabilities = TOTAL_LIABILITIES.load(deps.storage)? + amount;
+    let balance = token::get_token_amount(deps.querier, Denom::Cw20(cfg.fury_token_address.clone()), env.contract.address.clone())?;
+    if balance < liabilities {
+        return Err(ContractError::Insolvent { balance, liabilities });
+    }
+    TOTAL_LIABILITIES.save(deps.storage, &liabilities)?;
 
-    if timestamp / 86400 != cfg.last_timestamp / 86400 {
-        // This is new day.
-        if cfg.daily_current_bond_amount <= cfg.daily_vesting_amount {
-            cfg.cumulated_amount += cfg.daily_vesting_amount - cfg.daily_current_bond_amount;
-        }
-        cfg.daily_current_bond_amount = Uint128::zero();
+    Ok(Response::new().add_attribute("action", "add_liabilities"))
+}
+
+pub fn execute_update_capacity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    window_seconds: u64,
+    max_carry_over: Uint128,
+    in_usdc: bool,
+    usdc_window_amount: Uint128
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if window_seconds == 0 || (in_usdc && usdc_window_amount.is_zero()) {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let mut capacity = refill_capacity(deps.storage, &cfg, env.block.time.seconds())?;
+    let switched = capacity.in_usdc != in_usdc;
+    capacity.window_seconds = window_seconds;
+    capacity.max_carry_over = max_carry_over;
+    capacity.in_usdc = in_usdc;
+    capacity.usdc_window_amount = usdc_window_amount;
+    // what is left cannot be carried over to the other denomination
+    if switched {
+        capacity.remaining = get_window_amount(&cfg, &capacity);
+    }
+    BOND_CAPACITY.save(deps.storage, &capacity)?;
+
+    return Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_capacity"),
+            attr("window_seconds", window_seconds.to_string()),
+            attr("max_carry_over", max_carry_over),
+            attr("in_usdc", in_usdc.to_string()),
+            attr("usdc_window_amount", usdc_window_amount),
+        ]));
+}
+
+/// Capacity refilled over each window, in usdc if the capacity is in usdc and FURY otherwise
+pub fn get_window_amount(
+    cfg: &Config,
+    capacity: &BondCapacity
+) -> Uint128 {
+    if capacity.in_usdc {
+        capacity.usdc_window_amount
+    } else {
+        cfg.daily_vesting_amount
     }
+}
+
+/// Capacity left at `timestamp`, refilled at the window amount per window up to one window plus `max_carry_over`
+pub fn get_remaining_capacity(
+    cfg: &Config,
+    capacity: &BondCapacity,
+    timestamp: u64
+) -> Uint128 {
+    let window_amount = get_window_amount(cfg, capacity);
+    let elapsed = timestamp.saturating_sub(capacity.last_refill);
+    let refill = window_amount.multiply_ratio(elapsed, capacity.window_seconds);
+    capacity.remaining.saturating_add(refill).min(window_amount + capacity.max_carry_over)
+}
 
-    if cfg.daily_current_bond_amount + receiving_amount > cfg.daily_vesting_amount + cfg.cumulated_amount {
+fn refill_capacity(
+    storage: &mut dyn Storage,
+    cfg: &Config,
+    timestamp: u64
+) -> Result<BondCapacity, ContractError> {
+    let mut capacity = BOND_CAPACITY.load(storage)?;
+    capacity.remaining = get_remaining_capacity(cfg, &capacity, timestamp);
+    capacity.last_refill = timestamp;
+    BOND_CAPACITY.save(storage, &capacity)?;
+    Ok(capacity)
+}
+
+/// Takes a bond out of the rolling capacity. In usdc, the bond counts what was paid for it, `paid_amount` FURY being valued at the pool price.
+pub fn take_capacity(
+    deps: DepsMut,
+    env: &Env,
+    cfg: &Config,
+    receiving_amount: Uint128,
+    paid_denom: Denom,
+    paid_amount: Uint128
+) -> Result<Response, ContractError> {
+    let mut capacity = refill_capacity(deps.storage, cfg, env.block.time.seconds())?;
+
+    let amount = if !capacity.in_usdc {
+        receiving_amount
+    } else {
+        match paid_denom {
+            Denom::Native(_) => paid_amount,
+            Denom::Cw20(_) => paid_amount.multiply_ratio(get_usdc_price(cfg.clone(), deps.querier)?, NORMAL_DECIMAL)
+        }
+    };
+
+    if amount > capacity.remaining {
         return Err(ContractError::MaxBondingExceed {  })
     }
-    cfg.last_timestamp = timestamp;
-    cfg.daily_current_bond_amount += receiving_amount;
-    CONFIG.save(storage, &cfg)?;
+    capacity.remaining -= amount;
+    BOND_CAPACITY.save(deps.storage, &capacity)?;
     
-    Ok(Response::new().add_attribute("action", "check_daily_vesting_amount"))
+    Ok(Response::new().add_attribute("action", "take_capacity"))
 }
 
 
 pub fn execute_bond(
-    deps: DepsMut, 
+    mut deps: DepsMut, 
     env: Env,
     info: MessageInfo,
+    market_id: u64,
     amount: Uint128
 ) -> Result<Response, ContractError> {
     
@@ -218,7 +603,7 @@ pub fn execute_bond(
     }
 
     let balance = Balance::from(info.funds);
-    let usdc_amount = util::get_amount_of_denom(balance, Denom::Native(cfg.usdc_denom.clone()))?;
+    let usdc_amount = token::get_amount_of_denom(balance, Denom::Native(cfg.usdc_denom.clone()))?;
 
     if usdc_amount == Uint128::zero() {
         return Err(ContractError::NativeInputZero {  })
@@ -231,22 +616,27 @@ pub fn execute_bond(
 
     let token2_price_response: Token1ForToken2PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: cfg.pool_address.clone().into(),
-        msg: to_binary(&WasmswapQueryMsg::Token1ForToken2Price {
+        msg: to_binary(&SwapQueryMsg::Token1ForToken2Price {
+            pair_id: None,
             token1_amount: amount
         })?,
     }))?;
 
-    let receiving_amount = token2_price_response.token2_amount * Uint128::from(THOUSAND) / Uint128::from(THOUSAND - cfg.discount);
+    let (receiving_amount, market, discount) = take_bond_debt(deps.storage, &cfg, market_id, env.block.time.seconds(), token2_price_response.token2_amount)?;
 
-    check_daily_vesting_amount(deps.storage, env.block.time.seconds(), receiving_amount)?;
+    take_capacity(deps.branch(), &env, &cfg, receiving_amount, Denom::Native(cfg.usdc_denom.clone()), amount)?;
+    add_liabilities(deps.branch(), &env, &cfg, receiving_amount)?;
 
     let mut messages:Vec<CosmosMsg> = vec![];
-    messages.push(util::transfer_token_message(Denom::Native(cfg.usdc_denom.clone()), usdc_amount, cfg.treasury_address.clone())?);
+    messages.push(token::transfer_token_message(Denom::Native(cfg.usdc_denom.clone()), usdc_amount, cfg.treasury_address.clone())?);
 
     let mut list:Vec<BondingRecord> = BONDING.load(deps.storage, info.sender.clone()).unwrap_or(vec![]);
     list.push(BondingRecord {
         amount: receiving_amount,
-        timestamp: env.block.time.seconds() + cfg.lock_seconds
+        timestamp: env.block.time.seconds() + market.lock_seconds,
+        start: env.block.time.seconds(),
+        claimed: Uint128::zero(),
+        market_id
     });
     BONDING.save(deps.storage, info.sender.clone(), &list)?;
     
@@ -257,15 +647,18 @@ pub fn execute_bond(
             attr("action", "bond"),
             attr("bond_usdc_amount", amount),
             attr("receiving_fury_amount", receiving_amount),
+            attr("market_id", market_id.to_string()),
+            attr("discount", discount.to_string()),
             attr("address", info.sender.clone()),
         ]));
 }
 
 
 pub fn execute_lp_bond(
-    deps: DepsMut, 
+    mut deps: DepsMut, 
     env: Env,
     info: MessageInfo,
+    market_id: u64,
     address: Addr,
     amount: Uint128
 ) -> Result<Response, ContractError> {
@@ -287,14 +680,18 @@ pub fn execute_lp_bond(
     }
     
     // On lp bonding, the platform fee and tx fee is already stolen from swap contract
-    let receiving_amount = amount * Uint128::from(THOUSAND) / Uint128::from(THOUSAND - cfg.discount);
+    let (receiving_amount, market, discount) = take_bond_debt(deps.storage, &cfg, market_id, env.block.time.seconds(), amount)?;
     
-    check_daily_vesting_amount(deps.storage, env.block.time.seconds(), receiving_amount)?;
+    take_capacity(deps.branch(), &env, &cfg, receiving_amount, Denom::Cw20(cfg.fury_token_address.clone()), amount)?;
+    add_liabilities(deps.branch(), &env, &cfg, receiving_amount)?;
 
     let mut list:Vec<BondingRecord> = BONDING.load(deps.storage, address.clone()).unwrap_or(vec![]);
     list.push(BondingRecord {
         amount: receiving_amount,
-        timestamp: env.block.time.seconds() + cfg.lock_seconds
+        timestamp: env.block.time.seconds() + market.lock_seconds,
+        start: env.block.time.seconds(),
+        claimed: Uint128::zero(),
+        market_id
     });
     BONDING.save(deps.storage, address.clone(), &list)?;
     
@@ -303,6 +700,8 @@ pub fn execute_lp_bond(
             attr("action", "lp_bond"),
             attr("bond_fury_amount", amount),
             attr("receiving_amount", receiving_amount),
+            attr("market_id", market_id.to_string()),
+            attr("discount", discount.to_string()),
             attr("address", address.clone()),
         ]));
 }
@@ -332,30 +731,35 @@ pub fn execute_unbond(
         return Err(ContractError::NothingToUnbond {})
     }
 
+    // fully claimed bonds are dropped, the others remember what was paid
     let mut new_list: Vec<BondingRecord> = vec![];
-    for item in list {
-        if item.timestamp > env.block.time.seconds() {
+    for mut item in list {
+        item.claimed = get_vested_amount(&item, env.block.time.seconds());
+        if item.claimed < item.amount {
             new_list.push(item);
         }
     }
     BONDING.save(deps.storage, info.sender.clone(), &new_list)?;    
+    TOTAL_LIABILITIES.update(deps.storage, |liabilities| -> StdResult<_> {
+        Ok(liabilities.checked_sub(bond_state.unbond_amount)?)
+    })?;
     
     let balance = Balance::from(info.funds);
     //calculate tx fee
-    let usdc_amount = util::get_amount_of_denom(balance, Denom::Native(cfg.usdc_denom.clone()))?;
+    let usdc_amount = token::get_amount_of_denom(balance, Denom::Native(cfg.usdc_denom.clone()))?;
 
     if usdc_amount < bond_state.fee_amount {
         return Err(ContractError::InsufficientFee { })
     }
 
-    let fury_balance = util::get_token_amount(deps.querier, Denom::Cw20(cfg.fury_token_address.clone()), env.contract.address.clone())?;
+    let fury_balance = token::get_token_amount(deps.querier, Denom::Cw20(cfg.fury_token_address.clone()), env.contract.address.clone())?;
     if fury_balance < bond_state.unbond_amount {
         return Err(ContractError::InsufficientFury {})
     }
 
     let mut messages:Vec<CosmosMsg> = vec![];
-    messages.push(util::transfer_token_message(Denom::Cw20(cfg.fury_token_address.clone()), bond_state.unbond_amount, info.sender.clone())?);
-    messages.push(util::transfer_token_message(Denom::Native(cfg.usdc_denom.clone()), usdc_amount, cfg.treasury_address.clone())?);
+    messages.push(token::transfer_token_message(Denom::Cw20(cfg.fury_token_address.clone()), bond_state.unbond_amount, info.sender.clone())?);
+    messages.push(token::transfer_token_message(Denom::Native(cfg.usdc_denom.clone()), usdc_amount, cfg.treasury_address.clone())?);
     
     return Ok(Response::new()
         .add_messages(messages)
@@ -377,13 +781,18 @@ pub fn execute_withdraw(
 
     let cfg = CONFIG.load(deps.storage)?;
 
-    let fury_balance = util::get_token_amount(deps.querier, Denom::Cw20(cfg.fury_token_address.clone()), env.contract.address.clone())?;
+    let fury_balance = token::get_token_amount(deps.querier, Denom::Cw20(cfg.fury_token_address.clone()), env.contract.address.clone())?;
     if fury_balance < amount {
         return Err(ContractError::InsufficientFury {})
     }
+    // the FURY owed to bonders stays in the contract
+    let liabilities = TOTAL_LIABILITIES.load(deps.storage)?;
+    if fury_balance - amount < liabilities {
+        return Err(ContractError::Insolvent { balance: fury_balance - amount, liabilities });
+    }
 
     let mut messages:Vec<CosmosMsg> = vec![];
-    messages.push(util::transfer_token_message(Denom::Cw20(cfg.fury_token_address.clone()), amount, info.sender.clone())?);
+    messages.push(token::transfer_token_message(Denom::Cw20(cfg.fury_token_address.clone()), amount, info.sender.clone())?);
 
     return Ok(Response::new()
         .add_messages(messages)
@@ -405,6 +814,20 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             => to_binary(&query_bond_state(deps, env, address)?),
         QueryMsg::AllBondState {start_after, limit} 
             => to_binary(&query_all_bond_state(deps, env, start_after, limit)?),
+        QueryMsg::BondPrice {market_id}
+            => to_binary(&query_bond_price(deps, env, market_id.unwrap_or(DEFAULT_MARKET_ID))?),
+        QueryMsg::BondMarket {market_id}
+            => to_binary(&query_bond_market(deps, market_id)?),
+        QueryMsg::BondMarkets {start_after, limit}
+            => to_binary(&query_bond_markets(deps, start_after, limit)?),
+        QueryMsg::Solvency {}
+            => to_binary(&query_solvency(deps, env)?),
+        QueryMsg::RemainingCapacity {}
+            => to_binary(&query_remaining_capacity(deps, env)?),
+        QueryMsg::Ownership {}
+            => to_binary(&access::query_ownership(deps.storage, CONFIG.load(deps.storage)?.owner)?),
+        QueryMsg::RoleMembers {role, start_after, limit}
+            => to_binary(&access::query_role_members(deps.storage, role, maybe_addr(deps.api, start_after)?, limit)?),
     }
 }
 
@@ -422,10 +845,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         tx_fee: cfg.tx_fee,
         platform_fee: cfg.platform_fee,
         enabled: cfg.enabled,
-        daily_current_bond_amount: cfg.daily_current_bond_amount,
-        cumulated_amount: cfg.cumulated_amount,
-        daily_vesting_amount: cfg.daily_vesting_amount,
-        last_timestamp: cfg.last_timestamp
+        daily_vesting_amount: cfg.daily_vesting_amount
     })
 }
 
@@ -439,12 +859,14 @@ pub fn get_bond_state(
 
     let mut unbond_amount = Uint128::zero();
     let mut fee_amount = Uint128::zero();
-
-    for item in list.clone() {
-        if item.timestamp > current_timestamp {
-            continue;
-        }
-        unbond_amount += item.amount
+    let mut vested_amount = Uint128::zero();
+    let mut locked_amount = Uint128::zero();
+
+    for item in list.iter() {
+        let vested = get_vested_amount(item, current_timestamp);
+        vested_amount += vested;
+        locked_amount += item.amount - vested;
+        unbond_amount += vested - item.claimed;
     }
     
     if unbond_amount > Uint128::zero() {
@@ -455,17 +877,35 @@ pub fn get_bond_state(
         address,
         list,
         unbond_amount,
-        fee_amount
+        fee_amount,
+        vested_amount,
+        locked_amount
     })
 }
 
+/// Part of the bond vested at `current_timestamp`, growing linearly from its start to its maturity
+pub fn get_vested_amount(
+    item: &BondingRecord,
+    current_timestamp: u64
+) -> Uint128 {
+    if current_timestamp >= item.timestamp {
+        return item.amount;
+    }
+    // bonds made before linear vesting have no start and unlock at maturity only
+    if item.start == 0 || current_timestamp <= item.start {
+        return Uint128::zero();
+    }
+    item.amount.multiply_ratio(current_timestamp - item.start, item.timestamp - item.start)
+}
+
 pub fn get_usdc_price(
     cfg: Config,
     querier: QuerierWrapper
 ) -> StdResult<Uint128> {
     let usdc_price_response: Token2ForToken1PriceResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: cfg.pool_address.clone().into(),
-        msg: to_binary(&WasmswapQueryMsg::Token2ForToken1Price {
+        msg: to_binary(&SwapQueryMsg::Token2ForToken1Price {
+            pair_id: None,
             token2_amount: Uint128::from(NORMAL_DECIMAL)
         })?,
     }))?;
@@ -485,10 +925,92 @@ pub fn query_bond_state(deps: Deps, env: Env, address: Addr) -> StdResult<BondSt
     
 }
 
+pub fn query_bond_price(deps: Deps, env: Env, market_id: u64) -> StdResult<BondPriceResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let market = BOND_MARKETS.load(deps.storage, market_id)?;
+
+    let total_debt = get_decayed_debt(&market.pricing, env.block.time.seconds());
+    let discount = get_bond_discount(&cfg, &market, total_debt);
+    let market_price = get_usdc_price(cfg.clone(), deps.querier)?;
+
+    Ok(BondPriceResponse {
+        market_id,
+        discount,
+        control_variable: market.pricing.control_variable,
+        min_discount: market.pricing.min_discount,
+        max_discount: get_max_discount(&market),
+        decay_seconds: market.pricing.decay_seconds,
+        total_debt,
+        debt_ratio: get_debt_ratio(&cfg, total_debt),
+        market_price,
+        bond_price: market_price.multiply_ratio(THOUSAND - discount, THOUSAND)
+    })
+}
+
+pub fn query_solvency(deps: Deps, env: Env) -> StdResult<SolvencyResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let balance = token::get_token_amount(deps.querier, Denom::Cw20(cfg.fury_token_address.clone()), env.contract.address.clone())
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let liabilities = TOTAL_LIABILITIES.load(deps.storage)?;
+
+    Ok(SolvencyResponse {
+        balance,
+        liabilities,
+        surplus: balance.saturating_sub(liabilities)
+    })
+}
+
+pub fn query_remaining_capacity(deps: Deps, env: Env) -> StdResult<RemainingCapacityResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let capacity = BOND_CAPACITY.load(deps.storage)?;
+
+    Ok(RemainingCapacityResponse {
+        remaining: get_remaining_capacity(&cfg, &capacity, env.block.time.seconds()),
+        window_amount: get_window_amount(&cfg, &capacity),
+        window_seconds: capacity.window_seconds,
+        max_carry_over: capacity.max_carry_over,
+        in_usdc: capacity.in_usdc
+    })
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 
+fn map_bond_market(market_id: u64, market: BondMarket) -> BondMarketResponse {
+    BondMarketResponse {
+        market_id,
+        lock_seconds: market.lock_seconds,
+        discount: market.discount,
+        capacity: market.capacity,
+        sold_amount: market.sold_amount,
+        open_time: market.open_time,
+        close_time: market.close_time,
+        enabled: market.enabled
+    }
+}
+
+pub fn query_bond_market(deps: Deps, market_id: u64) -> StdResult<BondMarketResponse> {
+    Ok(map_bond_market(market_id, BOND_MARKETS.load(deps.storage, market_id)?))
+}
+
+pub fn query_bond_markets(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<BondMarketsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let markets: StdResult<Vec<_>> = BOND_MARKETS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(market_id, market)| map_bond_market(market_id, market)))
+        .collect();
+
+    Ok(BondMarketsResponse { markets: markets? })
+}
+
 fn map_bonding(
     cfg: Config,
     current_timestamp: u64,
@@ -532,13 +1054,73 @@ fn query_all_bond_state(
 
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
-        });
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous_version = check_migration_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let from_v1 = previous_version < ROLLING_CAPACITY_VERSION.parse().map_err(CommonError::from)?;
+    match msg {
+        MigrateMsg::Upgrade {} if !from_v1 => {}
+        MigrateMsg::FromV1 { max_carry_over } if from_v1 => migrate_from_v1(deps.storage, &env, max_carry_over)?,
+        _ => {
+            return Err(ContractError::UnsupportedMigration {
+                previous_version: previous_version.to_string(),
+            })
+        }
     }
-    Ok(Response::default())
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
+}
+
+fn migrate_from_v1(storage: &mut dyn Storage, env: &Env, max_carry_over: Uint128) -> Result<(), ContractError> {
+    let config = CONFIG_V1.load(storage)?;
+    let cfg = Config {
+        owner: config.owner,
+        pool_address: config.pool_address,
+        treasury_address: config.treasury_address,
+        fury_token_address: config.fury_token_address,
+        lock_seconds: config.lock_seconds,
+        discount: config.discount,
+        usdc_denom: config.usdc_denom,
+        is_native_bonding: config.is_native_bonding,
+        tx_fee: config.tx_fee,
+        platform_fee: config.platform_fee,
+        enabled: config.enabled,
+        daily_vesting_amount: config.daily_vesting_amount
+    };
+    CONFIG.save(storage, &cfg)?;
+
+    // the single set of terms becomes the default market, keeping its fixed discount
+    BOND_MARKETS.save(storage, DEFAULT_MARKET_ID, &default_bond_market(&cfg, env.block.time.seconds()))?;
+    NEXT_MARKET_ID.save(storage, &(DEFAULT_MARKET_ID + 1))?;
+
+    // what is owed is counted from the unpaid bonds
+    let mut liabilities = Uint128::zero();
+    for item in BONDING.range(storage, None, None, Order::Ascending) {
+        for record in item?.1 {
+            liabilities += record.amount - record.claimed;
+        }
+    }
+    TOTAL_LIABILITIES.save(storage, &liabilities)?;
+
+    // the calendar-day limit, rolled over to today as it would have been by the next bond
+    let (cumulated_amount, bonded_today) = if env.block.time.seconds() / DEFAULT_WINDOW_SECONDS != config.last_timestamp / DEFAULT_WINDOW_SECONDS {
+        (config.cumulated_amount + config.daily_vesting_amount.saturating_sub(config.daily_current_bond_amount), Uint128::zero())
+    } else {
+        (config.cumulated_amount, config.daily_current_bond_amount)
+    };
+    BOND_CAPACITY.save(storage, &BondCapacity {
+        window_seconds: DEFAULT_WINDOW_SECONDS,
+        max_carry_over,
+        in_usdc: false,
+        usdc_window_amount: Uint128::zero(),
+        remaining: (cfg.daily_vesting_amount + cumulated_amount)
+            .saturating_sub(bonded_today)
+            .min(cfg.daily_vesting_amount + max_carry_over),
+        last_refill: env.block.time.seconds()
+    })?;
+    Ok(())
 }
 
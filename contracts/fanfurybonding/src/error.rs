@@ -1,12 +1,17 @@
-use cosmwasm_std::{StdError};
+use cosmwasm_std::{StdError, Uint128};
 use hex::FromHexError;
 use thiserror::Error;
 
+use fanfury_common::CommonError;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error(transparent)]
+    Common(#[from] CommonError),
+
     #[error("{0}")]
     Hex(#[from] FromHexError),
 
@@ -34,6 +39,9 @@ pub enum ContractError {
     #[error("InsufficientFury")]
     InsufficientFury{},
 
+    #[error("Bond liabilities of {liabilities} exceed the FURY balance of {balance}")]
+    Insolvent { balance: Uint128, liabilities: Uint128 },
+
     #[error("MaxBondingExceed")]
     MaxBondingExceed{},
 
@@ -43,6 +51,18 @@ pub enum ContractError {
     #[error("Already started shorting")]
     AlreadyStarted {},
 
+    #[error("Invalid discount: {discount}")]
+    InvalidDiscount { discount: u64 },
+
+    #[error("Unknown bond market: {market_id}")]
+    UnknownBondMarket { market_id: u64 },
+
+    #[error("Bond market {market_id} is not open")]
+    BondMarketClosed { market_id: u64 },
+
+    #[error("Bond market {market_id} capacity exceeded")]
+    BondMarketCapacityExceeded { market_id: u64 },
+
     #[error("Not Allowed Bonding Typ")]
     NotAllowedBondingType {},
 
@@ -52,9 +72,6 @@ pub enum ContractError {
     #[error("Map2List failed")]
     Map2ListFailed {},
 
-    #[error("Cannot migrate from different contract type: {previous_contract}")]
-    CannotMigrate { previous_contract: String },
-
     #[error("Count {count}")]
     Count { count: u64 },
 
@@ -64,6 +81,6 @@ pub enum ContractError {
     #[error("Amount of the cw20 coin inputed is zero")]
     Cw20InputZero {},
 
-    #[error("Token type mismatch")]
-    TokenTypeMismatch {},
+    #[error("No migration from version {previous_version} with this message")]
+    UnsupportedMigration { previous_version: String },
 }
@@ -1,358 +1,167 @@
 #![cfg(test)]
 
-use std::borrow::BorrowMut;
-
-use cosmwasm_std::{coins, Addr, Coin, Empty, Uint128};
-use cw20::Expiration;
-
-use crate::{error::ContractError};
-use cw20::{Cw20Coin, Cw20Contract, Cw20ExecuteMsg, Denom};
-use cw_multi_test::{App, Contract, ContractWrapper, Executor};
-use fanfuryswap;
-
-use crate::msg::{ExecuteMsg, ConfigResponse, InstantiateMsg, QueryMsg, BondStateResponse};
-
-fn mock_app() -> App {
-    App::default()
-}
-
-pub fn contract_bonding() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(
-        crate::contract::execute,
-        crate::contract::instantiate,
-        crate::contract::query,
-    );
-    
-    Box::new(contract)
-}
-
-pub fn contract_amm() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(
-        fanfuryswap::contract::execute,
-        fanfuryswap::contract::instantiate,
-        fanfuryswap::contract::query,
-    ).with_reply(fanfuryswap::contract::reply);
-    Box::new(contract)
-}
-
-pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(
-        cw20_base::contract::execute,
-        cw20_base::contract::instantiate,
-        cw20_base::contract::query,
-    );
-    Box::new(contract)
-}
-
-fn get_config(router: &App, contract_addr: &Addr) -> ConfigResponse {
-    router
-        .wrap()
-        .query_wasm_smart(contract_addr, &QueryMsg::Config {})
-        .unwrap()
-}
-
-fn get_pool_config(router: &App, contract_addr: &Addr) -> fanfuryswap::msg::ConfigResponse {
-    router
-        .wrap()
-        .query_wasm_smart(contract_addr, &QueryMsg::Config {})
-        .unwrap()
-}
-
-
-
-fn get_pool_address(router: &App, contract_addr:&Addr) -> Addr {
-    get_config(router, contract_addr).pool_address
-}
-
-fn get_bonding_info(router: &App, contract_addr: &Addr, user: &Addr) -> BondStateResponse {
-    router
-        .wrap()
-        .query_wasm_smart(contract_addr, &QueryMsg::BondState { address: user.clone() })
-        .unwrap()
-}
-
-fn get_pair_bonding_contract(router: &App, contract_addr: &Addr, user: &Addr) -> Addr {
-    let res : fanfuryswap::msg::ConfigResponse= router
-    .wrap()
-    .query_wasm_smart(contract_addr, &fanfuryswap::msg::QueryMsg::Config {  })
-    .unwrap();
-
-    res.bonding_contract_address
-}
-
-fn create_amm(router: &mut App, owner: &Addr, cash: &Cw20Contract, native_denom: String) -> Addr {
-    // set up amm contract
-    let cw20_id = router.store_code(contract_cw20());
-    let amm_id = router.store_code(contract_amm());
-    let bonding_id = router.store_code(contract_bonding());
-    let msg = fanfuryswap::msg::InstantiateMsg {
-
-        lp_token_code_id: cw20_id,
-        bonding_code_id: bonding_id,
-        owner: owner.clone(),
-        treasury_address: owner.clone(),
-        fury_token_address: cash.addr(),
-        usdc_denom: native_denom,
-        lock_seconds: 7u64,
-        discount: 5u64,
-        tx_fee: 3u64,
-        platform_fee: 10u64,
-        daily_vesting_amount: Uint128::from(10000000000u128)
-    };
-    router
-        .instantiate_contract(amm_id, owner.clone(), &msg, &[], "amm", None)
-        .unwrap()
-}
-
-fn create_native_bonding(router: &mut App, owner: &Addr, pool_address: &Addr, cash: &Cw20Contract, native_denom: String) -> Addr {
-    // set up bond contract
-    let bonding_id = router.store_code(contract_bonding());
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{Addr, DepsMut, Env, Uint128};
+
+use crate::contract::{
+    execute, get_remaining_capacity, instantiate, migrate, take_bond_debt, CONTRACT_NAME, CONTRACT_VERSION,
+};
+use crate::error::ContractError;
+use crate::msg::{BondingRecord, ExecuteMsg, InstantiateMsg, MigrateMsg};
+use crate::state::{
+    BondCapacity, ConfigV1, BONDING, BOND_CAPACITY, BOND_MARKETS, CONFIG, CONFIG_V1, DEFAULT_MARKET_ID, TOTAL_LIABILITIES,
+};
+
+fn instantiate_bonding(deps: DepsMut, env: &Env) {
     let msg = InstantiateMsg {
-        owner: owner.clone(),
-        pool_address: pool_address.clone(),
-        treasury_address: owner.clone(),
-        fury_token_address: cash.addr(),
-        usdc_denom: native_denom,
-
-        lock_seconds: 5u64,
-        discount: 7u64,
-        tx_fee: 3u64,
-        platform_fee: 10u64,
-        daily_vesting_amount: Uint128::from(10000000000u128),
-        is_native_bonding: true
+        owner: Addr::unchecked("owner"),
+        pool_address: Addr::unchecked("pool"),
+        treasury_address: Addr::unchecked("owner"),
+        fury_token_address: Addr::unchecked("fury"),
+        usdc_denom: "usdc".to_string(),
+        lock_seconds: 100,
+        discount: 100,
+        tx_fee: 3,
+        platform_fee: 10,
+        daily_vesting_amount: Uint128::new(1000),
+        is_native_bonding: false,
     };
-    router
-        .instantiate_contract(bonding_id, owner.clone(), &msg, &[], "nativebond", None)
-        .unwrap()
+    instantiate(deps, env.clone(), mock_info("pool", &[]), msg).unwrap();
 }
 
-
-// CreateCW20 create new cw20 with given initial balance belonging to owner
-fn create_cw20(
-    router: &mut App,
-    owner: &Addr,
-    name: String,
-    symbol: String,
-    balance: Uint128,
-) -> Cw20Contract {
-    // set up cw20 contract with some tokens
-    let cw20_id = router.store_code(contract_cw20());
-    let msg = cw20_base::msg::InstantiateMsg {
-        name,
-        symbol,
-        decimals: 2,
-        initial_balances: vec![Cw20Coin {
-            address: owner.to_string(),
-            amount: balance,
-        }],
-        mint: None,
-        marketing: None,
+#[test]
+fn take_bond_debt_prices_each_market() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    instantiate_bonding(deps.as_mut(), &env);
+
+    let create_market_msg = ExecuteMsg::CreateBondMarket {
+        lock_seconds: 100,
+        discount: 100,
+        capacity: Some(Uint128::new(2100)),
+        open_time: Some(now + 10),
+        close_time: Some(now + 1000),
     };
-    let addr = router
-        .instantiate_contract(cw20_id, owner.clone(), &msg, &[], "CASH", None)
-        .unwrap();
-    Cw20Contract(addr)
-}
-
-fn bank_balance(router: &mut App, addr: &Addr, denom: String) -> Coin {
-    router
-        .wrap()
-        .query_balance(addr.to_string(), denom)
-        .unwrap()
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), create_market_msg).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), ExecuteMsg::UpdateBondPricing {
+        market_id: Some(1),
+        control_variable: 50,
+        min_discount: 20,
+        max_discount: None,
+        decay_seconds: 100,
+    }).unwrap();
+    let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+
+    let err = take_bond_debt(deps.as_mut().storage, &cfg, 1, now, Uint128::new(900)).unwrap_err();
+    assert_eq!(ContractError::BondMarketClosed { market_id: 1 }, err);
+
+    // without debt the market sells at its full discount
+    let open = now + 10;
+    let (receiving_amount, _, discount) = take_bond_debt(deps.as_mut().storage, &cfg, 1, open, Uint128::new(900)).unwrap();
+    assert_eq!((receiving_amount, discount), (Uint128::new(1000), 100));
+
+    // a debt of one daily vesting amount takes the control variable off the discount
+    let (receiving_amount, market, discount) = take_bond_debt(deps.as_mut().storage, &cfg, 1, open, Uint128::new(950)).unwrap();
+    assert_eq!((receiving_amount, discount), (Uint128::new(1000), 50));
+    assert_eq!(market.pricing.total_debt, Uint128::new(2000));
+    assert_eq!(market.sold_amount, Uint128::new(2000));
+
+    // the debt has decayed after decay_seconds, the market capacity has not
+    let (receiving_amount, _, discount) = take_bond_debt(deps.as_mut().storage, &cfg, 1, open + 100, Uint128::new(90)).unwrap();
+    assert_eq!((receiving_amount, discount), (Uint128::new(100), 100));
+    let err = take_bond_debt(deps.as_mut().storage, &cfg, 1, open + 100, Uint128::new(9)).unwrap_err();
+    assert_eq!(ContractError::BondMarketCapacityExceeded { market_id: 1 }, err);
+
+    let err = take_bond_debt(deps.as_mut().storage, &cfg, 1, now + 1000, Uint128::new(9)).unwrap_err();
+    assert_eq!(ContractError::BondMarketClosed { market_id: 1 }, err);
+
+    // the default market keeps its own debt
+    let default_market = BOND_MARKETS.load(deps.as_ref().storage, DEFAULT_MARKET_ID).unwrap();
+    assert_eq!(default_market.pricing.total_debt, Uint128::zero());
+    assert_eq!(default_market.sold_amount, Uint128::zero());
 }
 
 #[test]
-// receive cw20 tokens and release upon approval
-fn test_instantiate() {
-    let mut router = mock_app();
-
-    const NATIVE_TOKEN_DENOM: &str = "usdc";
-
-    let owner = Addr::unchecked("owner");
-    let funds = coins(2000000, NATIVE_TOKEN_DENOM);
-    router.borrow_mut().init_modules(|router, _, storage| {
-        router.bank.init_balance(storage, &owner, funds).unwrap()
-    });
-
-    let cw20_token = create_cw20(
-        &mut router,
-        &owner,
-        "fury".to_string(),
-        "FURY".to_string(),
-        Uint128::new(2000000),
-    );
-
-    let amm_addr = create_amm(&mut router, &owner, &cw20_token, NATIVE_TOKEN_DENOM.into());
-    let native_bonding_addr = create_native_bonding(&mut router, &owner, &amm_addr, &cw20_token, NATIVE_TOKEN_DENOM.into());
-
-    assert_ne!(cw20_token.addr(), amm_addr);
-
-    let info = get_config(&router, &native_bonding_addr);
-    println!("{:?}", info);
-    assert_eq!(info.pool_address, "contract1".to_string())
-}
-#[test]
-fn lp_bonding() {
-    let mut router = mock_app();
-
-    const NATIVE_TOKEN_DENOM: &str = "usdc";
-
-    let owner = Addr::unchecked("owner");
-    let bonder = Addr::unchecked("bonder");
-    let funds = coins(200000, NATIVE_TOKEN_DENOM);
-
-    router.borrow_mut().init_modules(|router, _, storage| {
-        router.bank.init_balance(storage, &bonder, funds).unwrap()
-    });
-
-    let token = create_cw20(
-        &mut router,
-        &bonder,
-        "fury".to_string(),
-        "FURY".to_string(),
-        Uint128::new(500000),
-    );
-
-    let amm = create_amm(&mut router, &owner, &token, NATIVE_TOKEN_DENOM.to_string());
-
-    // Add initial liquidity to pools
-    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
-        spender: amm.to_string(),
-        amount: Uint128::new(100000),
-        expires: None,
+fn remaining_capacity_carries_over_up_to_the_limit() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    instantiate_bonding(deps.as_mut(), &env);
+    let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+
+    let mut capacity = BondCapacity {
+        window_seconds: 100,
+        max_carry_over: Uint128::new(500),
+        in_usdc: false,
+        usdc_window_amount: Uint128::zero(),
+        remaining: Uint128::new(200),
+        last_refill: 1000,
     };
-    let _res = router
-        .execute_contract(bonder.clone(), token.addr(), &allowance_msg, &[])
-        .unwrap();
-
-    let add_liquidity_msg = fanfuryswap::msg::ExecuteMsg::AddLiquidity {
-        token1_amount: Uint128::new(100000),
-        min_liquidity: Uint128::new(100000),
-        max_token2: Uint128::new(100000),
-        fee_amount: Uint128::new(2600),
-        expiration: None,
-    };
-    let res = router
-        .execute_contract(
-            bonder.clone(),
-            amm.clone(),
-            &add_liquidity_msg,
-            &[Coin {
-                denom: NATIVE_TOKEN_DENOM.into(),
-                amount: Uint128::new(102600),
-            }],
-        )
-        .unwrap();
-    
-    // ensure balances updated
-    let token1_balance = token.balance::<_,_,Empty>(&router, bonder.clone()).unwrap();
-    assert_eq!(token1_balance, Uint128::new(400000));
-
-    
-    // check pool contract info
-    let res:fanfuryswap::msg::ConfigResponse = get_pool_config(&router, &amm);
-    
-    let bonding_contract = res.bonding_contract_address;
+    assert_eq!(get_remaining_capacity(&cfg, &capacity, 900), Uint128::new(200));
+    assert_eq!(get_remaining_capacity(&cfg, &capacity, 1000), Uint128::new(200));
+    assert_eq!(get_remaining_capacity(&cfg, &capacity, 1050), Uint128::new(700));
+    assert_eq!(get_remaining_capacity(&cfg, &capacity, 1100), Uint128::new(1200));
+    // one window plus the carry-over limit
+    assert_eq!(get_remaining_capacity(&cfg, &capacity, 1200), Uint128::new(1500));
 
-    // println!("{:?}", res);
-
-
-    // Check bonding record
-    let record = get_bonding_info(&router, &bonding_contract, &bonder);
-    // println!("{:?}", record);
-
-    // println!("{:?}", router.block_info());
-    assert_eq!(record.list[0].amount, Uint128::new(201005));
-    
+    // in usdc the window refills with the usdc amount
+    capacity.in_usdc = true;
+    capacity.usdc_window_amount = Uint128::new(300);
+    assert_eq!(get_remaining_capacity(&cfg, &capacity, 1100), Uint128::new(500));
+    assert_eq!(get_remaining_capacity(&cfg, &capacity, 1300), Uint128::new(800));
 }
 
-
 #[test]
-fn native_bonding() {
-    let mut router = mock_app();
-
-    const NATIVE_TOKEN_DENOM: &str = "usdc";
-
-    let owner = Addr::unchecked("owner");
-    let bonder = Addr::unchecked("bonder");
-    let funds = coins(200000, NATIVE_TOKEN_DENOM);
-
-    router.borrow_mut().init_modules(|router, _, storage| {
-        router.bank.init_balance(storage, &bonder, funds).unwrap()
-    });
-
-    let token = create_cw20(
-        &mut router,
-        &bonder,
-        "fury".to_string(),
-        "FURY".to_string(),
-        Uint128::new(500000),
-    );
-
-    let amm = create_amm(&mut router, &owner, &token, NATIVE_TOKEN_DENOM.to_string());
-    
-    // Add initial liquidity to pools
-    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
-        spender: amm.to_string(),
-        amount: Uint128::new(100000),
-        expires: None,
-    };
-    let _res = router
-        .execute_contract(bonder.clone(), token.addr(), &allowance_msg, &[])
+fn migrate_from_v1() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "1.0.0").unwrap();
+    CONFIG_V1
+        .save(deps.as_mut().storage, &ConfigV1 {
+            owner: Addr::unchecked("owner"),
+            pool_address: Addr::unchecked("pool"),
+            treasury_address: Addr::unchecked("owner"),
+            fury_token_address: Addr::unchecked("fury"),
+            lock_seconds: 7,
+            discount: 5,
+            usdc_denom: "usdc".to_string(),
+            is_native_bonding: false,
+            tx_fee: 3,
+            platform_fee: 10,
+            enabled: true,
+            daily_vesting_amount: Uint128::new(1000),
+            cumulated_amount: Uint128::new(300),
+            daily_current_bond_amount: Uint128::new(200),
+            last_timestamp: env.block.time.seconds(),
+        })
         .unwrap();
-
-    let add_liquidity_msg = fanfuryswap::msg::ExecuteMsg::AddLiquidity {
-        token1_amount: Uint128::new(100000),
-        min_liquidity: Uint128::new(100000),
-        max_token2: Uint128::new(100000),
-        fee_amount: Uint128::new(2600),
-        expiration: None,
-    };
-    let res = router
-        .execute_contract(
-            bonder.clone(),
-            amm.clone(),
-            &add_liquidity_msg,
-            &[Coin {
-                denom: NATIVE_TOKEN_DENOM.into(),
-                amount: Uint128::new(102600),
-            }],
-        )
+    BONDING
+        .save(deps.as_mut().storage, Addr::unchecked("bonder"), &vec![BondingRecord {
+            amount: Uint128::new(500),
+            timestamp: env.block.time.seconds() + 7,
+            start: 0,
+            claimed: Uint128::zero(),
+            market_id: 0,
+        }])
         .unwrap();
-    
-    // ensure balances updated
-    let token1_balance = token.balance::<_,_,Empty>(&router, bonder.clone()).unwrap();
-    assert_eq!(token1_balance, Uint128::new(400000));
-
-    
-    // check pool contract info
-    let res:fanfuryswap::msg::ConfigResponse = get_pool_config(&router, &amm);
-
-    //make native bonding
-    let bonding_contract = create_native_bonding(&mut router, &owner,&amm, &token, NATIVE_TOKEN_DENOM.to_string());
-    // println!("{:?}", res);
-
-    let bond_msg = ExecuteMsg::Bond { amount: Uint128::new(10000) };
-    let res = router
-        .execute_contract(
-            bonder.clone(),
-            bonding_contract.clone(),
-            &bond_msg,
-            &[Coin {
-                denom: NATIVE_TOKEN_DENOM.into(),
-                amount: Uint128::new(10130),
-            }],
-        )
-        .unwrap();
-
-    // println!("{:?}", res);
-    // Check bonding record
-    let record = get_bonding_info(&router, &bonding_contract, &bonder);
-    println!("{:?}", record);
-
-    assert_eq!(record.list[0].amount, Uint128::new(9129));
 
-    // check the fee
-    
-    
+    // the calendar-day limit needs the carry-over limit of the rolling window
+    let err = migrate(deps.as_mut(), env.clone(), MigrateMsg::Upgrade {}).unwrap_err();
+    assert_eq!(ContractError::UnsupportedMigration { previous_version: "1.0.0".to_string() }, err);
+    migrate(deps.as_mut(), env.clone(), MigrateMsg::FromV1 { max_carry_over: Uint128::new(50) }).unwrap();
+
+    // what is left of today's limit is kept, up to one window plus the carry-over limit
+    let storage = deps.as_ref().storage;
+    let capacity = BOND_CAPACITY.load(storage).unwrap();
+    assert_eq!(capacity.max_carry_over, Uint128::new(50));
+    assert_eq!(capacity.remaining, Uint128::new(1050));
+    assert_eq!(TOTAL_LIABILITIES.load(storage).unwrap(), Uint128::new(500));
+    assert_eq!(BOND_MARKETS.load(storage, DEFAULT_MARKET_ID).unwrap().discount, 5);
+    let config = CONFIG.load(storage).unwrap();
+    assert_eq!(config.daily_vesting_amount, Uint128::new(1000));
+    assert!(config.enabled);
+
+    migrate(deps.as_mut(), env.clone(), MigrateMsg::Upgrade {}).unwrap();
+    let err = migrate(deps.as_mut(), env, MigrateMsg::FromV1 { max_carry_over: Uint128::zero() }).unwrap_err();
+    assert_eq!(ContractError::UnsupportedMigration { previous_version: CONTRACT_VERSION.to_string() }, err);
 }
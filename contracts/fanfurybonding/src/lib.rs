@@ -2,7 +2,6 @@ pub mod contract;
 mod error;
 pub mod msg;
 pub mod state;
-pub mod util;
 pub use crate::error::ContractError;
 
-// mod integration_test;
+mod integration_test;
@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use cw20::{Cw20ReceiveMsg, Denom};
 use cosmwasm_std::{Uint128, Addr};
 
+pub use fanfury_common::access::{AccessMsg, OwnershipResponse, Role, RoleMembersResponse};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: Addr,
@@ -27,12 +29,12 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    UpdateOwner {
-        owner: Addr
-    },
+    /// Only callable by the owner, or by a pauser to disable
     UpdateEnabled {
         enabled: bool
     },
+    /// Callable by the owner or the pool. A fee manager may only change `tx_fee` and `platform_fee`.
+    /// `lock_seconds` and `discount` are the terms of the default market, `discount` being offered while there is no bond debt.
     UpdateConfig {
         treasury_address: Addr,
         lock_seconds: u64,
@@ -41,18 +43,62 @@ pub enum ExecuteMsg {
         platform_fee: u64,
         daily_vesting_amount: Uint128
     },
+    /// Bonds in the market, the default one if not set
     Bond {
+        market_id: Option<u64>,
         amount: Uint128
     }, // For native bonding, 
     LpBond {
+        market_id: Option<u64>,
         address: Addr,
         amount: Uint128 // Only callable by pool
     },
+    /// Pays the vested and not yet claimed part of every bond of the sender
     Unbond {
     },
+    /// Only callable by the owner, and only out of the FURY not owed to bonders
     Withdraw {
         amount: Uint128
-    }
+    },
+    /// Sets the rolling capacity window, refilled with `daily_vesting_amount` FURY or, if `in_usdc`, with
+    /// `usdc_window_amount`. `max_carry_over` is in the same denomination. Switching `in_usdc` resets the capacity
+    /// to one window. Only callable by the owner.
+    UpdateCapacity {
+        window_seconds: u64,
+        max_carry_over: Uint128,
+        in_usdc: bool,
+        usdc_window_amount: Uint128,
+    },
+    /// Opens a new market next to the default one. Only callable by the owner.
+    CreateBondMarket {
+        lock_seconds: u64,
+        discount: u64,
+        capacity: Option<Uint128>,
+        /// Now if not set
+        open_time: Option<u64>,
+        close_time: Option<u64>,
+    },
+    /// Replaces the terms of a market. Only callable by the owner.
+    UpdateBondMarket {
+        market_id: u64,
+        lock_seconds: u64,
+        discount: u64,
+        capacity: Option<Uint128>,
+        open_time: u64,
+        close_time: Option<u64>,
+        enabled: bool,
+    },
+    /// Sets the control-variable pricing of the market, the default one if not set. `max_discount` caps the discount of the
+    /// market, leaving its terms as they are. Only callable by the owner.
+    UpdateBondPricing {
+        market_id: Option<u64>,
+        control_variable: u64,
+        min_discount: u64,
+        max_discount: Option<u64>,
+        decay_seconds: u64,
+    },
+    /// Ownership transfer and role management
+    Access(AccessMsg),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -65,8 +111,30 @@ pub enum QueryMsg {
     AllBondState {
         start_after: Option<String>,
         limit: Option<u32>,
-    }
-    
+    },
+    /// Discount and price offered to the next bond in the market, the default one if not set
+    BondPrice {
+        market_id: Option<u64>,
+    },
+    BondMarket {
+        market_id: u64,
+    },
+    BondMarkets {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// FURY held against the FURY owed to bonders
+    Solvency {},
+    /// Bond capacity left in the rolling window
+    RemainingCapacity {},
+    /// Owner and the pending ownership offer
+    Ownership {},
+    /// Addresses granted the role, the owner not included
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -84,28 +152,105 @@ pub struct ConfigResponse {
     pub platform_fee: u64,
     pub enabled: bool,
     pub daily_vesting_amount: Uint128,
-    pub cumulated_amount: Uint128,
-    pub daily_current_bond_amount: Uint128,
-    pub last_timestamp: u64
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RemainingCapacityResponse {
+    /// Left to sell now, in usdc if `in_usdc` and FURY otherwise
+    pub remaining: Uint128,
+    /// Refilled over each window, in the denomination of `remaining`
+    pub window_amount: Uint128,
+    pub window_seconds: u64,
+    pub max_carry_over: Uint128,
+    pub in_usdc: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SolvencyResponse {
+    pub balance: Uint128,
+    pub liabilities: Uint128,
+    /// FURY the owner may withdraw or sell in new bonds
+    pub surplus: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BondMarketResponse {
+    pub market_id: u64,
+    pub lock_seconds: u64,
+    pub discount: u64,
+    pub capacity: Option<Uint128>,
+    pub sold_amount: Uint128,
+    pub open_time: u64,
+    pub close_time: Option<u64>,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BondMarketsResponse {
+    pub markets: Vec<BondMarketResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BondPriceResponse {
+    pub market_id: u64,
+    /// Permille off the market price
+    pub discount: u64,
+    pub control_variable: u64,
+    pub min_discount: u64,
+    pub max_discount: u64,
+    pub decay_seconds: u64,
+    /// Outstanding debt of the market after decay
+    pub total_debt: Uint128,
+    /// Permille of the total debt against the daily vesting amount
+    pub debt_ratio: Uint128,
+    /// usdc for one FURY on the pool
+    pub market_price: Uint128,
+    /// usdc for one FURY through a bond
+    pub bond_price: Uint128,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    /// Records the new contract version, for releases keeping the stored state as it is
+    Upgrade {},
+    /// Moves the 1.0 release to bond markets and the rolling capacity window. What is left of its calendar-day
+    /// limit stays available, up to one window plus `max_carry_over`, which is kept as the carry-over limit.
+    FromV1 {
+        max_carry_over: Uint128,
+    },
+}
 
 
+/// FURY of one bond, vesting linearly from `start` until it is fully unlocked at `timestamp`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BondingRecord {
     pub amount: Uint128,
-    pub timestamp: u64
+    /// Maturity of the bond
+    pub timestamp: u64,
+    /// Creation of the bond. Zero for the bonds made before linear vesting, which unlock at maturity only.
+    #[serde(default)]
+    pub start: u64,
+    /// Part of `amount` already paid by `Unbond`
+    #[serde(default)]
+    pub claimed: Uint128,
+    /// Market the bond was sold in
+    #[serde(default)]
+    pub market_id: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct BondStateResponse {
     pub address: Addr,
     pub list: Vec<BondingRecord>,
+    /// Vested but not yet claimed, paid by the next `Unbond`
     pub unbond_amount: Uint128,
-    pub fee_amount: Uint128
+    /// Fee in usdc to send along with the next `Unbond`
+    pub fee_amount: Uint128,
+    /// Vested so far, claimed or not
+    pub vested_amount: Uint128,
+    /// Still vesting
+    pub locked_amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
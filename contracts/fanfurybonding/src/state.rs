@@ -8,6 +8,27 @@ use cw20::Denom;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
+    pub owner: Addr,
+    pub pool_address: Addr,
+    pub treasury_address: Addr,
+    pub fury_token_address: Addr,
+    pub lock_seconds: u64,
+    pub discount: u64,
+    pub usdc_denom: String,
+    pub is_native_bonding: bool,
+    pub tx_fee: u64,
+    pub platform_fee: u64,
+    pub enabled: bool,
+    /// Bond capacity refilled over each capacity window
+    pub daily_vesting_amount: Uint128,
+}
+
+pub const CONFIG_KEY: &str = "config";
+pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
+
+/// Config of the 1.0 release and its calendar-day limit, only read by its migration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV1 {
     pub owner: Addr,
     pub pool_address: Addr,
     pub treasury_address: Addr,
@@ -23,11 +44,66 @@ pub struct Config {
     pub cumulated_amount: Uint128,
     pub daily_current_bond_amount: Uint128,
     pub last_timestamp: u64
+}
+
+pub const CONFIG_V1: Item<ConfigV1> = Item::new(CONFIG_KEY);
 
+/// Control-variable pricing of one market. The discount starts at the market discount and shrinks as the debt of the market grows.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondPricing {
+    /// Permille of discount taken off for each daily vesting amount of outstanding debt
+    pub control_variable: u64,
+    pub min_discount: u64,
+    /// Cap on the market discounts, none if not set
+    pub max_discount: Option<u64>,
+    /// Seconds over which the debt of a bond decays to zero
+    pub decay_seconds: u64,
+    pub total_debt: Uint128,
+    pub last_decay: u64,
 }
 
-pub const CONFIG_KEY: &str = "config";
-pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
+/// Terms of one bond market. The default market follows `Config::lock_seconds` and `Config::discount`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondMarket {
+    pub lock_seconds: u64,
+    /// Maximum discount, shrunk by the control-variable pricing
+    pub discount: u64,
+    /// FURY the market may sell over its life, unlimited if not set
+    pub capacity: Option<Uint128>,
+    pub sold_amount: Uint128,
+    pub open_time: u64,
+    pub close_time: Option<u64>,
+    pub enabled: bool,
+    pub pricing: BondPricing,
+}
+
+pub const DEFAULT_MARKET_ID: u64 = 0;
+
+pub const BOND_MARKETS_KEY: &str = "bond_markets";
+pub const BOND_MARKETS: Map<u64, BondMarket> = Map::new(BOND_MARKETS_KEY);
+
+pub const NEXT_MARKET_ID_KEY: &str = "next_market_id";
+pub const NEXT_MARKET_ID: Item<u64> = Item::new(NEXT_MARKET_ID_KEY);
+
+/// Rolling capacity. `remaining` refills continuously at `Config::daily_vesting_amount` FURY, or `usdc_window_amount`
+/// if `in_usdc`, per window, up to one window plus `max_carry_over` of unused capacity.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondCapacity {
+    pub window_seconds: u64,
+    pub max_carry_over: Uint128,
+    /// Capacity counted in usdc paid instead of FURY sold
+    pub in_usdc: bool,
+    pub usdc_window_amount: Uint128,
+    pub remaining: Uint128,
+    pub last_refill: u64,
+}
+
+pub const BOND_CAPACITY_KEY: &str = "bond_capacity";
+pub const BOND_CAPACITY: Item<BondCapacity> = Item::new(BOND_CAPACITY_KEY);
+
+/// FURY owed to bonders, vested or not, and not yet paid by `Unbond`
+pub const TOTAL_LIABILITIES_KEY: &str = "total_liabilities";
+pub const TOTAL_LIABILITIES: Item<Uint128> = Item::new(TOTAL_LIABILITIES_KEY);
 
 pub const BONDING_KEY: &str = "bonding";
 pub const BONDING: Map<Addr, Vec<BondingRecord>> = Map::new(BONDING_KEY);
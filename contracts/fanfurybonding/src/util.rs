@@ -1 +0,0 @@
-../../../util/util.rs
\ No newline at end of file
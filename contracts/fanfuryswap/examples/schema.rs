@@ -4,7 +4,7 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 use cosmwasm_std::Coin;
 
-use fanfuryswap::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, InfoResponse, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse};
+use fanfuryswap::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, InfoResponse, PairsResponse, ReceiveMsg, FlashLoanReceiverMsg, DepositResponse, TwapResponse, ObservationsResponse, ProtocolFeesResponse, ReserveBalancesResponse, DailyStatsResponse, AmpResponse, FarmResponse, FarmStakerResponse, OrderResponse, OrdersResponse, OwnershipResponse, RoleMembersResponse, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse, Token1ForExactToken2Response, Token2ForExactToken1Response, SimulateSwapResponse};
 
 fn main() {
   let mut out_dir = current_dir().unwrap();
@@ -15,8 +15,27 @@ fn main() {
   export_schema(&schema_for!(InstantiateMsg), &out_dir);
   export_schema(&schema_for!(ExecuteMsg), &out_dir);
   export_schema(&schema_for!(QueryMsg), &out_dir);
+  export_schema(&schema_for!(ReceiveMsg), &out_dir);
+  export_schema(&schema_for!(FlashLoanReceiverMsg), &out_dir);
   export_schema(&schema_for!(InfoResponse), &out_dir);
   export_schema(&schema_for!(ConfigResponse), &out_dir);
   export_schema(&schema_for!(Token1ForToken2PriceResponse), &out_dir);
   export_schema(&schema_for!(Token2ForToken1PriceResponse), &out_dir);
+  export_schema(&schema_for!(Token1ForExactToken2Response), &out_dir);
+  export_schema(&schema_for!(Token2ForExactToken1Response), &out_dir);
+  export_schema(&schema_for!(SimulateSwapResponse), &out_dir);
+  export_schema(&schema_for!(PairsResponse), &out_dir);
+  export_schema(&schema_for!(DepositResponse), &out_dir);
+  export_schema(&schema_for!(TwapResponse), &out_dir);
+  export_schema(&schema_for!(ObservationsResponse), &out_dir);
+  export_schema(&schema_for!(ProtocolFeesResponse), &out_dir);
+  export_schema(&schema_for!(ReserveBalancesResponse), &out_dir);
+  export_schema(&schema_for!(DailyStatsResponse), &out_dir);
+  export_schema(&schema_for!(AmpResponse), &out_dir);
+  export_schema(&schema_for!(FarmResponse), &out_dir);
+  export_schema(&schema_for!(FarmStakerResponse), &out_dir);
+  export_schema(&schema_for!(OrderResponse), &out_dir);
+  export_schema(&schema_for!(OrdersResponse), &out_dir);
+  export_schema(&schema_for!(OwnershipResponse), &out_dir);
+  export_schema(&schema_for!(RoleMembersResponse), &out_dir);
 }
@@ -1,22 +1,38 @@
 use cosmwasm_std::{
     attr, entry_point, to_binary, Addr, Binary, BlockInfo, Coin, CosmosMsg, Deps, DepsMut, Env,
-    MessageInfo, Reply, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg, BankMsg, from_binary, ReplyOn
+    MessageInfo, Reply, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg, BankMsg, from_binary, ReplyOn,
+    Order, Storage, Decimal256, Uint256, Empty
 };
-use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Bound;
+use cw2::set_contract_version;
 use cw20::Cw20ReceiveMsg;
 use cw0::parse_reply_instantiate_data;
 use cw20::Denom::Cw20;
 use cw20::{Cw20ExecuteMsg, Denom, Expiration, MinterResponse};
 use cw20_base::contract::query_balance;
+use std::convert::TryFrom;
 
 use crate::error::ContractError;
 use crate::msg::{
     ExecuteMsg, InfoResponse, InstantiateMsg, QueryMsg, Token1ForToken2PriceResponse,
-    Token2ForToken1PriceResponse, TokenSelect, StakeReceiveMsg, ConfigResponse, MigrateMsg
+    Token2ForToken1PriceResponse, Token1ForExactToken2Response, Token2ForExactToken1Response, TokenSelect, ReceiveMsg, ConfigResponse, MigrateMsg,
+    PairResponse, PairsResponse, DepositResponse, TwapResponse, ObservationResponse, ObservationsResponse,
+    ProtocolFeesResponse, SimulateSwapResponse, SwapOperation, FlashLoanReceiverMsg, ReserveBalancesResponse,
+    DailyStatsResponse, DayStatsResponse, Curve, AmpResponse, FarmResponse, FarmStakerResponse, OrderResponse,
+    OrdersResponse, Role
 };
-use crate::state::{Token, LP_TOKEN, TOKEN1, TOKEN2, Config, CONFIG};
-use crate::util::{NORMAL_DECIMAL, THOUSAND};
-use crate::util;
+use crate::state::{
+    Token, LP_TOKEN, TOKEN1, TOKEN2, Config, CONFIG, DEFAULT_PAIR_ID, NEXT_PAIR_ID, PAIR_BONDING,
+    PendingPair, PENDING_PAIR, LIQUIDITY_DEPOSITS, PriceCumulative, PRICE_CUMULATIVE, OBSERVATIONS,
+    OBSERVATION_INTERVAL, OBSERVATION_RETENTION, PROTOCOL_FEES, FlashLoan, FLASH_LOAN, Donations, DONATIONS,
+    DailyStats, DAILY_STATS, SECONDS_PER_DAY, PAIR_FEE_TOKEN, AmpRamp, STABLE_AMP,
+    Farm, FARMS, FarmStaker, FARM_STAKERS, LimitOrder, LIMIT_ORDERS, OWNER_ORDERS, NEXT_ORDER_ID, CONFIG_V1, LP_TOKEN_V1,
+    TOKEN1_V1, TOKEN2_V1
+};
+use crate::stableswap::{self, MAX_AMP, MAX_AMP_CHANGE, MIN_RAMP_SECONDS};
+use fanfury_common::access::{self, ROLES};
+use fanfury_common::migrate::check_migration_version;
+use fanfury_common::token::{self, THOUSAND};
 
 // Version info for migration info
 pub const CONTRACT_NAME: &str = "fanfuryswap";
@@ -24,6 +40,13 @@ pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const INSTANTIATE_LP_TOKEN_REPLY_ID: u64 = 0;
 const INSTANTIATE_BONDING_ID:u64 = 1;
+const FLASH_LOAN_REPLY_ID: u64 = 2;
+const DEFAULT_LP_FEE: u64 = 3;
+const DEFAULT_FLASH_LOAN_FEE: u64 = 1;
+const DEFAULT_ORDER_BOUNTY: u64 = 1;
+const MAX_BPS: u64 = 10000;
+/// First release keeping the pairs by id, older ones are migrated with `MigrateMsg::FromV1`
+const MULTI_PAIR_VERSION: &str = "1.1.0";
 use fanfurybonding::msg::{InstantiateMsg as BondingInstantiateMsg, ExecuteMsg as BondingExecuteMsg};
 
 
@@ -38,8 +61,16 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    if let Some(lp_fee) = msg.lp_fee {
+        validate_lp_fee(lp_fee)?;
+    }
+    if let Some(max_price_impact_bps) = msg.max_price_impact_bps {
+        validate_price_impact_cap(max_price_impact_bps)?;
+    }
+
     let config = Config {
         owner: msg.owner.clone(),
+        lp_token_code_id: msg.lp_token_code_id,
         bonding_code_id: msg.bonding_code_id,
         bonding_contract_address: msg.owner.clone(),
         fury_token_address: msg.fury_token_address.clone(),
@@ -47,49 +78,81 @@ pub fn instantiate(
         usdc_denom: msg.usdc_denom.clone(),
         tx_fee: msg.tx_fee,
         platform_fee: msg.platform_fee,
+        lp_fee: msg.lp_fee.unwrap_or(DEFAULT_LP_FEE),
+        fee_in_kind: msg.fee_in_kind.unwrap_or(false),
+        enabled: true,
+        max_price_impact_bps: msg.max_price_impact_bps.unwrap_or(MAX_BPS),
+        flash_loan_fee: DEFAULT_FLASH_LOAN_FEE,
+        order_bounty: DEFAULT_ORDER_BOUNTY,
         lock_seconds: msg.lock_seconds,
         discount: msg.discount,
         daily_vesting_amount: msg.daily_vesting_amount
     };
     CONFIG.save(deps.storage, &config)?;
 
-    let token1 = Token {
-        reserve: Uint128::zero(),
-        denom: Denom::Native(msg.usdc_denom.clone()),
-    };
+    let reply_msg = register_pair(
+        deps.storage,
+        &env,
+        &config,
+        DEFAULT_PAIR_ID,
+        Denom::Native(msg.usdc_denom.clone()),
+        Denom::Cw20(msg.fury_token_address),
+        true,
+    )?;
+    NEXT_PAIR_ID.save(deps.storage, &(DEFAULT_PAIR_ID + 1))?;
+    if let Some(guardian) = msg.guardian {
+        ROLES.save(deps.storage, (Role::Pauser.as_str(), &guardian), &Empty {})?;
+    }
 
-    TOKEN1.save(deps.storage, &token1)?;
+    Ok(Response::new().add_submessage(reply_msg))
+}
 
-    let token2 = Token {
-        denom: Denom::Cw20(msg.fury_token_address),
+/// Stores the empty reserves of a new pair and returns the submessage instantiating its lp token.
+/// The rest of the pair is filled in by `reply`.
+fn register_pair(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    pair_id: u64,
+    token1_denom: Denom,
+    token2_denom: Denom,
+    with_bonding: bool,
+) -> Result<SubMsg, ContractError> {
+    TOKEN1.save(storage, pair_id, &Token {
         reserve: Uint128::zero(),
-    };
-
-    TOKEN2.save(deps.storage, &token2)?;
+        denom: token1_denom,
+    })?;
+    TOKEN2.save(storage, pair_id, &Token {
+        reserve: Uint128::zero(),
+        denom: token2_denom,
+    })?;
+    PENDING_PAIR.save(storage, &PendingPair { pair_id, with_bonding })?;
 
+    let label = if pair_id == DEFAULT_PAIR_ID {
+        "lp_token".to_string()
+    } else {
+        format!("lp_token_{}", pair_id)
+    };
 
     let instantiate_lp_token_msg = WasmMsg::Instantiate {
-        code_id: msg.lp_token_code_id,
+        code_id: cfg.lp_token_code_id,
         funds: vec![],
         admin: None,
-        label: "lp_token".to_string(),
+        label,
         msg: to_binary(&cw20_base::msg::InstantiateMsg {
             name: "FanFurySwap_Liquidity_Token".into(),
             symbol: "ffslpt".into(),
             decimals: 6,
             initial_balances: vec![],
             mint: Some(MinterResponse {
-                minter: env.contract.address.into(),
+                minter: env.contract.address.clone().into(),
                 cap: None,
             }),
             marketing: None,
         })?,
     };
 
-    let reply_msg =
-        SubMsg::reply_on_success(instantiate_lp_token_msg, INSTANTIATE_LP_TOKEN_REPLY_ID);
-
-    Ok(Response::new().add_submessage(reply_msg))
+    Ok(SubMsg::reply_on_success(instantiate_lp_token_msg, INSTANTIATE_LP_TOKEN_REPLY_ID))
 }
 
 // And declare a custom Error variant for the ones where you will want to make use of it
@@ -102,11 +165,59 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner,
             bonding_contract_address,
-            treasury_address
-        } => execute_update_config(info, deps, owner, bonding_contract_address, treasury_address),
+            treasury_address,
+            lp_fee,
+            fee_in_kind,
+            max_price_impact_bps,
+            tx_fee,
+            platform_fee,
+            lock_seconds,
+            discount,
+            daily_vesting_amount,
+            update_bonding,
+            flash_loan_fee,
+            order_bounty,
+        } => execute_update_config(
+            info,
+            deps,
+            bonding_contract_address,
+            treasury_address,
+            lp_fee,
+            fee_in_kind,
+            max_price_impact_bps,
+            tx_fee,
+            platform_fee,
+            lock_seconds,
+            discount,
+            daily_vesting_amount,
+            update_bonding.unwrap_or(false),
+            flash_loan_fee,
+            order_bounty,
+        ),
+        ExecuteMsg::UpdateEnabled { enabled } => execute_update_enabled(deps, info, enabled),
+        ExecuteMsg::CreatePair {
+            token1_denom,
+            token2_denom,
+            with_bonding,
+            fee_token,
+            curve,
+        } => execute_create_pair(
+            deps,
+            env,
+            info,
+            token1_denom,
+            token2_denom,
+            with_bonding,
+            fee_token.unwrap_or(TokenSelect::Token1),
+            curve.unwrap_or(Curve::ConstantProduct {}),
+        ),
+        ExecuteMsg::RampAmp { pair_id, future_amp, future_time } => {
+            execute_ramp_amp(deps, env, info, pair_id, future_amp, future_time)
+        }
+        ExecuteMsg::StopRampAmp { pair_id } => execute_stop_ramp_amp(deps, env, info, pair_id),
         ExecuteMsg::AddLiquidity {
+            pair_id,
             token1_amount,
             min_liquidity,
             max_token2,
@@ -116,72 +227,403 @@ pub fn execute(
             deps,
             &info,
             env,
+            pair_id.unwrap_or(DEFAULT_PAIR_ID),
             min_liquidity,
             token1_amount,
             max_token2,
             fee_amount,
             expiration,
         ),
+        ExecuteMsg::ZapIn {
+            pair_id,
+            input_token,
+            amount,
+            min_liquidity,
+            expiration,
+        } => execute_zap_in(
+            deps,
+            env,
+            &info,
+            pair_id.unwrap_or(DEFAULT_PAIR_ID),
+            input_token,
+            amount,
+            min_liquidity,
+            expiration,
+        ),
+        ExecuteMsg::ZapOut {
+            pair_id,
+            amount,
+            output_token,
+            min_output,
+            expiration,
+        } => execute_zap_out(
+            deps,
+            env,
+            &info,
+            pair_id.unwrap_or(DEFAULT_PAIR_ID),
+            amount,
+            output_token,
+            min_output,
+            expiration,
+        ),
         ExecuteMsg::RemoveLiquidity {
+            pair_id,
+            amount,
+            min_token1,
+            min_token2,
+            expiration,
+        } => execute_remove_liquidity(
+            deps,
+            info,
+            env,
+            pair_id.unwrap_or(DEFAULT_PAIR_ID),
             amount,
             min_token1,
             min_token2,
             expiration,
-        } => execute_remove_liquidity(deps, info, env, amount, min_token1, min_token2, expiration),
+        ),
         ExecuteMsg::Swap {
+            pair_id,
             input_token,
             input_amount,
             min_output,
             fee_amount,
             expiration,
-            ..
-        } => execute_swap(
+            recipient,
+        } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => info.sender.clone(),
+            };
+            execute_swap(
+                deps,
+                &info,
+                input_amount,
+                env,
+                pair_id.unwrap_or(DEFAULT_PAIR_ID),
+                input_token,
+                &recipient,
+                min_output,
+                fee_amount,
+                expiration,
+            )
+        }
+        ExecuteMsg::MultiHopSwap {
+            operations,
+            offer_amount,
+            minimum_receive,
+            recipient,
+            expiration,
+        } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => info.sender.clone(),
+            };
+            execute_multi_hop_swap(deps, env, &info, operations, offer_amount, minimum_receive, &recipient, expiration)
+        }
+        ExecuteMsg::SwapExactOutput {
+            pair_id,
+            input_token,
+            output_amount,
+            max_input,
+            fee_amount,
+            expiration,
+        } => execute_swap_exact_output(
             deps,
             &info,
-            input_amount,
             env,
+            pair_id.unwrap_or(DEFAULT_PAIR_ID),
             input_token,
-            &info.sender,
-            min_output,
+            output_amount,
+            max_input,
             fee_amount,
             expiration,
         ),
         ExecuteMsg::AddToken {
+            pair_id,
             input_token,
             amount
         } => execute_add_token(
             deps,
             env,
             &info,
+            pair_id.unwrap_or(DEFAULT_PAIR_ID),
             input_token,
             amount
-        )
-        
+        ),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::WithdrawDeposit { pair_id } => {
+            execute_withdraw_deposit(deps, info, pair_id.unwrap_or(DEFAULT_PAIR_ID))
+        }
+        ExecuteMsg::FlashLoan {
+            pair_id,
+            token,
+            amount,
+            callback_msg,
+        } => execute_flash_loan(deps, env, info, pair_id.unwrap_or(DEFAULT_PAIR_ID), token, amount, callback_msg),
+        ExecuteMsg::CollectProtocolFees { pair_id } => {
+            execute_collect_protocol_fees(deps, info, pair_id.unwrap_or(DEFAULT_PAIR_ID))
+        }
+        ExecuteMsg::Sync { pair_id } => execute_sync(deps, env, info, pair_id.unwrap_or(DEFAULT_PAIR_ID)),
+        ExecuteMsg::Skim { pair_id, recipient } => {
+            execute_skim(deps, env, info, pair_id.unwrap_or(DEFAULT_PAIR_ID), recipient)
+        }
+        ExecuteMsg::SetFarmRewardRate { pair_id, reward_rate } => {
+            execute_set_farm_reward_rate(deps, env, info, pair_id.unwrap_or(DEFAULT_PAIR_ID), reward_rate)
+        }
+        ExecuteMsg::UnstakeLp { pair_id, amount } => {
+            execute_unstake_lp(deps, env, info, pair_id.unwrap_or(DEFAULT_PAIR_ID), amount)
+        }
+        ExecuteMsg::ClaimFarmRewards { pair_id } => {
+            execute_claim_farm_rewards(deps, env, info, pair_id.unwrap_or(DEFAULT_PAIR_ID))
+        }
+        ExecuteMsg::PlaceOrder {
+            pair_id,
+            input_token,
+            input_amount,
+            min_output,
+        } => execute_place_order(
+            deps,
+            env,
+            info,
+            pair_id.unwrap_or(DEFAULT_PAIR_ID),
+            input_token,
+            input_amount,
+            min_output,
+        ),
+        ExecuteMsg::CancelOrder { order_id } => execute_cancel_order(deps, info, order_id),
+        ExecuteMsg::ExecuteOrders { start_after, limit } => execute_execute_orders(deps, env, info, start_after, limit),
+        ExecuteMsg::Access(msg) => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(access::execute_access_msg(deps, &env, &info, &cfg.owner, msg, |storage, new_owner| {
+                CONFIG.update(storage, |mut cfg| -> StdResult<_> {
+                    cfg.owner = new_owner.clone();
+                    Ok(cfg)
+                })?;
+                Ok(())
+            })?)
+        }
+    }
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let msg: ReceiveMsg = from_binary(&wrapper.msg)?;
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    match msg {
+        ReceiveMsg::Swap {
+            pair_id,
+            min_output,
+            expiration,
+            recipient,
+        } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => sender,
+            };
+            execute_receive_swap(
+                deps,
+                env,
+                &info,
+                pair_id.unwrap_or(DEFAULT_PAIR_ID),
+                &recipient,
+                wrapper.amount,
+                min_output,
+                expiration,
+            )
+        }
+        ReceiveMsg::ZapIn {
+            pair_id,
+            min_liquidity,
+            expiration,
+        } => {
+            let pair_id = pair_id.unwrap_or(DEFAULT_PAIR_ID);
+            check_expiration(&expiration, &env.block)?;
+            let input_token = get_received_token(deps.storage, pair_id, &info.sender)?;
+            zap_in(deps, env, &sender, pair_id, input_token, wrapper.amount, min_liquidity, vec![])
+        }
+        ReceiveMsg::MultiHopSwap {
+            operations,
+            minimum_receive,
+            recipient,
+            expiration,
+        } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => sender,
+            };
+            execute_receive_multi_hop_swap(
+                deps,
+                env,
+                &info,
+                operations,
+                wrapper.amount,
+                minimum_receive,
+                &recipient,
+                expiration,
+            )
+        }
+        ReceiveMsg::AddLiquidity { pair_id } => {
+            execute_deposit_liquidity(deps, &info, pair_id.unwrap_or(DEFAULT_PAIR_ID), &sender, wrapper.amount)
+        }
+        ReceiveMsg::Fund { pair_id } => {
+            execute_fund(deps, env, &info, pair_id.unwrap_or(DEFAULT_PAIR_ID), &sender, wrapper.amount)
+        }
+        ReceiveMsg::StakeLp { pair_id } => {
+            execute_stake_lp(deps, env, &info, pair_id.unwrap_or(DEFAULT_PAIR_ID), &sender, wrapper.amount)
+        }
+        ReceiveMsg::FundFarm { pair_id } => {
+            execute_fund_farm(deps, env, &info, pair_id.unwrap_or(DEFAULT_PAIR_ID), &sender, wrapper.amount)
+        }
+        ReceiveMsg::PlaceOrder { pair_id, min_output } => {
+            let pair_id = pair_id.unwrap_or(DEFAULT_PAIR_ID);
+            let input_token = get_received_token(deps.storage, pair_id, &info.sender)?;
+            place_order(deps, &sender, pair_id, input_token, wrapper.amount, min_output, vec![])
+        }
+    }
+}
+
+/// Side of the pair whose cw20 called the receive hook
+fn get_received_token(
+    storage: &dyn Storage,
+    pair_id: u64,
+    token_addr: &Addr,
+) -> Result<TokenSelect, ContractError> {
+    if TOKEN1.load(storage, pair_id)?.denom == Denom::Cw20(token_addr.clone()) {
+        return Ok(TokenSelect::Token1);
+    }
+    check_received_token2(storage, pair_id, token_addr)?;
+    Ok(TokenSelect::Token2)
+}
+
+/// Checks that the cw20 calling the receive hook is the token2 of the pair
+fn check_received_token2(
+    storage: &dyn Storage,
+    pair_id: u64,
+    token_addr: &Addr,
+) -> Result<(), ContractError> {
+    match TOKEN2.load(storage, pair_id)?.denom {
+        Denom::Cw20(addr) if addr == *token_addr => Ok(()),
+        Denom::Cw20(addr) => Err(ContractError::InvalidToken {
+            received: token_addr.clone(),
+            expected: addr,
+        }),
+        Denom::Native(_) => Err(ContractError::TokenTypeMismatch {}),
     }
 }
 
 
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_config(
     info: MessageInfo,
     deps: DepsMut,
-    owner: Addr,
     bonding_contract_address: Addr,
-    treasury_address: Addr
+    treasury_address: Addr,
+    lp_fee: Option<u64>,
+    fee_in_kind: Option<bool>,
+    max_price_impact_bps: Option<u64>,
+    tx_fee: Option<u64>,
+    platform_fee: Option<u64>,
+    lock_seconds: Option<u64>,
+    discount: Option<u64>,
+    daily_vesting_amount: Option<Uint128>,
+    update_bonding: bool,
+    flash_loan_fee: Option<u64>,
+    order_bounty: Option<u64>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage)?;
 
-    if info.sender.clone() != config.owner {
+    access::check_role(deps.storage, &config.owner, &info.sender, Role::FeeManager)?;
+    // a fee manager only changes the fees, the addresses and the bonding terms stay with the owner
+    if info.sender != config.owner
+        && (bonding_contract_address != config.bonding_contract_address
+            || treasury_address != config.treasury_address
+            || lock_seconds.is_some()
+            || discount.is_some()
+            || daily_vesting_amount.is_some())
+    {
         return Err(ContractError::Unauthorized {});
-    };
-    
-    config.owner = owner;
+    }
+
     config.bonding_contract_address = bonding_contract_address;
     config.treasury_address = treasury_address;
+    if let Some(lp_fee) = lp_fee {
+        validate_lp_fee(lp_fee)?;
+        config.lp_fee = lp_fee;
+    }
+    if let Some(fee_in_kind) = fee_in_kind {
+        config.fee_in_kind = fee_in_kind;
+    }
+    if let Some(max_price_impact_bps) = max_price_impact_bps {
+        validate_price_impact_cap(max_price_impact_bps)?;
+        config.max_price_impact_bps = max_price_impact_bps;
+    }
+    if let Some(tx_fee) = tx_fee {
+        config.tx_fee = tx_fee;
+    }
+    if let Some(platform_fee) = platform_fee {
+        config.platform_fee = platform_fee;
+    }
+    // the liquidity fee is twice the platform and tx fee
+    if (config.tx_fee + config.platform_fee) * 2 >= THOUSAND {
+        return Err(ContractError::InvalidFee { fee: config.tx_fee + config.platform_fee });
+    }
+    if let Some(lock_seconds) = lock_seconds {
+        config.lock_seconds = lock_seconds;
+    }
+    if let Some(discount) = discount {
+        if discount >= THOUSAND {
+            return Err(ContractError::InvalidDiscount { discount });
+        }
+        config.discount = discount;
+    }
+    if let Some(daily_vesting_amount) = daily_vesting_amount {
+        config.daily_vesting_amount = daily_vesting_amount;
+    }
+    if let Some(flash_loan_fee) = flash_loan_fee {
+        if flash_loan_fee >= THOUSAND {
+            return Err(ContractError::InvalidFee { fee: flash_loan_fee });
+        }
+        config.flash_loan_fee = flash_loan_fee;
+    }
+    if let Some(order_bounty) = order_bounty {
+        if order_bounty >= THOUSAND {
+            return Err(ContractError::InvalidFee { fee: order_bounty });
+        }
+        config.order_bounty = order_bounty;
+    }
 
     CONFIG.save(deps.storage, &config)?;
 
+    let mut bonding_msgs: Vec<CosmosMsg> = vec![];
+    if update_bonding {
+        let mut bonding_addresses = vec![config.bonding_contract_address.clone()];
+        for item in PAIR_BONDING.range(deps.storage, None, None, Order::Ascending) {
+            bonding_addresses.push(item?.1);
+        }
+        for bonding_address in bonding_addresses {
+            bonding_msgs.push(WasmMsg::Execute {
+                contract_addr: bonding_address.into(),
+                msg: to_binary(&BondingExecuteMsg::UpdateConfig {
+                    treasury_address: config.treasury_address.clone(),
+                    lock_seconds: config.lock_seconds,
+                    discount: config.discount,
+                    tx_fee: config.tx_fee,
+                    platform_fee: config.platform_fee,
+                    daily_vesting_amount: config.daily_vesting_amount,
+                })?,
+                funds: vec![],
+            }.into());
+        }
+    }
+
     Ok(Response::new()
+        .add_messages(bonding_msgs)
         .add_attribute("action", "update_config")
         .add_attribute(
             "owner",
@@ -194,574 +636,2494 @@ pub fn execute_update_config(
             config
                 .bonding_code_id
                 .to_string(),
-        ))
+        )
+        .add_attribute("lp_fee", config.lp_fee.to_string())
+        .add_attribute("fee_in_kind", config.fee_in_kind.to_string())
+        .add_attribute("max_price_impact_bps", config.max_price_impact_bps.to_string())
+        .add_attribute("tx_fee", config.tx_fee.to_string())
+        .add_attribute("platform_fee", config.platform_fee.to_string())
+        .add_attribute("lock_seconds", config.lock_seconds.to_string())
+        .add_attribute("discount", config.discount.to_string())
+        .add_attribute("daily_vesting_amount", config.daily_vesting_amount)
+        .add_attribute("update_bonding", update_bonding.to_string())
+        .add_attribute("flash_loan_fee", config.flash_loan_fee.to_string())
+        .add_attribute("order_bounty", config.order_bounty.to_string()))
 }
 
-fn check_expiration(
-    expiration: &Option<Expiration>,
-    block: &BlockInfo,
-) -> Result<(), ContractError> {
-    match expiration {
-        Some(e) => {
-            if e.is_expired(block) {
-                return Err(ContractError::MsgExpirationError {});
-            }
-            Ok(())
-        }
-        None => Ok(()),
+pub fn execute_update_enabled(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    access::check_update_enabled(deps.storage, &config.owner, &info.sender, enabled)?;
+
+    config.enabled = enabled;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_enabled"),
+        attr("enabled", enabled.to_string()),
+    ]))
+}
+
+/// Also keeps flash loan receivers from using the pool before the loan is settled
+fn check_enabled(storage: &dyn Storage) -> Result<(), ContractError> {
+    access::check_enabled(CONFIG.load(storage)?.enabled)?;
+    check_no_flash_loan(storage)
+}
+
+fn check_no_flash_loan(storage: &dyn Storage) -> Result<(), ContractError> {
+    if FLASH_LOAN.may_load(storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
     }
+    Ok(())
 }
 
-fn get_lp_token_amount_to_mint(
-    token1_amount: Uint128,
-    liquidity_supply: Uint128,
-    token1_reserve: Uint128,
-) -> Result<Uint128, ContractError> {
-    if liquidity_supply == Uint128::zero() {
-        Ok(token1_amount)
-    } else {
-        Ok(token1_amount
-            .checked_mul(liquidity_supply)
-            .map_err(StdError::overflow)?
-            .checked_div(token1_reserve)
-            .map_err(StdError::divide_by_zero)?)
+fn validate_price_impact_cap(max_price_impact_bps: u64) -> Result<(), ContractError> {
+    if max_price_impact_bps == 0 || max_price_impact_bps > MAX_BPS {
+        return Err(ContractError::InvalidPriceImpactCap { max_price_impact_bps });
     }
+    Ok(())
 }
 
-fn get_token2_amount_required(
-    max_token: Uint128,
-    token1_amount: Uint128,
-    liquidity_supply: Uint128,
-    token2_reserve: Uint128,
-    token1_reserve: Uint128,
-) -> Result<Uint128, StdError> {
-    if liquidity_supply == Uint128::zero() {
-        Ok(max_token)
-    } else {
-        Ok(token1_amount
-            .checked_mul(token2_reserve)
-            .map_err(StdError::overflow)?
-            .checked_div(token1_reserve)
-            .map_err(StdError::divide_by_zero)?
-            .checked_add(Uint128::new(1))
-            .map_err(StdError::overflow)?)
+fn check_price_impact(cfg: &Config, price_impact_bps: u64) -> Result<(), ContractError> {
+    if price_impact_bps > cfg.max_price_impact_bps {
+        return Err(ContractError::PriceImpactTooHigh {
+            price_impact_bps,
+            max_price_impact_bps: cfg.max_price_impact_bps,
+        });
     }
+    Ok(())
 }
 
-pub fn execute_add_liquidity(
-    deps: DepsMut,
-    info: &MessageInfo,
-    env: Env,
-    min_liquidity: Uint128,
-    token1_amount: Uint128,
-    max_token2: Uint128,
-    fee_amount: Uint128,
-    expiration: Option<Expiration>,
-) -> Result<Response, ContractError> {
-    check_expiration(&expiration, &env.block)?;
+/// Basis points the output price of the input token drops by when `input_amount` is added
+/// to the input reserve and `output_amount` taken from the output reserve
+fn get_price_impact_bps(
+    curve: &Curve,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    input_amount: Uint128,
+    output_amount: Uint128,
+) -> StdResult<u64> {
+    if let Curve::Stable { amp } = curve {
+        return stableswap::get_price_impact_bps(*amp, input_reserve, output_reserve, input_amount, output_amount, MAX_BPS);
+    }
+    // after / before = (output_after * input_before) / (input_after * output_before)
+    let price_ratio = Uint256::from(output_reserve - output_amount) * Uint256::from(input_reserve) * Uint256::from(MAX_BPS)
+        / (Uint256::from(input_reserve + input_amount) * Uint256::from(output_reserve));
+    Ok(MAX_BPS - Uint128::try_from(price_ratio)?.u128() as u64)
+}
 
-    let token1 = TOKEN1.load(deps.storage)?;
-    let token2 = TOKEN2.load(deps.storage)?;
-    let lp_token_addr = LP_TOKEN.load(deps.storage)?;
+fn validate_lp_fee(lp_fee: u64) -> Result<(), ContractError> {
+    if lp_fee >= THOUSAND {
+        return Err(ContractError::InvalidFee { fee: lp_fee });
+    }
+    Ok(())
+}
 
-    // validate funds
-    validate_input_amount(&info.funds, token1_amount + fee_amount, &token1.denom)?;
-    validate_input_amount(&info.funds, max_token2, &token2.denom)?;
+/// Platform and tx fee due on the given amount
+fn get_protocol_fee(cfg: &Config, amount: Uint128) -> Uint128 {
+    amount * Uint128::from(cfg.platform_fee + cfg.tx_fee) / Uint128::from(THOUSAND)
+}
 
-    let lp_token_supply = get_lp_token_supply(deps.as_ref(), &lp_token_addr)?;
-    let liquidity_amount =
-        get_lp_token_amount_to_mint(token1_amount, lp_token_supply, token1.reserve)?;
+/// Part of a swap input kept as protocol fee when fees are taken in kind
+fn get_in_kind_fee(cfg: &Config, input_amount: Uint128) -> Uint128 {
+    if cfg.fee_in_kind {
+        get_protocol_fee(cfg, input_amount)
+    } else {
+        Uint128::zero()
+    }
+}
 
-    let token2_amount = get_token2_amount_required(
-        max_token2,
-        token1_amount,
-        lp_token_supply,
-        token2.reserve,
-        token1.reserve,
-    )?;
+fn accrue_protocol_fee(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    pair_id: u64,
+    token: &TokenSelect,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let mut fees = PROTOCOL_FEES.may_load(storage, pair_id)?.unwrap_or_default();
+    match token {
+        TokenSelect::Token1 => fees.token1_amount += amount,
+        TokenSelect::Token2 => fees.token2_amount += amount,
+    }
+    PROTOCOL_FEES.save(storage, pair_id, &fees)?;
+    record_daily_stats(storage, block, pair_id, |stats| add_treasury_fee(stats, token, amount))
+}
 
-    if liquidity_amount < min_liquidity {
-        return Err(ContractError::MinLiquidityError {
-            min_liquidity,
-            liquidity_available: liquidity_amount,
-        });
+fn add_treasury_fee(stats: &mut DailyStats, token: &TokenSelect, amount: Uint128) {
+    match token {
+        TokenSelect::Token1 => stats.token1_treasury_fee += amount,
+        TokenSelect::Token2 => stats.token2_treasury_fee += amount,
     }
+}
 
-    if token2_amount > max_token2 {
-        return Err(ContractError::MaxTokenError {
-            max_token: max_token2,
-            tokens_required: token2_amount,
-        });
+/// Adds to the pair's bucket for the current day and records its reserves as the closing ones
+fn record_daily_stats(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    pair_id: u64,
+    update: impl FnOnce(&mut DailyStats),
+) -> StdResult<()> {
+    let key = (pair_id, block.time.seconds() / SECONDS_PER_DAY);
+    let mut stats = DAILY_STATS.may_load(storage, key)?.unwrap_or_default();
+    update(&mut stats);
+    stats.token1_reserve = TOKEN1.load(storage, pair_id)?.reserve;
+    stats.token2_reserve = TOKEN2.load(storage, pair_id)?.reserve;
+    DAILY_STATS.save(storage, key, &stats)
+}
+
+fn record_donation(
+    storage: &mut dyn Storage,
+    pair_id: u64,
+    token: &TokenSelect,
+    amount: Uint128,
+) -> StdResult<Donations> {
+    let mut donations = DONATIONS.may_load(storage, pair_id)?.unwrap_or_default();
+    match token {
+        TokenSelect::Token1 => donations.token1_amount += amount,
+        TokenSelect::Token2 => donations.token2_amount += amount,
     }
+    DONATIONS.save(storage, pair_id, &donations)?;
+    Ok(donations)
+}
 
-    // Generate cw20 transfer messages if necessary
-    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
-    if let Cw20(addr) = token1.denom.clone() {
-        transfer_msgs.push(get_cw20_transfer_from_msg(
-            &info.sender,
-            &env.contract.address,
-            &addr,
-            token1_amount,
-        )?)
+pub fn execute_collect_protocol_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    pair_id: u64,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.treasury_address {
+        access::check_role(deps.storage, &cfg.owner, &info.sender, Role::FeeManager)?;
     }
-    if let Cw20(addr) = token2.denom.clone() {
-        transfer_msgs.push(get_cw20_transfer_from_msg(
-            &info.sender,
-            &env.contract.address,
-            &addr,
-            token2_amount,
-        )?)
+
+    let fees = PROTOCOL_FEES.may_load(deps.storage, pair_id)?.unwrap_or_default();
+    PROTOCOL_FEES.remove(deps.storage, pair_id);
+
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !fees.token1_amount.is_zero() {
+        messages.push(token::transfer_token_message(token1.denom, fees.token1_amount, cfg.treasury_address.clone())?);
+    }
+    if !fees.token2_amount.is_zero() {
+        messages.push(token::transfer_token_message(token2.denom, fees.token2_amount, cfg.treasury_address.clone())?);
     }
 
-    // Refund token 2 if is a native token and not all is spent
-    if let Denom::Native(denom) = token2.denom.clone() {
-        if token2_amount < max_token2 {
-            transfer_msgs.push(get_bank_transfer_to_msg(
-                &info.sender,
-                &denom,
-                max_token2 - token2_amount,
-            ))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "collect_protocol_fees"),
+            attr("pair_id", pair_id.to_string()),
+            attr("token1_amount", fees.token1_amount),
+            attr("token2_amount", fees.token2_amount),
+        ]))
+}
+
+/// Amount of `denom` the contract owes to the reserves, protocol fees, liquidity deposits, farms and limit orders
+/// of every pair
+fn get_accounted_amount(storage: &dyn Storage, denom: &Denom) -> StdResult<Uint128> {
+    let mut accounted = Uint128::zero();
+    for item in TOKEN1.range(storage, None, None, Order::Ascending) {
+        let (pair_id, token1) = item?;
+        let token2 = TOKEN2.load(storage, pair_id)?;
+        let fees = PROTOCOL_FEES.may_load(storage, pair_id)?.unwrap_or_default();
+        if &token1.denom == denom {
+            accounted += token1.reserve + fees.token1_amount;
+        }
+        if &token2.denom == denom {
+            accounted += token2.reserve + fees.token2_amount;
         }
     }
+    for item in LIQUIDITY_DEPOSITS.range(storage, None, None, Order::Ascending) {
+        let ((pair_id, _), amount) = item?;
+        if &TOKEN2.load(storage, pair_id)?.denom == denom {
+            accounted += amount;
+        }
+    }
+    let fury_denom = Denom::Cw20(CONFIG.load(storage)?.fury_token_address);
+    for item in FARMS.range(storage, None, None, Order::Ascending) {
+        let (pair_id, farm) = item?;
+        if &fury_denom == denom {
+            accounted += farm.undistributed + farm.unclaimed;
+        }
+        if &Denom::Cw20(LP_TOKEN.load(storage, pair_id)?) == denom {
+            accounted += farm.total_staked;
+        }
+    }
+    for item in LIMIT_ORDERS.range(storage, None, None, Order::Ascending) {
+        let (_, order) = item?;
+        if &load_order_tokens(storage, &order)?.0.denom == denom {
+            accounted += order.input_amount;
+        }
+    }
+    Ok(accounted)
+}
 
-    TOKEN1.update(deps.storage, |mut token1| -> Result<_, ContractError> {
-        token1.reserve += token1_amount;
-        Ok(token1)
-    })?;
-    TOKEN2.update(deps.storage, |mut token2| -> Result<_, ContractError> {
-        token2.reserve += token2_amount;
-        Ok(token2)
-    })?;
+fn get_reserve_balances(deps: Deps, env: &Env, pair_id: u64) -> Result<ReserveBalancesResponse, ContractError> {
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let donations = DONATIONS.may_load(deps.storage, pair_id)?.unwrap_or_default();
 
-    let mut config = CONFIG.load(deps.storage)?;
-    // let mint_msg = mint_lp_tokens(&info.sender, liquidity_amount, &lp_token_addr)?;
-    let mint_msg = mint_lp_tokens(&config.owner, liquidity_amount, &lp_token_addr)?;
+    let token1_balance = token::get_token_amount(deps.querier, token1.denom.clone(), env.contract.address.clone())?;
+    let token2_balance = token::get_token_amount(deps.querier, token2.denom.clone(), env.contract.address.clone())?;
+    let token1_accounted = get_accounted_amount(deps.storage, &token1.denom)?;
+    let token2_accounted = get_accounted_amount(deps.storage, &token2.denom)?;
 
-    // Send Fee
-    // check if the fee is larger than required
-    if fee_amount < token1_amount * Uint128::from(config.platform_fee + config.tx_fee) * Uint128::from(2u128) / Uint128::from(THOUSAND) {
-        return Err(ContractError::InsufficientFee {  })
+    Ok(ReserveBalancesResponse {
+        token1_reserve: token1.reserve,
+        token1_balance,
+        token1_excess: token1_balance.saturating_sub(token1_accounted),
+        token1_donated: donations.token1_amount,
+        token2_reserve: token2.reserve,
+        token2_balance,
+        token2_excess: token2_balance.saturating_sub(token2_accounted),
+        token2_donated: donations.token2_amount,
+    })
+}
+
+pub fn execute_sync(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
     }
-    
-    transfer_msgs.push(util::transfer_token_message(token1.clone().denom.clone(), fee_amount, config.treasury_address.clone())?);
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    /// Bonding Part
-    
-    let mut bond_msgs:Vec<CosmosMsg> = vec![];
-    // Just bond if the info.sender is not owner or treasury( the wallet that contains lp token)
-    if info.sender.clone() != config.owner.clone() && info.sender.clone() != config.treasury_address.clone() {
-    
-        bond_msgs.push(WasmMsg::Execute {
-            contract_addr: config.bonding_contract_address.into(),
-            msg: to_binary(&BondingExecuteMsg::LpBond { 
-                address: info.sender.clone(), 
-                amount: token2_amount * Uint128::from(2u128) 
-            })?,
-            funds: vec![],
-        }.into());
+    check_no_flash_loan(deps.storage)?;
+
+    let mut token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let mut token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let token1_balance = token::get_token_amount(deps.querier, token1.denom.clone(), env.contract.address.clone())?;
+    let token2_balance = token::get_token_amount(deps.querier, token2.denom.clone(), env.contract.address.clone())?;
+    // whatever is not owed elsewhere belongs to this pair's reserve
+    let token1_owed_elsewhere = get_accounted_amount(deps.storage, &token1.denom)? - token1.reserve;
+    let token2_owed_elsewhere = get_accounted_amount(deps.storage, &token2.denom)? - token2.reserve;
+
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
+    token1.reserve = token1_balance.saturating_sub(token1_owed_elsewhere);
+    token2.reserve = token2_balance.saturating_sub(token2_owed_elsewhere);
+    TOKEN1.save(deps.storage, pair_id, &token1)?;
+    TOKEN2.save(deps.storage, pair_id, &token2)?;
+    record_daily_stats(deps.storage, &env.block, pair_id, |_| {})?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "sync"),
+        attr("pair_id", pair_id.to_string()),
+        attr("token1_reserve", token1.reserve),
+        attr("token2_reserve", token2.reserve),
+    ]))
+}
+
+pub fn execute_skim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    check_no_flash_loan(deps.storage)?;
+
+    let balances = get_reserve_balances(deps.as_ref(), &env, pair_id)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !balances.token1_excess.is_zero() {
+        messages.push(token::transfer_token_message(token1.denom, balances.token1_excess, recipient.clone())?);
+    }
+    if !balances.token2_excess.is_zero() {
+        messages.push(token::transfer_token_message(token2.denom, balances.token2_excess, recipient.clone())?);
     }
 
     Ok(Response::new()
-        .add_messages(transfer_msgs)
-        .add_message(mint_msg)
-        .add_messages(bond_msgs)
+        .add_messages(messages)
         .add_attributes(vec![
-            attr("token1_amount", token1_amount),
-            attr("token2_amount", token2_amount),
-            attr("liquidity_received", liquidity_amount),
+            attr("action", "skim"),
+            attr("pair_id", pair_id.to_string()),
+            attr("recipient", recipient.to_string()),
+            attr("token1_amount", balances.token1_excess),
+            attr("token2_amount", balances.token2_excess),
         ]))
 }
 
-fn get_lp_token_supply(deps: Deps, lp_token_addr: &Addr) -> StdResult<Uint128> {
-    let resp: cw20::TokenInfoResponse = deps
-        .querier
-        .query_wasm_smart(lp_token_addr, &cw20_base::msg::QueryMsg::TokenInfo {})?;
-    Ok(resp.total_supply)
-}
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_pair(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token1_denom: Denom,
+    token2_denom: Denom,
+    with_bonding: bool,
+    fee_token: TokenSelect,
+    curve: Curve,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
 
-fn mint_lp_tokens(
-    recipient: &Addr,
-    liquidity_amount: Uint128,
-    lp_token_address: &Addr,
-) -> StdResult<CosmosMsg> {
-    let mint_msg = cw20_base::msg::ExecuteMsg::Mint {
-        recipient: recipient.into(),
-        amount: liquidity_amount,
-    };
-    Ok(WasmMsg::Execute {
-        contract_addr: lp_token_address.to_string(),
-        msg: to_binary(&mint_msg)?,
-        funds: vec![],
+    if token1_denom == token2_denom {
+        return Err(ContractError::IdenticalTokens {});
+    }
+    // the lp bond counts the token2 deposit as FURY
+    if with_bonding && token2_denom != Denom::Cw20(cfg.fury_token_address.clone()) {
+        return Err(ContractError::BondingRequiresFury {});
+    }
+    for item in TOKEN1.range(deps.storage, None, None, Order::Ascending) {
+        let (pair_id, token1) = item?;
+        let token2 = TOKEN2.load(deps.storage, pair_id)?;
+        if token1.denom == token1_denom && token2.denom == token2_denom {
+            return Err(ContractError::PairAlreadyExists { pair_id });
+        }
     }
-    .into())
-}
 
-fn get_token_balance(deps: Deps, contract: &Addr, addr: &Addr) -> StdResult<Uint128> {
-    let resp: cw20::BalanceResponse = deps.querier.query_wasm_smart(
-        contract,
-        &cw20_base::msg::QueryMsg::Balance {
-            address: addr.to_string(),
-        },
+    let pair_id = NEXT_PAIR_ID.load(deps.storage)?;
+    NEXT_PAIR_ID.save(deps.storage, &(pair_id + 1))?;
+    PAIR_FEE_TOKEN.save(deps.storage, pair_id, &fee_token)?;
+    if let Curve::Stable { amp } = curve {
+        validate_amp(amp)?;
+        let now = env.block.time.seconds();
+        STABLE_AMP.save(deps.storage, pair_id, &AmpRamp {
+            initial_amp: amp,
+            initial_time: now,
+            future_amp: amp,
+            future_time: now,
+        })?;
+    }
+
+    let reply_msg = register_pair(
+        deps.storage,
+        &env,
+        &cfg,
+        pair_id,
+        token1_denom,
+        token2_denom,
+        with_bonding,
     )?;
-    Ok(resp.balance)
+
+    Ok(Response::new()
+        .add_submessage(reply_msg)
+        .add_attributes(vec![
+            attr("action", "create_pair"),
+            attr("pair_id", pair_id.to_string()),
+        ]))
 }
 
-fn validate_input_amount(
-    actual_funds: &[Coin],
-    given_amount: Uint128,
-    given_denom: &Denom,
-) -> Result<(), ContractError> {
-    match given_denom {
-        Denom::Cw20(_) => Ok(()),
-        Denom::Native(denom) => {
-            let actual = get_amount_for_denom(actual_funds, denom);
-            if actual.amount != given_amount {
-                return Err(ContractError::InsufficientFunds {});
-            }
-            if &actual.denom != denom {
-                return Err(ContractError::IncorrectNativeDenom {
-                    provided: actual.denom,
-                    required: denom.to_string(),
-                });
-            };
-            Ok(())
-        }
+fn validate_amp(amp: u64) -> Result<(), ContractError> {
+    if amp == 0 || amp > MAX_AMP {
+        return Err(ContractError::InvalidAmp { amp });
     }
+    Ok(())
 }
 
-fn get_cw20_transfer_from_msg(
-    owner: &Addr,
-    recipient: &Addr,
-    token_addr: &Addr,
-    token_amount: Uint128,
-) -> StdResult<CosmosMsg> {
-    // create transfer cw20 msg
-    let transfer_cw20_msg = Cw20ExecuteMsg::TransferFrom {
-        owner: owner.into(),
-        recipient: recipient.into(),
-        amount: token_amount,
-    };
-    let exec_cw20_transfer = WasmMsg::Execute {
-        contract_addr: token_addr.into(),
-        msg: to_binary(&transfer_cw20_msg)?,
-        funds: vec![],
-    };
-    let cw20_transfer_cosmos_msg: CosmosMsg = exec_cw20_transfer.into();
-    Ok(cw20_transfer_cosmos_msg)
+fn load_amp_ramp(storage: &dyn Storage, pair_id: u64) -> Result<AmpRamp, ContractError> {
+    STABLE_AMP
+        .may_load(storage, pair_id)?
+        .ok_or(ContractError::NotStablePair { pair_id })
 }
 
-fn get_cw20_increase_allowance_msg(
-    token_addr: &Addr,
-    spender: &Addr,
-    amount: Uint128,
-    expires: Option<Expiration>,
-) -> StdResult<CosmosMsg> {
-    // create transfer cw20 msg
-    let increase_allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
-        spender: spender.to_string(),
-        amount,
-        expires,
-    };
-    let exec_allowance = WasmMsg::Execute {
-        contract_addr: token_addr.into(),
-        msg: to_binary(&increase_allowance_msg)?,
-        funds: vec![],
-    };
-    Ok(exec_allowance.into())
+pub fn execute_ramp_amp(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+    future_amp: u64,
+    future_time: u64,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let ramp = load_amp_ramp(deps.storage, pair_id)?;
+    validate_amp(future_amp)?;
+
+    let now = env.block.time.seconds();
+    let amp = stableswap::get_amp(&ramp, now);
+    if future_time < now + MIN_RAMP_SECONDS
+        || future_amp > amp * MAX_AMP_CHANGE
+        || future_amp * MAX_AMP_CHANGE < amp
+    {
+        return Err(ContractError::InvalidAmpRamp {});
+    }
+    STABLE_AMP.save(deps.storage, pair_id, &AmpRamp {
+        initial_amp: amp,
+        initial_time: now,
+        future_amp,
+        future_time,
+    })?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "ramp_amp"),
+        attr("pair_id", pair_id.to_string()),
+        attr("initial_amp", amp.to_string()),
+        attr("future_amp", future_amp.to_string()),
+        attr("future_time", future_time.to_string()),
+    ]))
 }
 
-pub fn execute_remove_liquidity(
+pub fn execute_stop_ramp_amp(
     deps: DepsMut,
-    info: MessageInfo,
     env: Env,
-    amount: Uint128,
-    min_token1: Uint128,
-    min_token2: Uint128,
-    expiration: Option<Expiration>,
+    info: MessageInfo,
+    pair_id: u64,
 ) -> Result<Response, ContractError> {
-    check_expiration(&expiration, &env.block)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let ramp = load_amp_ramp(deps.storage, pair_id)?;
+
+    let now = env.block.time.seconds();
+    let amp = stableswap::get_amp(&ramp, now);
+    STABLE_AMP.save(deps.storage, pair_id, &AmpRamp {
+        initial_amp: amp,
+        initial_time: now,
+        future_amp: amp,
+        future_time: now,
+    })?;
 
-    let lp_token_addr = LP_TOKEN.load(deps.storage)?;
-    let balance = get_token_balance(deps.as_ref(), &lp_token_addr, &info.sender)?;
-    let lp_token_supply = get_lp_token_supply(deps.as_ref(), &lp_token_addr)?;
-    let token1 = TOKEN1.load(deps.storage)?;
-    let token2 = TOKEN2.load(deps.storage)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "stop_ramp_amp"),
+        attr("pair_id", pair_id.to_string()),
+        attr("amp", amp.to_string()),
+    ]))
+}
 
-    if amount > balance {
-        return Err(ContractError::InsufficientLiquidityError {
-            requested: amount,
-            available: balance,
-        });
-    }
+/// Curve of the pair with the amplification in effect at `now`
+fn get_curve(storage: &dyn Storage, pair_id: u64, now: u64) -> StdResult<Curve> {
+    Ok(match STABLE_AMP.may_load(storage, pair_id)? {
+        Some(ramp) => Curve::Stable { amp: stableswap::get_amp(&ramp, now) },
+        None => Curve::ConstantProduct {},
+    })
+}
 
-    let token1_amount = amount
-        .checked_mul(token1.reserve)
-        .map_err(StdError::overflow)?
-        .checked_div(lp_token_supply)
-        .map_err(StdError::divide_by_zero)?;
-    if token1_amount < min_token1 {
-        return Err(ContractError::MinToken1Error {
-            requested: min_token1,
-            available: token1_amount,
-        });
+/// Bonding child receiving the lp bonds of the given pair, if it has one
+fn get_pair_bonding_address(
+    storage: &dyn Storage,
+    cfg: &Config,
+    pair_id: u64,
+) -> StdResult<Option<Addr>> {
+    if pair_id == DEFAULT_PAIR_ID {
+        return Ok(Some(cfg.bonding_contract_address.clone()));
     }
+    PAIR_BONDING.may_load(storage, pair_id)
+}
 
-    let token2_amount = amount
-        .checked_mul(token2.reserve)
-        .map_err(StdError::overflow)?
-        .checked_div(lp_token_supply)
-        .map_err(StdError::divide_by_zero)?;
-    if token2_amount < min_token2 {
-        return Err(ContractError::MinToken2Error {
-            requested: min_token2,
-            available: token2_amount,
+/// Prices of the pair accumulated up to `timestamp` with the current reserves
+fn get_price_cumulative(
+    storage: &dyn Storage,
+    pair_id: u64,
+    timestamp: u64,
+) -> StdResult<PriceCumulative> {
+    let last = PRICE_CUMULATIVE.may_load(storage, pair_id)?.unwrap_or(PriceCumulative {
+        token1_price_cumulative: Decimal256::zero(),
+        token2_price_cumulative: Decimal256::zero(),
+        timestamp,
+    });
+    let token1 = TOKEN1.load(storage, pair_id)?;
+    let token2 = TOKEN2.load(storage, pair_id)?;
+
+    let elapsed = timestamp.saturating_sub(last.timestamp);
+    if elapsed == 0 || token1.reserve.is_zero() || token2.reserve.is_zero() {
+        return Ok(PriceCumulative {
+            timestamp: timestamp.max(last.timestamp),
+            ..last
         });
     }
 
-    TOKEN1.update(deps.storage, |mut token1| -> Result<_, ContractError> {
-        token1.reserve = token1
-            .reserve
-            .checked_sub(token1_amount)
-            .map_err(StdError::overflow)?;
-        Ok(token1)
-    })?;
+    let (token1_price_elapsed, token2_price_elapsed) = match get_curve(storage, pair_id, timestamp)? {
+        Curve::ConstantProduct {} => (
+            Decimal256::from_ratio(Uint256::from(token2.reserve) * Uint256::from(elapsed), token1.reserve),
+            Decimal256::from_ratio(Uint256::from(token1.reserve) * Uint256::from(elapsed), token2.reserve),
+        ),
+        Curve::Stable { amp } => {
+            let elapsed = Decimal256::from_ratio(elapsed, 1u64);
+            (
+                stableswap::get_spot_price(amp, token1.reserve, token2.reserve)? * elapsed,
+                stableswap::get_spot_price(amp, token2.reserve, token1.reserve)? * elapsed,
+            )
+        }
+    };
 
-    TOKEN2.update(deps.storage, |mut token2| -> Result<_, ContractError> {
-        token2.reserve = token2
-            .reserve
-            .checked_sub(token2_amount)
-            .map_err(StdError::overflow)?;
-        Ok(token2)
-    })?;
+    Ok(PriceCumulative {
+        token1_price_cumulative: last.token1_price_cumulative + token1_price_elapsed,
+        token2_price_cumulative: last.token2_price_cumulative + token2_price_elapsed,
+        timestamp,
+    })
+}
 
-    let token1_transfer_msg = match token1.denom {
-        Denom::Cw20(addr) => get_cw20_transfer_to_msg(&info.sender, &addr, token1_amount)?,
-        Denom::Native(denom) => get_bank_transfer_to_msg(&info.sender, &denom, token1_amount),
+/// Accumulates the prices in effect since the last update and records an observation.
+/// Must be called before every change of the pair reserves.
+fn update_price_cumulative(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    pair_id: u64,
+) -> StdResult<()> {
+    let timestamp = block.time.seconds();
+    let cumulative = get_price_cumulative(storage, pair_id, timestamp)?;
+    PRICE_CUMULATIVE.save(storage, pair_id, &cumulative)?;
+
+    let last_observation = OBSERVATIONS
+        .prefix(pair_id)
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    if let Some(last_observation) = last_observation {
+        if timestamp < last_observation + OBSERVATION_INTERVAL {
+            return Ok(());
+        }
+    }
+    OBSERVATIONS.save(storage, (pair_id, timestamp), &cumulative)?;
+
+    // Drop at most one expired observation per new one, so the history stays bounded
+    let expired = OBSERVATIONS
+        .prefix(pair_id)
+        .keys(
+            storage,
+            None,
+            Some(Bound::exclusive(timestamp.saturating_sub(OBSERVATION_RETENTION))),
+            Order::Ascending,
+        )
+        .next()
+        .transpose()?;
+    if let Some(expired) = expired {
+        OBSERVATIONS.remove(storage, (pair_id, expired));
+    }
+    Ok(())
+}
+
+fn check_expiration(
+    expiration: &Option<Expiration>,
+    block: &BlockInfo,
+) -> Result<(), ContractError> {
+    match expiration {
+        Some(e) => {
+            if e.is_expired(block) {
+                return Err(ContractError::MsgExpirationError {});
+            }
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+fn get_lp_token_amount_to_mint(
+    token1_amount: Uint128,
+    liquidity_supply: Uint128,
+    token1_reserve: Uint128,
+) -> Result<Uint128, ContractError> {
+    if liquidity_supply == Uint128::zero() {
+        Ok(token1_amount)
+    } else {
+        Ok(token1_amount
+            .checked_mul(liquidity_supply)
+            .map_err(StdError::overflow)?
+            .checked_div(token1_reserve)
+            .map_err(StdError::divide_by_zero)?)
+    }
+}
+
+fn get_token2_amount_required(
+    max_token: Uint128,
+    token1_amount: Uint128,
+    liquidity_supply: Uint128,
+    token2_reserve: Uint128,
+    token1_reserve: Uint128,
+) -> Result<Uint128, StdError> {
+    if liquidity_supply == Uint128::zero() {
+        Ok(max_token)
+    } else {
+        Ok(token1_amount
+            .checked_mul(token2_reserve)
+            .map_err(StdError::overflow)?
+            .checked_div(token1_reserve)
+            .map_err(StdError::divide_by_zero)?
+            .checked_add(Uint128::new(1))
+            .map_err(StdError::overflow)?)
+    }
+}
+
+fn add_liquidity_reserves(
+    storage: &mut dyn Storage,
+    pair_id: u64,
+    token1_amount: Uint128,
+    token2_amount: Uint128,
+) -> Result<(), ContractError> {
+    TOKEN1.update(storage, pair_id, |token1| -> Result<_, ContractError> {
+        let mut token1 = token1.ok_or(ContractError::UnknownPair { pair_id })?;
+        token1.reserve += token1_amount;
+        Ok(token1)
+    })?;
+    TOKEN2.update(storage, pair_id, |token2| -> Result<_, ContractError> {
+        let mut token2 = token2.ok_or(ContractError::UnknownPair { pair_id })?;
+        token2.reserve += token2_amount;
+        Ok(token2)
+    })?;
+    Ok(())
+}
+
+/// Bonds the added liquidity in the pair's bonding child on behalf of the provider
+fn get_lp_bond_msgs(
+    storage: &dyn Storage,
+    config: &Config,
+    pair_id: u64,
+    provider: &Addr,
+    token2_amount: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut bond_msgs:Vec<CosmosMsg> = vec![];
+    let bonding_address = get_pair_bonding_address(storage, config, pair_id)?;
+    // Just bond if the provider is not owner or treasury( the wallet that contains lp token)
+    if let Some(bonding_address) = bonding_address {
+        if provider != &config.owner && provider != &config.treasury_address {
+        
+            bond_msgs.push(WasmMsg::Execute {
+                contract_addr: bonding_address.into(),
+                msg: to_binary(&BondingExecuteMsg::LpBond { 
+                    market_id: None,
+                    address: provider.clone(), 
+                    amount: token2_amount * Uint128::from(2u128) 
+                })?,
+                funds: vec![],
+            }.into());
+        }
+    }
+    Ok(bond_msgs)
+}
+
+pub fn execute_add_liquidity(
+    deps: DepsMut,
+    info: &MessageInfo,
+    env: Env,
+    pair_id: u64,
+    min_liquidity: Uint128,
+    token1_amount: Uint128,
+    max_token2: Uint128,
+    fee_amount: Uint128,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &env.block)?;
+    check_enabled(deps.storage)?;
+
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let lp_token_addr = LP_TOKEN.load(deps.storage, pair_id)?;
+    let fee_token = get_fee_token(deps.storage, pair_id)?;
+    let fee_denom = match fee_token {
+        TokenSelect::Token1 => token1.denom.clone(),
+        TokenSelect::Token2 => token2.denom.clone(),
     };
-    let token2_transfer_msg = match token2.denom {
-        Denom::Cw20(addr) => get_cw20_transfer_to_msg(&info.sender, &addr, token2_amount)?,
-        Denom::Native(denom) => get_bank_transfer_to_msg(&info.sender, &denom, token2_amount),
+
+    // validate funds
+    validate_funds(
+        &info.funds,
+        &[(&token1.denom, token1_amount), (&token2.denom, max_token2), (&fee_denom, fee_amount)],
+    )?;
+
+    // token2 already received through the cw20 hook is used instead of an allowance
+    let deposit = LIQUIDITY_DEPOSITS.may_load(deps.storage, (pair_id, info.sender.clone()))?;
+    let max_token2 = match deposit {
+        Some(deposit) => max_token2.min(deposit),
+        None => max_token2,
     };
-        
-    let mut messages = vec![];
-    messages.push(token1_transfer_msg);
-    messages.push(token2_transfer_msg);
 
-    let lp_token_burn_msg = get_burn_msg(&lp_token_addr, &info.sender, amount)?;
-    messages.push(lp_token_burn_msg);
+    let lp_token_supply = get_lp_token_supply(deps.as_ref(), &lp_token_addr)?;
+    let liquidity_amount =
+        get_lp_token_amount_to_mint(token1_amount, lp_token_supply, token1.reserve)?;
+
+    let token2_amount = get_token2_amount_required(
+        max_token2,
+        token1_amount,
+        lp_token_supply,
+        token2.reserve,
+        token1.reserve,
+    )?;
+
+    if liquidity_amount < min_liquidity {
+        return Err(ContractError::MinLiquidityError {
+            min_liquidity,
+            liquidity_available: liquidity_amount,
+        });
+    }
+
+    if token2_amount > max_token2 {
+        return Err(ContractError::MaxTokenError {
+            max_token: max_token2,
+            tokens_required: token2_amount,
+        });
+    }
+
+    // Generate cw20 transfer messages if necessary
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    if let Cw20(addr) = token1.denom.clone() {
+        transfer_msgs.push(get_cw20_transfer_from_msg(
+            &info.sender,
+            &env.contract.address,
+            &addr,
+            token1_amount,
+        )?)
+    }
+    if let Cw20(addr) = token2.denom.clone() {
+        match deposit {
+            Some(deposit) => {
+                LIQUIDITY_DEPOSITS.remove(deps.storage, (pair_id, info.sender.clone()));
+                if deposit > token2_amount {
+                    transfer_msgs.push(get_cw20_transfer_to_msg(
+                        &info.sender,
+                        &addr,
+                        deposit - token2_amount,
+                    )?)
+                }
+            }
+            None => transfer_msgs.push(get_cw20_transfer_from_msg(
+                &info.sender,
+                &env.contract.address,
+                &addr,
+                token2_amount,
+            )?),
+        }
+    }
+
+    // Refund token 2 if is a native token and not all is spent
+    if let Denom::Native(denom) = token2.denom.clone() {
+        if token2_amount < max_token2 {
+            transfer_msgs.push(get_bank_transfer_to_msg(
+                &info.sender,
+                &denom,
+                max_token2 - token2_amount,
+            ))
+        }
+    }
+
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
+    add_liquidity_reserves(deps.storage, pair_id, token1_amount, token2_amount)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    // let mint_msg = mint_lp_tokens(&info.sender, liquidity_amount, &lp_token_addr)?;
+    let mint_msg = mint_lp_tokens(&config.owner, liquidity_amount, &lp_token_addr)?;
+
+    // Send Fee
+    // check if the fee is larger than required
+    let fee_side_amount = match fee_token {
+        TokenSelect::Token1 => token1_amount,
+        TokenSelect::Token2 => token2_amount,
+    };
+    if fee_amount < fee_side_amount * Uint128::from(config.platform_fee + config.tx_fee) * Uint128::from(2u128) / Uint128::from(THOUSAND) {
+        return Err(ContractError::InsufficientFee {  })
+    }
+    
+    transfer_msgs.extend(get_fee_transfer_msgs(&fee_denom, fee_amount, &info.sender, &config.treasury_address)?);
+    record_daily_stats(deps.storage, &env.block, pair_id, |stats| {
+        stats.lp_minted += liquidity_amount;
+        add_treasury_fee(stats, &fee_token, fee_amount);
+    })?;
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    /// Bonding Part
     
+    let bond_msgs = get_lp_bond_msgs(deps.storage, &config, pair_id, &info.sender, token2_amount)?;
+
     Ok(Response::new()
-    .add_messages(messages)
-    .add_attributes(vec![
-        attr("liquidity_burned", amount),
-        attr("token1_returned", token1_amount),
-        attr("token2_returned", token2_amount),
-    ]))
+        .add_messages(transfer_msgs)
+        .add_message(mint_msg)
+        .add_messages(bond_msgs)
+        .add_attributes(vec![
+            attr("pair_id", pair_id.to_string()),
+            attr("token1_amount", token1_amount),
+            attr("token2_amount", token2_amount),
+            attr("liquidity_received", liquidity_amount),
+        ]))
+}
 
-    
+fn get_lp_token_supply(deps: Deps, lp_token_addr: &Addr) -> StdResult<Uint128> {
+    let resp: cw20::TokenInfoResponse = deps
+        .querier
+        .query_wasm_smart(lp_token_addr, &cw20_base::msg::QueryMsg::TokenInfo {})?;
+    Ok(resp.total_supply)
 }
 
-fn get_burn_msg(contract: &Addr, owner: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
-    let msg = cw20_base::msg::ExecuteMsg::BurnFrom {
-        owner: owner.to_string(),
-        amount,
+fn mint_lp_tokens(
+    recipient: &Addr,
+    liquidity_amount: Uint128,
+    lp_token_address: &Addr,
+) -> StdResult<CosmosMsg> {
+    let mint_msg = cw20_base::msg::ExecuteMsg::Mint {
+        recipient: recipient.into(),
+        amount: liquidity_amount,
     };
     Ok(WasmMsg::Execute {
-        contract_addr: contract.to_string(),
-        msg: to_binary(&msg)?,
+        contract_addr: lp_token_address.to_string(),
+        msg: to_binary(&mint_msg)?,
         funds: vec![],
     }
     .into())
 }
 
-fn get_cw20_transfer_to_msg(
-    recipient: &Addr,
-    token_addr: &Addr,
-    token_amount: Uint128,
-) -> StdResult<CosmosMsg> {
-    // create transfer cw20 msg
-    let transfer_cw20_msg = Cw20ExecuteMsg::Transfer {
-        recipient: recipient.into(),
-        amount: token_amount,
-    };
-    let exec_cw20_transfer = WasmMsg::Execute {
-        contract_addr: token_addr.into(),
-        msg: to_binary(&transfer_cw20_msg)?,
-        funds: vec![],
-    };
-    let cw20_transfer_cosmos_msg: CosmosMsg = exec_cw20_transfer.into();
-    Ok(cw20_transfer_cosmos_msg)
+fn get_token_balance(deps: Deps, contract: &Addr, addr: &Addr) -> StdResult<Uint128> {
+    let resp: cw20::BalanceResponse = deps.querier.query_wasm_smart(
+        contract,
+        &cw20_base::msg::QueryMsg::Balance {
+            address: addr.to_string(),
+        },
+    )?;
+    Ok(resp.balance)
 }
 
-fn get_bank_transfer_to_msg(recipient: &Addr, denom: &str, native_amount: Uint128) -> CosmosMsg {
-    let transfer_bank_msg = BankMsg::Send {
-        to_address: recipient.into(),
-        amount: vec![Coin {
-            denom: denom.to_string(),
-            amount: native_amount,
-        }],
+fn validate_input_amount(
+    actual_funds: &[Coin],
+    given_amount: Uint128,
+    given_denom: &Denom,
+) -> Result<(), ContractError> {
+    match given_denom {
+        Denom::Cw20(_) => Ok(()),
+        Denom::Native(denom) => {
+            let actual = get_amount_for_denom(actual_funds, denom);
+            if actual.amount != given_amount {
+                return Err(ContractError::InsufficientFunds {});
+            }
+            if &actual.denom != denom {
+                return Err(ContractError::IncorrectNativeDenom {
+                    provided: actual.denom,
+                    required: denom.to_string(),
+                });
+            };
+            Ok(())
+        }
+    }
+}
+
+/// Checks the native funds sent match what is owed in each denom.
+/// Cw20 amounts are pulled with allowances instead.
+fn validate_funds(actual_funds: &[Coin], required: &[(&Denom, Uint128)]) -> Result<(), ContractError> {
+    let mut totals: Vec<(&Denom, Uint128)> = vec![];
+    for (denom, amount) in required {
+        match totals.iter_mut().find(|(total_denom, _)| total_denom == denom) {
+            Some((_, total)) => *total += *amount,
+            None => totals.push((denom, *amount)),
+        }
+    }
+    for (denom, amount) in totals {
+        validate_input_amount(actual_funds, amount, denom)?;
+    }
+    Ok(())
+}
+
+fn get_fee_token(storage: &dyn Storage, pair_id: u64) -> StdResult<TokenSelect> {
+    Ok(PAIR_FEE_TOKEN.may_load(storage, pair_id)?.unwrap_or(TokenSelect::Token1))
+}
+
+/// Pays a separately charged fee to the treasury. A native fee came with the funds,
+/// a cw20 fee is pulled from the sender.
+fn get_fee_transfer_msgs(
+    fee_denom: &Denom,
+    fee_amount: Uint128,
+    sender: &Addr,
+    treasury_address: &Addr,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if fee_amount.is_zero() {
+        return Ok(vec![]);
+    }
+    match fee_denom {
+        Denom::Cw20(addr) => Ok(vec![get_cw20_transfer_from_msg(sender, treasury_address, addr, fee_amount)?]),
+        Denom::Native(_) => Ok(vec![token::transfer_token_message(fee_denom.clone(), fee_amount, treasury_address.clone())?]),
+    }
+}
+
+fn get_cw20_transfer_from_msg(
+    owner: &Addr,
+    recipient: &Addr,
+    token_addr: &Addr,
+    token_amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    // create transfer cw20 msg
+    let transfer_cw20_msg = Cw20ExecuteMsg::TransferFrom {
+        owner: owner.into(),
+        recipient: recipient.into(),
+        amount: token_amount,
+    };
+    let exec_cw20_transfer = WasmMsg::Execute {
+        contract_addr: token_addr.into(),
+        msg: to_binary(&transfer_cw20_msg)?,
+        funds: vec![],
+    };
+    let cw20_transfer_cosmos_msg: CosmosMsg = exec_cw20_transfer.into();
+    Ok(cw20_transfer_cosmos_msg)
+}
+
+/// Takes the share of both reserves owned by `amount` lp tokens of the holder out of the pair
+fn withdraw_liquidity(
+    deps: DepsMut,
+    env: &Env,
+    holder: &Addr,
+    pair_id: u64,
+    amount: Uint128,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let lp_token_addr = LP_TOKEN.load(deps.storage, pair_id)?;
+    let balance = get_token_balance(deps.as_ref(), &lp_token_addr, holder)?;
+    let lp_token_supply = get_lp_token_supply(deps.as_ref(), &lp_token_addr)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+
+    if amount > balance {
+        return Err(ContractError::InsufficientLiquidityError {
+            requested: amount,
+            available: balance,
+        });
+    }
+
+    let token1_amount = amount
+        .checked_mul(token1.reserve)
+        .map_err(StdError::overflow)?
+        .checked_div(lp_token_supply)
+        .map_err(StdError::divide_by_zero)?;
+    let token2_amount = amount
+        .checked_mul(token2.reserve)
+        .map_err(StdError::overflow)?
+        .checked_div(lp_token_supply)
+        .map_err(StdError::divide_by_zero)?;
+
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
+    TOKEN1.update(deps.storage, pair_id, |token1| -> Result<_, ContractError> {
+        let mut token1 = token1.ok_or(ContractError::UnknownPair { pair_id })?;
+        token1.reserve = token1
+            .reserve
+            .checked_sub(token1_amount)
+            .map_err(StdError::overflow)?;
+        Ok(token1)
+    })?;
+
+    TOKEN2.update(deps.storage, pair_id, |token2| -> Result<_, ContractError> {
+        let mut token2 = token2.ok_or(ContractError::UnknownPair { pair_id })?;
+        token2.reserve = token2
+            .reserve
+            .checked_sub(token2_amount)
+            .map_err(StdError::overflow)?;
+        Ok(token2)
+    })?;
+    record_daily_stats(deps.storage, &env.block, pair_id, |stats| stats.lp_burned += amount)?;
+
+    Ok((token1_amount, token2_amount))
+}
+
+pub fn execute_remove_liquidity(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    pair_id: u64,
+    amount: Uint128,
+    min_token1: Uint128,
+    min_token2: Uint128,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &env.block)?;
+
+    let lp_token_addr = LP_TOKEN.load(deps.storage, pair_id)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+
+    let (token1_amount, token2_amount) =
+        withdraw_liquidity(deps.branch(), &env, &info.sender, pair_id, amount)?;
+    if token1_amount < min_token1 {
+        return Err(ContractError::MinToken1Error {
+            requested: min_token1,
+            available: token1_amount,
+        });
+    }
+    if token2_amount < min_token2 {
+        return Err(ContractError::MinToken2Error {
+            requested: min_token2,
+            available: token2_amount,
+        });
+    }
+
+    let token1_transfer_msg = match token1.denom {
+        Denom::Cw20(addr) => get_cw20_transfer_to_msg(&info.sender, &addr, token1_amount)?,
+        Denom::Native(denom) => get_bank_transfer_to_msg(&info.sender, &denom, token1_amount),
+    };
+    let token2_transfer_msg = match token2.denom {
+        Denom::Cw20(addr) => get_cw20_transfer_to_msg(&info.sender, &addr, token2_amount)?,
+        Denom::Native(denom) => get_bank_transfer_to_msg(&info.sender, &denom, token2_amount),
+    };
+        
+    let mut messages = vec![];
+    messages.push(token1_transfer_msg);
+    messages.push(token2_transfer_msg);
+
+    let lp_token_burn_msg = get_burn_msg(&lp_token_addr, &info.sender, amount)?;
+    messages.push(lp_token_burn_msg);
+    
+    Ok(Response::new()
+    .add_messages(messages)
+    .add_attributes(vec![
+        attr("pair_id", pair_id.to_string()),
+        attr("liquidity_burned", amount),
+        attr("token1_returned", token1_amount),
+        attr("token2_returned", token2_amount),
+    ]))
+
+    
+}
+
+fn get_burn_msg(contract: &Addr, owner: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    let msg = cw20_base::msg::ExecuteMsg::BurnFrom {
+        owner: owner.to_string(),
+        amount,
+    };
+    Ok(WasmMsg::Execute {
+        contract_addr: contract.to_string(),
+        msg: to_binary(&msg)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+fn get_cw20_transfer_to_msg(
+    recipient: &Addr,
+    token_addr: &Addr,
+    token_amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    // create transfer cw20 msg
+    let transfer_cw20_msg = Cw20ExecuteMsg::Transfer {
+        recipient: recipient.into(),
+        amount: token_amount,
+    };
+    let exec_cw20_transfer = WasmMsg::Execute {
+        contract_addr: token_addr.into(),
+        msg: to_binary(&transfer_cw20_msg)?,
+        funds: vec![],
+    };
+    let cw20_transfer_cosmos_msg: CosmosMsg = exec_cw20_transfer.into();
+    Ok(cw20_transfer_cosmos_msg)
+}
+
+fn get_bank_transfer_to_msg(recipient: &Addr, denom: &str, native_amount: Uint128) -> CosmosMsg {
+    let transfer_bank_msg = BankMsg::Send {
+        to_address: recipient.into(),
+        amount: vec![Coin {
+            denom: denom.to_string(),
+            amount: native_amount,
+        }],
+    };
+
+    let transfer_bank_cosmos_msg: CosmosMsg = transfer_bank_msg.into();
+    transfer_bank_cosmos_msg
+}
+
+fn get_input_price(
+    input_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    lp_fee: u64,
+) -> StdResult<Uint128> {
+    if input_reserve == Uint128::zero() || output_reserve == Uint128::zero() {
+        return Err(StdError::generic_err("No liquidity"));
+    };
+
+    let input_amount_with_fee = input_amount
+        .checked_mul(Uint128::from(THOUSAND - lp_fee))
+        .map_err(StdError::overflow)?;
+    let numerator = input_amount_with_fee
+        .checked_mul(output_reserve)
+        .map_err(StdError::overflow)?;
+    let denominator = input_reserve
+        .checked_mul(Uint128::from(THOUSAND))
+        .map_err(StdError::overflow)?
+        .checked_add(input_amount_with_fee)
+        .map_err(StdError::overflow)?;
+
+    numerator
+        .checked_div(denominator)
+        .map_err(StdError::divide_by_zero)
+}
+
+fn get_amount_for_denom(coins: &[Coin], denom: &str) -> Coin {
+    let amount: Uint128 = coins
+        .iter()
+        .filter(|c| c.denom == denom)
+        .map(|c| c.amount)
+        .sum();
+    Coin {
+        amount,
+        denom: denom.to_string(),
+    }
+}
+
+/// Input needed to buy exactly `output_amount`, rounded up
+fn get_output_price(
+    output_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    lp_fee: u64,
+) -> StdResult<Uint128> {
+    if input_reserve == Uint128::zero() || output_reserve == Uint128::zero() {
+        return Err(StdError::generic_err("No liquidity"));
+    };
+    if output_amount >= output_reserve {
+        return Err(StdError::generic_err("Insufficient liquidity"));
+    }
+
+    let numerator = input_reserve
+        .checked_mul(output_amount)
+        .map_err(StdError::overflow)?
+        .checked_mul(Uint128::from(THOUSAND))
+        .map_err(StdError::overflow)?;
+    let denominator = (output_reserve - output_amount)
+        .checked_mul(Uint128::from(THOUSAND - lp_fee))
+        .map_err(StdError::overflow)?;
+
+    Ok(numerator
+        .checked_div(denominator)
+        .map_err(StdError::divide_by_zero)?
+        + Uint128::new(1))
+}
+
+/// Output of selling `input_amount` along the pair curve
+fn get_curve_input_price(
+    curve: &Curve,
+    input_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    lp_fee: u64,
+) -> StdResult<Uint128> {
+    match curve {
+        Curve::ConstantProduct {} => get_input_price(input_amount, input_reserve, output_reserve, lp_fee),
+        Curve::Stable { amp } => stableswap::get_input_price(*amp, input_amount, input_reserve, output_reserve, lp_fee),
+    }
+}
+
+/// Input needed to buy exactly `output_amount` along the pair curve
+fn get_curve_output_price(
+    curve: &Curve,
+    output_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    lp_fee: u64,
+) -> StdResult<Uint128> {
+    match curve {
+        Curve::ConstantProduct {} => get_output_price(output_amount, input_reserve, output_reserve, lp_fee),
+        Curve::Stable { amp } => stableswap::get_output_price(*amp, output_amount, input_reserve, output_reserve, lp_fee),
+    }
+}
+
+/// Output token per input token for an infinitesimal swap
+fn get_spot_price(curve: &Curve, input_reserve: Uint128, output_reserve: Uint128) -> StdResult<Decimal256> {
+    match curve {
+        Curve::ConstantProduct {} => Ok(Decimal256::from_ratio(output_reserve, input_reserve)),
+        Curve::Stable { amp } => stableswap::get_spot_price(*amp, input_reserve, output_reserve),
+    }
+}
+
+/// Input whose in kind fee leaves at least `swap_amount` to be swapped
+fn get_input_with_in_kind_fee(cfg: &Config, swap_amount: Uint128) -> Uint128 {
+    if !cfg.fee_in_kind {
+        return swap_amount;
+    }
+    let kept = THOUSAND - cfg.platform_fee - cfg.tx_fee;
+    let numerator = swap_amount * Uint128::from(THOUSAND);
+    let input_amount = numerator / Uint128::from(kept);
+    if input_amount * Uint128::from(kept) == numerator {
+        input_amount
+    } else {
+        input_amount + Uint128::new(1)
+    }
+}
+
+/// Output, fees and price impact of selling `input_amount` of `input_token`.
+/// Used by both the swap execution and the `SimulateSwap` query.
+fn simulate_swap(
+    cfg: &Config,
+    curve: &Curve,
+    fee_token: &TokenSelect,
+    input_token_enum: &TokenSelect,
+    input_token: &Token,
+    output_token: &Token,
+    input_amount: Uint128,
+) -> StdResult<SimulateSwapResponse> {
+    let in_kind_fee = get_in_kind_fee(cfg, input_amount);
+    let swap_amount = input_amount - in_kind_fee;
+    let output_amount = get_curve_input_price(curve, swap_amount, input_token.reserve, output_token.reserve, cfg.lp_fee)?;
+
+    let (fee_amount, fee_denom) = if cfg.fee_in_kind {
+        (in_kind_fee, input_token.denom.clone())
+    } else if input_token_enum == fee_token {
+        (get_protocol_fee(cfg, input_amount), input_token.denom.clone())
+    } else {
+        (get_protocol_fee(cfg, output_amount), output_token.denom.clone())
+    };
+
+    let input_reserve_after = input_token.reserve + swap_amount;
+    let output_reserve_after = output_token.reserve - output_amount;
+
+    Ok(SimulateSwapResponse {
+        output_amount,
+        lp_fee_amount: swap_amount * Uint128::from(cfg.lp_fee) / Uint128::from(THOUSAND),
+        fee_amount,
+        fee_denom,
+        fee_in_kind: cfg.fee_in_kind,
+        spot_price_before: get_spot_price(curve, input_token.reserve, output_token.reserve)?,
+        spot_price_after: get_spot_price(curve, input_reserve_after, output_reserve_after)?,
+        price_impact_bps: get_price_impact_bps(curve, input_token.reserve, output_token.reserve, swap_amount, output_amount)?,
+    })
+}
+
+pub fn execute_swap(
+    deps: DepsMut,
+    info: &MessageInfo,
+    input_amount: Uint128,
+    _env: Env,
+    pair_id: u64,
+    input_token_enum: TokenSelect,
+    recipient: &Addr,
+    min_token: Uint128,
+    fee_amount: Uint128,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &_env.block)?;
+    check_enabled(deps.storage)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let input_token_item = match input_token_enum {
+        TokenSelect::Token1 => TOKEN1,
+        TokenSelect::Token2 => TOKEN2,
+    };
+    let input_token = input_token_item.load(deps.storage, pair_id)?;
+    let output_token_item = match input_token_enum {
+        TokenSelect::Token1 => TOKEN2,
+        TokenSelect::Token2 => TOKEN1,
+    };
+    let output_token = output_token_item.load(deps.storage, pair_id)?;
+    let fee_token = get_fee_token(deps.storage, pair_id)?;
+
+    if cfg.fee_in_kind && !fee_amount.is_zero() {
+        return Err(ContractError::FeeTakenInKind {});
+    }
+
+    let curve = get_curve(deps.storage, pair_id, _env.block.time.seconds())?;
+    let simulation = simulate_swap(&cfg, &curve, &fee_token, &input_token_enum, &input_token, &output_token, input_amount)?;
+    // validate the native input and fee
+    validate_funds(
+        &info.funds,
+        &[(&input_token.denom, input_amount), (&simulation.fee_denom, fee_amount)],
+    )?;
+    check_price_impact(&cfg, simulation.price_impact_bps)?;
+    let token_bought = simulation.output_amount;
+    let in_kind_fee = if cfg.fee_in_kind { simulation.fee_amount } else { Uint128::zero() };
+    let swap_amount = input_amount - in_kind_fee;
+
+    if min_token > token_bought {
+        return Err(ContractError::SwapMinError {
+            min: min_token,
+            available: token_bought,
+        });
+    }
+
+    // Create transfer from message
+    let mut transfer_msgs = match input_token.denom.clone() {
+        Denom::Cw20(addr) => vec![get_cw20_transfer_from_msg(
+            &info.sender,
+            &_env.contract.address,
+            &addr,
+            input_amount,
+        )?],
+        Denom::Native(_) => vec![],
+    };
+
+    // Create transfer to message
+    transfer_msgs.push(match output_token.denom.clone() {
+        Denom::Cw20(addr) => get_cw20_transfer_to_msg(recipient, &addr, token_bought)?,
+        Denom::Native(denom) => get_bank_transfer_to_msg(recipient, &denom, token_bought),
+    });
+
+    if cfg.fee_in_kind {
+        accrue_protocol_fee(deps.storage, &_env.block, pair_id, &input_token_enum, in_kind_fee)?;
+    } else {
+        //check fee is equal or larger than expected
+        if fee_amount < simulation.fee_amount {
+            return Err(ContractError::InsufficientFee {  })
+        }
+
+        // Create fee transfer message
+        transfer_msgs.extend(get_fee_transfer_msgs(&simulation.fee_denom, fee_amount, &info.sender, &cfg.treasury_address)?);
+        record_daily_stats(deps.storage, &_env.block, pair_id, |stats| add_treasury_fee(stats, &fee_token, fee_amount))?;
+    }
+
+    // Update token balances
+    update_price_cumulative(deps.storage, &_env.block, pair_id)?;
+    update_swap_reserves(deps.storage, &_env.block, pair_id, &input_token_enum, swap_amount, token_bought)?;
+
+    Ok(Response::new()
+        .add_messages(transfer_msgs)
+        .add_attributes(vec![
+            attr("pair_id", pair_id.to_string()),
+            attr("native_sold", input_amount),
+            attr("token_bought", token_bought),
+        ]))
+}
+
+/// Moves the sold amount into the input reserve and the bought amount out of the output reserve
+fn update_swap_reserves(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    pair_id: u64,
+    input_token_enum: &TokenSelect,
+    input_amount: Uint128,
+    output_amount: Uint128,
+) -> Result<(), ContractError> {
+    let (input_token_item, output_token_item) = match input_token_enum {
+        TokenSelect::Token1 => (TOKEN1, TOKEN2),
+        TokenSelect::Token2 => (TOKEN2, TOKEN1),
+    };
+
+    input_token_item.update(
+        storage,
+        pair_id,
+        |input_token| -> Result<_, ContractError> {
+            let mut input_token = input_token.ok_or(ContractError::UnknownPair { pair_id })?;
+            input_token.reserve = input_token
+                .reserve
+                .checked_add(input_amount)
+                .map_err(StdError::overflow)?;
+            Ok(input_token)
+        },
+    )?;
+
+    output_token_item.update(
+        storage,
+        pair_id,
+        |output_token| -> Result<_, ContractError> {
+            let mut output_token = output_token.ok_or(ContractError::UnknownPair { pair_id })?;
+            output_token.reserve = output_token
+                .reserve
+                .checked_sub(output_amount)
+                .map_err(StdError::overflow)?;
+            Ok(output_token)
+        },
+    )?;
+
+    record_daily_stats(storage, block, pair_id, |stats| {
+        stats.swap_count += 1;
+        match input_token_enum {
+            TokenSelect::Token1 => {
+                stats.token1_sold += input_amount;
+                stats.token2_bought += output_amount;
+            }
+            TokenSelect::Token2 => {
+                stats.token2_sold += input_amount;
+                stats.token1_bought += output_amount;
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Sells a token received through cw20 `Send`.
+/// No funds come along with the hook, so a fee charged in the input token is taken in kind
+/// and a fee charged in the output token is taken out of the bought amount.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_receive_swap(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    pair_id: u64,
+    recipient: &Addr,
+    input_amount: Uint128,
+    min_output: Uint128,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &env.block)?;
+    check_enabled(deps.storage)?;
+    let input_token_enum = get_received_token(deps.storage, pair_id, &info.sender)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let fee_token = get_fee_token(deps.storage, pair_id)?;
+    let cfg = Config { fee_in_kind: cfg.fee_in_kind || input_token_enum == fee_token, ..cfg };
+    let (input_token, output_token, output_token_enum) = match input_token_enum {
+        TokenSelect::Token1 => (TOKEN1.load(deps.storage, pair_id)?, TOKEN2.load(deps.storage, pair_id)?, TokenSelect::Token2),
+        TokenSelect::Token2 => (TOKEN2.load(deps.storage, pair_id)?, TOKEN1.load(deps.storage, pair_id)?, TokenSelect::Token1),
+    };
+
+    let curve = get_curve(deps.storage, pair_id, env.block.time.seconds())?;
+    let simulation = simulate_swap(&cfg, &curve, &fee_token, &input_token_enum, &input_token, &output_token, input_amount)?;
+    check_price_impact(&cfg, simulation.price_impact_bps)?;
+    let token_bought = simulation.output_amount;
+    let (fee_amount, in_kind_fee) = if cfg.fee_in_kind {
+        (Uint128::zero(), simulation.fee_amount)
+    } else {
+        (simulation.fee_amount, Uint128::zero())
+    };
+    let swap_amount = input_amount - in_kind_fee;
+    let output_amount = token_bought.checked_sub(fee_amount).map_err(StdError::overflow)?;
+
+    if min_output > output_amount {
+        return Err(ContractError::SwapMinError {
+            min: min_output,
+            available: output_amount,
+        });
+    }
+
+    let mut transfer_msgs = vec![token::transfer_token_message(
+        output_token.denom.clone(),
+        output_amount,
+        recipient.clone(),
+    )?];
+    if !fee_amount.is_zero() {
+        transfer_msgs.push(token::transfer_token_message(output_token.denom, fee_amount, cfg.treasury_address)?);
+        record_daily_stats(deps.storage, &env.block, pair_id, |stats| add_treasury_fee(stats, &output_token_enum, fee_amount))?;
+    }
+
+    accrue_protocol_fee(deps.storage, &env.block, pair_id, &input_token_enum, in_kind_fee)?;
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
+    update_swap_reserves(deps.storage, &env.block, pair_id, &input_token_enum, swap_amount, token_bought)?;
+
+    Ok(Response::new()
+        .add_messages(transfer_msgs)
+        .add_attributes(vec![
+            attr("action", "receive_swap"),
+            attr("pair_id", pair_id.to_string()),
+            attr("token_sold", input_amount),
+            attr("native_bought", output_amount),
+            attr("fee_amount", fee_amount + in_kind_fee),
+        ]))
+}
+
+pub fn execute_deposit_liquidity(
+    deps: DepsMut,
+    info: &MessageInfo,
+    pair_id: u64,
+    sender: &Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    check_enabled(deps.storage)?;
+    check_received_token2(deps.storage, pair_id, &info.sender)?;
+
+    let deposit = LIQUIDITY_DEPOSITS.update(
+        deps.storage,
+        (pair_id, sender.clone()),
+        |deposit| -> StdResult<_> { Ok(deposit.unwrap_or_default().checked_add(amount)?) },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "deposit_liquidity"),
+        attr("pair_id", pair_id.to_string()),
+        attr("address", sender),
+        attr("deposit", deposit),
+    ]))
+}
+
+pub fn execute_withdraw_deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    pair_id: u64,
+) -> Result<Response, ContractError> {
+    let deposit = LIQUIDITY_DEPOSITS
+        .may_load(deps.storage, (pair_id, info.sender.clone()))?
+        .ok_or(ContractError::InsufficientFunds {})?;
+    LIQUIDITY_DEPOSITS.remove(deps.storage, (pair_id, info.sender.clone()));
+
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+
+    Ok(Response::new()
+        .add_message(token::transfer_token_message(token2.denom, deposit, info.sender.clone())?)
+        .add_attributes(vec![
+            attr("action", "withdraw_deposit"),
+            attr("pair_id", pair_id.to_string()),
+            attr("amount", deposit),
+        ]))
+}
+
+/// Donates token2 received through cw20 `Send` to the reserve, like `AddToken` does with an allowance
+pub fn execute_fund(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    pair_id: u64,
+    sender: &Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg.owner != *sender {
+        return Err(ContractError::Unauthorized {  });
+    }
+    check_received_token2(deps.storage, pair_id, &info.sender)?;
+    let donations = record_donation(deps.storage, pair_id, &TokenSelect::Token2, amount)?;
+
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
+    TOKEN2.update(deps.storage, pair_id, |token2| -> Result<_, ContractError> {
+        let mut token2 = token2.ok_or(ContractError::UnknownPair { pair_id })?;
+        token2.reserve = token2.reserve.checked_add(amount).map_err(StdError::overflow)?;
+        Ok(token2)
+    })?;
+    record_daily_stats(deps.storage, &env.block, pair_id, |_| {})?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("pair_id", pair_id.to_string()),
+            attr("add_token", amount),
+            attr("donated_token1", donations.token1_amount),
+            attr("donated_token2", donations.token2_amount),
+        ]))
+}
+
+/// Farm of the pair with the rewards streamed up to `now`
+fn load_farm(storage: &dyn Storage, pair_id: u64, now: u64) -> Result<Farm, ContractError> {
+    if !LP_TOKEN.has(storage, pair_id) {
+        return Err(ContractError::UnknownPair { pair_id });
+    }
+    let mut farm = FARMS.may_load(storage, pair_id)?.unwrap_or(Farm {
+        reward_rate: Uint128::zero(),
+        reward_per_share: Decimal256::zero(),
+        total_staked: Uint128::zero(),
+        undistributed: Uint128::zero(),
+        unclaimed: Uint128::zero(),
+        last_update: now,
+    });
+
+    let elapsed = now.saturating_sub(farm.last_update);
+    farm.last_update = farm.last_update.max(now);
+    if elapsed == 0 || farm.total_staked.is_zero() {
+        return Ok(farm);
+    }
+    let reward = farm
+        .reward_rate
+        .checked_mul(Uint128::from(elapsed))
+        .map_err(StdError::overflow)?
+        .min(farm.undistributed);
+    farm.undistributed -= reward;
+    farm.unclaimed += reward;
+    farm.reward_per_share += Decimal256::from_ratio(reward, farm.total_staked);
+    Ok(farm)
+}
+
+/// Stake of the address with what it earned up to the farm index moved to its pending reward
+fn load_farm_staker(storage: &dyn Storage, pair_id: u64, address: &Addr, farm: &Farm) -> StdResult<FarmStaker> {
+    let mut staker = FARM_STAKERS
+        .may_load(storage, (pair_id, address.clone()))?
+        .unwrap_or_default();
+    let earned = Uint256::from(staker.amount) * (farm.reward_per_share - staker.reward_per_share_paid);
+    staker.pending_reward += Uint128::try_from(earned)?;
+    staker.reward_per_share_paid = farm.reward_per_share;
+    Ok(staker)
+}
+
+fn save_farm_staker(storage: &mut dyn Storage, pair_id: u64, address: &Addr, staker: &FarmStaker) -> StdResult<()> {
+    if staker.amount.is_zero() && staker.pending_reward.is_zero() {
+        FARM_STAKERS.remove(storage, (pair_id, address.clone()));
+        Ok(())
+    } else {
+        FARM_STAKERS.save(storage, (pair_id, address.clone()), staker)
+    }
+}
+
+/// Pays the pending reward of the staker out of the farm
+fn get_farm_reward_msgs(
+    cfg: &Config,
+    farm: &mut Farm,
+    staker: &mut FarmStaker,
+    recipient: &Addr,
+) -> StdResult<Vec<CosmosMsg>> {
+    let reward = staker.pending_reward;
+    staker.pending_reward = Uint128::zero();
+    farm.unclaimed = farm.unclaimed.saturating_sub(reward);
+    if reward.is_zero() {
+        return Ok(vec![]);
+    }
+    Ok(vec![get_cw20_transfer_to_msg(recipient, &cfg.fury_token_address, reward)?])
+}
+
+pub fn execute_set_farm_reward_rate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+    reward_rate: Uint128,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    access::check_role(deps.storage, &cfg.owner, &info.sender, Role::Operator)?;
+
+    // rewards up to now are streamed at the previous rate
+    let mut farm = load_farm(deps.storage, pair_id, env.block.time.seconds())?;
+    farm.reward_rate = reward_rate;
+    FARMS.save(deps.storage, pair_id, &farm)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_farm_reward_rate"),
+        attr("pair_id", pair_id.to_string()),
+        attr("reward_rate", reward_rate),
+    ]))
+}
+
+pub fn execute_fund_farm(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    pair_id: u64,
+    sender: &Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    check_no_flash_loan(deps.storage)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    access::check_role(deps.storage, &cfg.owner, sender, Role::Operator)?;
+    if info.sender != cfg.fury_token_address {
+        return Err(ContractError::InvalidToken {
+            received: info.sender.clone(),
+            expected: cfg.fury_token_address,
+        });
+    }
+
+    let mut farm = load_farm(deps.storage, pair_id, env.block.time.seconds())?;
+    farm.undistributed += amount;
+    FARMS.save(deps.storage, pair_id, &farm)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "fund_farm"),
+        attr("pair_id", pair_id.to_string()),
+        attr("amount", amount),
+        attr("undistributed", farm.undistributed),
+    ]))
+}
+
+pub fn execute_stake_lp(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    pair_id: u64,
+    sender: &Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    check_no_flash_loan(deps.storage)?;
+    let mut farm = load_farm(deps.storage, pair_id, env.block.time.seconds())?;
+    let lp_token_addr = LP_TOKEN.load(deps.storage, pair_id)?;
+    if info.sender != lp_token_addr {
+        return Err(ContractError::InvalidToken {
+            received: info.sender.clone(),
+            expected: lp_token_addr,
+        });
+    }
+
+    let mut staker = load_farm_staker(deps.storage, pair_id, sender, &farm)?;
+    staker.amount += amount;
+    farm.total_staked += amount;
+    save_farm_staker(deps.storage, pair_id, sender, &staker)?;
+    FARMS.save(deps.storage, pair_id, &farm)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "stake_lp"),
+        attr("pair_id", pair_id.to_string()),
+        attr("amount", amount),
+        attr("staked", staker.amount),
+    ]))
+}
+
+pub fn execute_unstake_lp(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    check_no_flash_loan(deps.storage)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut farm = load_farm(deps.storage, pair_id, env.block.time.seconds())?;
+    let mut staker = load_farm_staker(deps.storage, pair_id, &info.sender, &farm)?;
+    if amount > staker.amount {
+        return Err(ContractError::InsufficientStake {
+            requested: amount,
+            staked: staker.amount,
+        });
+    }
+
+    staker.amount -= amount;
+    farm.total_staked -= amount;
+    let reward = staker.pending_reward;
+    let mut messages = get_farm_reward_msgs(&cfg, &mut farm, &mut staker, &info.sender)?;
+    if !amount.is_zero() {
+        let lp_token_addr = LP_TOKEN.load(deps.storage, pair_id)?;
+        messages.push(get_cw20_transfer_to_msg(&info.sender, &lp_token_addr, amount)?);
+    }
+    save_farm_staker(deps.storage, pair_id, &info.sender, &staker)?;
+    FARMS.save(deps.storage, pair_id, &farm)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "unstake_lp"),
+            attr("pair_id", pair_id.to_string()),
+            attr("amount", amount),
+            attr("reward", reward),
+        ]))
+}
+
+pub fn execute_claim_farm_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+) -> Result<Response, ContractError> {
+    check_no_flash_loan(deps.storage)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut farm = load_farm(deps.storage, pair_id, env.block.time.seconds())?;
+    let mut staker = load_farm_staker(deps.storage, pair_id, &info.sender, &farm)?;
+
+    let reward = staker.pending_reward;
+    let messages = get_farm_reward_msgs(&cfg, &mut farm, &mut staker, &info.sender)?;
+    save_farm_staker(deps.storage, pair_id, &info.sender, &staker)?;
+    FARMS.save(deps.storage, pair_id, &farm)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "claim_farm_rewards"),
+            attr("pair_id", pair_id.to_string()),
+            attr("reward", reward),
+        ]))
+}
+
+/// Input and output token of the pair an order sells into
+fn load_order_tokens(storage: &dyn Storage, order: &LimitOrder) -> StdResult<(Token, Token)> {
+    let token1 = TOKEN1.load(storage, order.pair_id)?;
+    let token2 = TOKEN2.load(storage, order.pair_id)?;
+    Ok(match order.input_token {
+        TokenSelect::Token1 => (token1, token2),
+        TokenSelect::Token2 => (token2, token1),
+    })
+}
+
+fn load_order(storage: &dyn Storage, order_id: u64) -> Result<LimitOrder, ContractError> {
+    LIMIT_ORDERS
+        .may_load(storage, order_id)?
+        .ok_or(ContractError::UnknownOrder { order_id })
+}
+
+fn remove_order(storage: &mut dyn Storage, order_id: u64, order: &LimitOrder) {
+    LIMIT_ORDERS.remove(storage, order_id);
+    OWNER_ORDERS.remove(storage, (order.owner.clone(), order_id));
+}
+
+pub fn execute_place_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+    input_token: TokenSelect,
+    input_amount: Uint128,
+    min_output: Uint128,
+) -> Result<Response, ContractError> {
+    let input_denom = match input_token {
+        TokenSelect::Token1 => TOKEN1.load(deps.storage, pair_id)?.denom,
+        TokenSelect::Token2 => TOKEN2.load(deps.storage, pair_id)?.denom,
+    };
+    let transfer_msgs = match input_denom {
+        Denom::Cw20(addr) => vec![get_cw20_transfer_from_msg(
+            &info.sender,
+            &env.contract.address,
+            &addr,
+            input_amount,
+        )?],
+        Denom::Native(_) => {
+            validate_input_amount(&info.funds, input_amount, &input_denom)?;
+            vec![]
+        }
+    };
+    place_order(deps, &info.sender, pair_id, input_token, input_amount, min_output, transfer_msgs)
+}
+
+fn place_order(
+    deps: DepsMut,
+    owner: &Addr,
+    pair_id: u64,
+    input_token: TokenSelect,
+    input_amount: Uint128,
+    min_output: Uint128,
+    transfer_msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    // an order placed from a flash loan callback would count as repayment and could be cancelled afterwards
+    check_no_flash_loan(deps.storage)?;
+    if !LP_TOKEN.has(deps.storage, pair_id) {
+        return Err(ContractError::UnknownPair { pair_id });
+    }
+    if input_amount.is_zero() {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    let order_id = NEXT_ORDER_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_ORDER_ID.save(deps.storage, &(order_id + 1))?;
+    let order = LimitOrder {
+        owner: owner.clone(),
+        pair_id,
+        input_token,
+        input_amount,
+        min_output,
+    };
+    LIMIT_ORDERS.save(deps.storage, order_id, &order)?;
+    OWNER_ORDERS.save(deps.storage, (owner.clone(), order_id), &Empty {})?;
+
+    Ok(Response::new()
+        .add_messages(transfer_msgs)
+        .add_attributes(vec![
+            attr("action", "place_order"),
+            attr("order_id", order_id.to_string()),
+            attr("pair_id", pair_id.to_string()),
+            attr("input_amount", input_amount),
+            attr("min_output", min_output),
+        ]))
+}
+
+pub fn execute_cancel_order(
+    deps: DepsMut,
+    info: MessageInfo,
+    order_id: u64,
+) -> Result<Response, ContractError> {
+    let order = load_order(deps.storage, order_id)?;
+    if info.sender != order.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let (input_token, _) = load_order_tokens(deps.storage, &order)?;
+    remove_order(deps.storage, order_id, &order);
+
+    Ok(Response::new()
+        .add_message(token::transfer_token_message(input_token.denom, order.input_amount, order.owner)?)
+        .add_attributes(vec![
+            attr("action", "cancel_order"),
+            attr("order_id", order_id.to_string()),
+            attr("input_amount", order.input_amount),
+        ]))
+}
+
+pub fn execute_execute_orders(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    check_enabled(deps.storage)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let hop_cfg = Config { fee_in_kind: true, ..cfg.clone() };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut filled: Vec<String> = vec![];
+    let mut cursor: Option<u64> = start_after;
+    // the book is walked one order at a time, as filling one changes the reserves the next is priced against.
+    // unfillable orders count toward the limit too, so a call never scans more than a page of the book
+    for _ in 0..limit {
+        let next = LIMIT_ORDERS
+            .range(deps.storage, cursor.map(Bound::exclusive), None, Order::Ascending)
+            .next()
+            .transpose()?;
+        let (order_id, order) = match next {
+            Some(item) => item,
+            None => break,
+        };
+        cursor = Some(order_id);
+
+        let bounty = order.input_amount * Uint128::from(cfg.order_bounty) / Uint128::from(THOUSAND);
+        let swap_amount = order.input_amount - bounty;
+        let (input_token, output_token) = load_order_tokens(deps.storage, &order)?;
+        let fee_token = get_fee_token(deps.storage, order.pair_id)?;
+        let curve = get_curve(deps.storage, order.pair_id, env.block.time.seconds())?;
+        // orders the pool cannot fill yet are left resting
+        match simulate_swap(&hop_cfg, &curve, &fee_token, &order.input_token, &input_token, &output_token, swap_amount) {
+            Ok(simulation)
+                if simulation.output_amount >= order.min_output
+                    && simulation.price_impact_bps <= cfg.max_price_impact_bps => {}
+            _ => continue,
+        }
+
+        let operations = [SwapOperation {
+            pair_id: order.pair_id,
+            input_token: order.input_token.clone(),
+        }];
+        let (_, ask_denom, output_amount) = swap_route(deps.storage, &env.block, &operations, swap_amount)?;
+        remove_order(deps.storage, order_id, &order);
+
+        messages.push(token::transfer_token_message(ask_denom, output_amount, order.owner)?);
+        if !bounty.is_zero() {
+            messages.push(token::transfer_token_message(input_token.denom, bounty, info.sender.clone())?);
+        }
+        filled.push(order_id.to_string());
+    }
+
+    // empty attribute values are rejected, so a page without fills or orders leaves them out
+    let mut attributes = vec![attr("action", "execute_orders")];
+    if !filled.is_empty() {
+        attributes.push(attr("filled", filled.join(",")));
+    }
+    if let Some(order_id) = cursor {
+        attributes.push(attr("last_scanned", order_id.to_string()));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+fn isqrt(n: Uint256) -> Uint256 {
+    if n.is_zero() {
+        return n;
+    }
+    let mut x = n;
+    let mut y = (x + Uint256::one()) / Uint256::from(2u128);
+    while y < x {
+        x = y;
+        y = (x + n / x) / Uint256::from(2u128);
+    }
+    x
+}
+
+/// Part of a single sided deposit to swap so that what is left and what is bought
+/// have the ratio of the reserves once the swap is done
+fn get_zap_swap_amount(amount: Uint128, input_reserve: Uint128, fee: u64) -> StdResult<Uint128> {
+    if input_reserve.is_zero() {
+        return Err(StdError::generic_err("No liquidity"));
+    }
+    // s = (sqrt(R * (R * (2000 - f)^2 + 4000 * (1000 - f) * A)) - R * (2000 - f)) / (2 * (1000 - f))
+    let reserve = Uint256::from(input_reserve);
+    let k = Uint256::from(2 * THOUSAND - fee);
+    let g = Uint256::from(THOUSAND - fee);
+    let root = isqrt(reserve * (reserve * k * k + Uint256::from(4 * THOUSAND) * g * Uint256::from(amount)));
+    let swap_amount = (root - reserve * k) / (Uint256::from(2u128) * g);
+    Ok(Uint128::try_from(swap_amount)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_zap_in(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    pair_id: u64,
+    input_token: TokenSelect,
+    amount: Uint128,
+    min_liquidity: Uint128,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &env.block)?;
+
+    let input_denom = match input_token {
+        TokenSelect::Token1 => TOKEN1.load(deps.storage, pair_id)?.denom,
+        TokenSelect::Token2 => TOKEN2.load(deps.storage, pair_id)?.denom,
+    };
+    let transfer_msgs = match input_denom {
+        Denom::Cw20(addr) => vec![get_cw20_transfer_from_msg(
+            &info.sender,
+            &env.contract.address,
+            &addr,
+            amount,
+        )?],
+        Denom::Native(_) => {
+            validate_input_amount(&info.funds, amount, &input_denom)?;
+            vec![]
+        }
+    };
+
+    zap_in(deps, env, &info.sender, pair_id, input_token, amount, min_liquidity, transfer_msgs)
+}
+
+/// Adds a single sided deposit already held by the contract (or pulled by `messages`)
+#[allow(clippy::too_many_arguments)]
+fn zap_in(
+    deps: DepsMut,
+    env: Env,
+    provider: &Addr,
+    pair_id: u64,
+    input_token: TokenSelect,
+    amount: Uint128,
+    min_liquidity: Uint128,
+    mut messages: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    check_enabled(deps.storage)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let lp_token_addr = LP_TOKEN.load(deps.storage, pair_id)?;
+    let lp_token_supply = get_lp_token_supply(deps.as_ref(), &lp_token_addr)?;
+    // the first deposit sets the price, and donated reserves without lp tokens do not
+    if lp_token_supply.is_zero()
+        || TOKEN1.load(deps.storage, pair_id)?.reserve.is_zero()
+        || TOKEN2.load(deps.storage, pair_id)?.reserve.is_zero()
+    {
+        return Err(ContractError::ZapIntoEmptyPair { pair_id });
+    }
+
+    let (input_reserve, output_reserve) = match input_token {
+        TokenSelect::Token1 => (TOKEN1.load(deps.storage, pair_id)?.reserve, TOKEN2.load(deps.storage, pair_id)?.reserve),
+        TokenSelect::Token2 => (TOKEN2.load(deps.storage, pair_id)?.reserve, TOKEN1.load(deps.storage, pair_id)?.reserve),
+    };
+    let swap_fee = cfg.lp_fee + cfg.platform_fee + cfg.tx_fee;
+    let swap_amount = match get_curve(deps.storage, pair_id, env.block.time.seconds())? {
+        Curve::ConstantProduct {} => get_zap_swap_amount(amount, input_reserve, swap_fee)?,
+        Curve::Stable { amp } => stableswap::get_zap_swap_amount(amp, amount, input_reserve, output_reserve, swap_fee)?,
+    };
+    let operation = SwapOperation { pair_id, input_token: input_token.clone() };
+    let (_, _, bought) = swap_route(deps.storage, &env.block, &[operation], swap_amount)?;
+    let (token1_available, token2_available) = match input_token {
+        TokenSelect::Token1 => (amount - swap_amount, bought),
+        TokenSelect::Token2 => (bought, amount - swap_amount),
+    };
+
+    // the liquidity fee of `AddLiquidity` is kept from the fee side
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let fee_token = get_fee_token(deps.storage, pair_id)?;
+    let liquidity_fee = (cfg.platform_fee + cfg.tx_fee) * 2;
+    let without_fee = |available: Uint128| available * Uint128::from(THOUSAND) / Uint128::from(THOUSAND + liquidity_fee);
+    let (token1_budget, token2_budget) = match fee_token {
+        TokenSelect::Token1 => (without_fee(token1_available), token2_available),
+        TokenSelect::Token2 => (token1_available, without_fee(token2_available)),
+    };
+    let token1_amount = token1_budget.min(
+        (token2_budget.saturating_sub(Uint128::new(1)) * token1.reserve)
+            .checked_div(token2.reserve)
+            .map_err(StdError::divide_by_zero)?,
+    );
+    let token2_amount = get_token2_amount_required(
+        token2_available,
+        token1_amount,
+        lp_token_supply,
+        token2.reserve,
+        token1.reserve,
+    )?;
+    let (fee_amount, fee_denom, token1_fee, token2_fee) = match fee_token {
+        TokenSelect::Token1 => {
+            let fee_amount = token1_amount * Uint128::from(liquidity_fee) / Uint128::from(THOUSAND);
+            (fee_amount, token1.denom.clone(), fee_amount, Uint128::zero())
+        }
+        TokenSelect::Token2 => {
+            let fee_amount = token2_amount * Uint128::from(liquidity_fee) / Uint128::from(THOUSAND);
+            (fee_amount, token2.denom.clone(), Uint128::zero(), fee_amount)
+        }
+    };
+    let liquidity_amount = get_lp_token_amount_to_mint(token1_amount, lp_token_supply, token1.reserve)?;
+    if liquidity_amount < min_liquidity {
+        return Err(ContractError::MinLiquidityError {
+            min_liquidity,
+            liquidity_available: liquidity_amount,
+        });
+    }
+
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
+    add_liquidity_reserves(deps.storage, pair_id, token1_amount, token2_amount)?;
+
+    if !fee_amount.is_zero() {
+        messages.push(token::transfer_token_message(fee_denom, fee_amount, cfg.treasury_address.clone())?);
+    }
+    let token1_refund = token1_available.checked_sub(token1_amount + token1_fee).map_err(StdError::overflow)?;
+    if !token1_refund.is_zero() {
+        messages.push(token::transfer_token_message(token1.denom, token1_refund, provider.clone())?);
+    }
+    let token2_refund = token2_available.checked_sub(token2_amount + token2_fee).map_err(StdError::overflow)?;
+    if !token2_refund.is_zero() {
+        messages.push(token::transfer_token_message(token2.denom, token2_refund, provider.clone())?);
+    }
+    messages.push(mint_lp_tokens(&cfg.owner, liquidity_amount, &lp_token_addr)?);
+    record_daily_stats(deps.storage, &env.block, pair_id, |stats| {
+        stats.lp_minted += liquidity_amount;
+        add_treasury_fee(stats, &fee_token, fee_amount);
+    })?;
+    messages.extend(get_lp_bond_msgs(deps.storage, &cfg, pair_id, provider, token2_amount)?);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "zap_in"),
+            attr("pair_id", pair_id.to_string()),
+            attr("swap_amount", swap_amount),
+            attr("token1_amount", token1_amount),
+            attr("token2_amount", token2_amount),
+            attr("liquidity_received", liquidity_amount),
+        ]))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_zap_out(
+    mut deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    pair_id: u64,
+    amount: Uint128,
+    output_token: TokenSelect,
+    min_output: Uint128,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &env.block)?;
+
+    let lp_token_addr = LP_TOKEN.load(deps.storage, pair_id)?;
+    let (token1_amount, token2_amount) =
+        withdraw_liquidity(deps.branch(), &env, &info.sender, pair_id, amount)?;
+    let (kept_amount, sold_amount, sold_token) = match output_token {
+        TokenSelect::Token1 => (token1_amount, token2_amount, TokenSelect::Token2),
+        TokenSelect::Token2 => (token2_amount, token1_amount, TokenSelect::Token1),
+    };
+    let operation = SwapOperation { pair_id, input_token: sold_token };
+    let (_, output_denom, bought) = swap_route(deps.storage, &env.block, &[operation], sold_amount)?;
+
+    let output_amount = kept_amount + bought;
+    if min_output > output_amount {
+        return Err(ContractError::SwapMinError {
+            min: min_output,
+            available: output_amount,
+        });
+    }
+
+    Ok(Response::new()
+        .add_message(token::transfer_token_message(output_denom, output_amount, info.sender.clone())?)
+        .add_message(get_burn_msg(&lp_token_addr, &info.sender, amount)?)
+        .add_attributes(vec![
+            attr("action", "zap_out"),
+            attr("pair_id", pair_id.to_string()),
+            attr("liquidity_burned", amount),
+            attr("output_amount", output_amount),
+        ]))
+}
+
+/// Runs the swaps of a route against the reserves, each hop keeping its protocol fee in kind.
+/// Returns the denom the route starts from, and the denom and amount it ends with.
+fn swap_route(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    operations: &[SwapOperation],
+    offer_amount: Uint128,
+) -> Result<(Denom, Denom, Uint128), ContractError> {
+    if operations.is_empty() {
+        return Err(ContractError::InvalidSwapRoute {});
+    }
+    check_enabled(storage)?;
+    let cfg = CONFIG.load(storage)?;
+    let hop_cfg = Config { fee_in_kind: true, ..cfg };
+
+    let mut offer_denom: Option<Denom> = None;
+    let mut ask_denom: Option<Denom> = None;
+    let mut amount = offer_amount;
+    for operation in operations {
+        let token1 = TOKEN1.load(storage, operation.pair_id)?;
+        let token2 = TOKEN2.load(storage, operation.pair_id)?;
+        let (input_token, output_token) = match operation.input_token {
+            TokenSelect::Token1 => (token1, token2),
+            TokenSelect::Token2 => (token2, token1),
+        };
+        if let Some(denom) = &ask_denom {
+            if denom != &input_token.denom {
+                return Err(ContractError::InvalidSwapRoute {});
+            }
+        }
+
+        let fee_token = get_fee_token(storage, operation.pair_id)?;
+        let curve = get_curve(storage, operation.pair_id, block.time.seconds())?;
+        let simulation = simulate_swap(&hop_cfg, &curve, &fee_token, &operation.input_token, &input_token, &output_token, amount)?;
+        check_price_impact(&hop_cfg, simulation.price_impact_bps)?;
+        accrue_protocol_fee(storage, block, operation.pair_id, &operation.input_token, simulation.fee_amount)?;
+        update_price_cumulative(storage, block, operation.pair_id)?;
+        update_swap_reserves(
+            storage,
+            block,
+            operation.pair_id,
+            &operation.input_token,
+            amount - simulation.fee_amount,
+            simulation.output_amount,
+        )?;
+
+        offer_denom.get_or_insert(input_token.denom);
+        ask_denom = Some(output_token.denom);
+        amount = simulation.output_amount;
+    }
+
+    Ok((offer_denom.unwrap(), ask_denom.unwrap(), amount))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_multi_hop_swap(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    operations: Vec<SwapOperation>,
+    offer_amount: Uint128,
+    minimum_receive: Uint128,
+    recipient: &Addr,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &env.block)?;
+
+    let (offer_denom, ask_denom, return_amount) =
+        swap_route(deps.storage, &env.block, &operations, offer_amount)?;
+    if minimum_receive > return_amount {
+        return Err(ContractError::SwapMinError {
+            min: minimum_receive,
+            available: return_amount,
+        });
+    }
+
+    let mut transfer_msgs = match offer_denom {
+        Denom::Cw20(addr) => vec![get_cw20_transfer_from_msg(
+            &info.sender,
+            &env.contract.address,
+            &addr,
+            offer_amount,
+        )?],
+        Denom::Native(_) => {
+            validate_input_amount(&info.funds, offer_amount, &offer_denom)?;
+            vec![]
+        }
     };
+    transfer_msgs.push(token::transfer_token_message(ask_denom, return_amount, recipient.clone())?);
 
-    let transfer_bank_cosmos_msg: CosmosMsg = transfer_bank_msg.into();
-    transfer_bank_cosmos_msg
+    Ok(Response::new()
+        .add_messages(transfer_msgs)
+        .add_attributes(vec![
+            attr("action", "multi_hop_swap"),
+            attr("hops", operations.len().to_string()),
+            attr("offer_amount", offer_amount),
+            attr("return_amount", return_amount),
+            attr("recipient", recipient.to_string()),
+        ]))
 }
 
-fn get_input_price(
-    input_amount: Uint128,
-    input_reserve: Uint128,
-    output_reserve: Uint128,
-) -> StdResult<Uint128> {
-    if input_reserve == Uint128::zero() || output_reserve == Uint128::zero() {
-        return Err(StdError::generic_err("No liquidity"));
-    };
-
-    let input_amount_with_fee = input_amount
-        .checked_mul(Uint128::new(997))
-        .map_err(StdError::overflow)?;
-    let numerator = input_amount_with_fee
-        .checked_mul(output_reserve)
-        .map_err(StdError::overflow)?;
-    let denominator = input_reserve
-        .checked_mul(Uint128::new(1000))
-        .map_err(StdError::overflow)?
-        .checked_add(input_amount_with_fee)
-        .map_err(StdError::overflow)?;
-
-    numerator
-        .checked_div(denominator)
-        .map_err(StdError::divide_by_zero)
-}
+#[allow(clippy::too_many_arguments)]
+pub fn execute_receive_multi_hop_swap(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    operations: Vec<SwapOperation>,
+    offer_amount: Uint128,
+    minimum_receive: Uint128,
+    recipient: &Addr,
+    expiration: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_expiration(&expiration, &env.block)?;
 
-fn get_amount_for_denom(coins: &[Coin], denom: &str) -> Coin {
-    let amount: Uint128 = coins
-        .iter()
-        .filter(|c| c.denom == denom)
-        .map(|c| c.amount)
-        .sum();
-    Coin {
-        amount,
-        denom: denom.to_string(),
+    let (offer_denom, ask_denom, return_amount) =
+        swap_route(deps.storage, &env.block, &operations, offer_amount)?;
+    if offer_denom != Denom::Cw20(info.sender.clone()) {
+        return Err(ContractError::InvalidSwapRoute {});
+    }
+    if minimum_receive > return_amount {
+        return Err(ContractError::SwapMinError {
+            min: minimum_receive,
+            available: return_amount,
+        });
     }
+
+    Ok(Response::new()
+        .add_message(token::transfer_token_message(ask_denom, return_amount, recipient.clone())?)
+        .add_attributes(vec![
+            attr("action", "receive_multi_hop_swap"),
+            attr("hops", operations.len().to_string()),
+            attr("offer_amount", offer_amount),
+            attr("return_amount", return_amount),
+            attr("recipient", recipient.to_string()),
+        ]))
 }
 
 #[allow(clippy::too_many_arguments)]
-pub fn execute_swap(
+pub fn execute_swap_exact_output(
     deps: DepsMut,
     info: &MessageInfo,
-    input_amount: Uint128,
-    _env: Env,
+    env: Env,
+    pair_id: u64,
     input_token_enum: TokenSelect,
-    recipient: &Addr,
-    min_token: Uint128,
+    output_amount: Uint128,
+    max_input: Uint128,
     fee_amount: Uint128,
     expiration: Option<Expiration>,
 ) -> Result<Response, ContractError> {
-    check_expiration(&expiration, &_env.block)?;
+    check_expiration(&expiration, &env.block)?;
+    check_enabled(deps.storage)?;
 
     let cfg = CONFIG.load(deps.storage)?;
 
-    let input_token_item = match input_token_enum {
-        TokenSelect::Token1 => TOKEN1,
-        TokenSelect::Token2 => TOKEN2,
+    let (input_token, output_token) = match input_token_enum {
+        TokenSelect::Token1 => (TOKEN1.load(deps.storage, pair_id)?, TOKEN2.load(deps.storage, pair_id)?),
+        TokenSelect::Token2 => (TOKEN2.load(deps.storage, pair_id)?, TOKEN1.load(deps.storage, pair_id)?),
     };
-    let input_token = input_token_item.load(deps.storage)?;
-    let output_token_item = match input_token_enum {
-        TokenSelect::Token1 => TOKEN2,
-        TokenSelect::Token2 => TOKEN1,
+    let fee_token = get_fee_token(deps.storage, pair_id)?;
+    let fee_denom = if input_token_enum == fee_token {
+        input_token.denom.clone()
+    } else {
+        output_token.denom.clone()
     };
-    let output_token = output_token_item.load(deps.storage)?;
 
-    // validate input_amount if native input token
-    match input_token_enum.clone() {
-        TokenSelect::Token1 => validate_input_amount(&info.funds, input_amount + fee_amount, &input_token.denom)?,
-        TokenSelect::Token2 => validate_input_amount(&info.funds, fee_amount, &input_token.denom)?
+    if cfg.fee_in_kind && !fee_amount.is_zero() {
+        return Err(ContractError::FeeTakenInKind {});
     }
 
-    let token_bought = get_input_price(input_amount, input_token.reserve, output_token.reserve)?;
+    // native input is sent as the maximum, the difference is refunded below
+    validate_funds(&info.funds, &[(&input_token.denom, max_input), (&fee_denom, fee_amount)])?;
 
-    if min_token > token_bought {
-        return Err(ContractError::SwapMinError {
-            min: min_token,
-            available: token_bought,
+    let curve = get_curve(deps.storage, pair_id, env.block.time.seconds())?;
+    let swap_amount = get_curve_output_price(&curve, output_amount, input_token.reserve, output_token.reserve, cfg.lp_fee)?;
+    let input_amount = get_input_with_in_kind_fee(&cfg, swap_amount);
+    let in_kind_fee = input_amount - swap_amount;
+    check_price_impact(
+        &cfg,
+        get_price_impact_bps(&curve, input_token.reserve, output_token.reserve, swap_amount, output_amount)?,
+    )?;
+
+    if input_amount > max_input {
+        return Err(ContractError::SwapMaxError {
+            max: max_input,
+            required: input_amount,
         });
     }
 
-    // Create transfer from message
     let mut transfer_msgs = match input_token.denom.clone() {
         Denom::Cw20(addr) => vec![get_cw20_transfer_from_msg(
             &info.sender,
-            &_env.contract.address,
+            &env.contract.address,
             &addr,
             input_amount,
         )?],
-        Denom::Native(_) => vec![],
+        Denom::Native(denom) => {
+            if input_amount < max_input {
+                vec![get_bank_transfer_to_msg(&info.sender, &denom, max_input - input_amount)]
+            } else {
+                vec![]
+            }
+        }
     };
 
-    // Create transfer to message
     transfer_msgs.push(match output_token.denom.clone() {
-        Denom::Cw20(addr) => get_cw20_transfer_to_msg(recipient, &addr, token_bought)?,
-        Denom::Native(denom) => get_bank_transfer_to_msg(recipient, &denom, token_bought),
+        Denom::Cw20(addr) => get_cw20_transfer_to_msg(&info.sender, &addr, output_amount)?,
+        Denom::Native(denom) => get_bank_transfer_to_msg(&info.sender, &denom, output_amount),
     });
 
-    //check fee is equal or larger than expected
-    match input_token_enum.clone() {
-        TokenSelect::Token1 => {
-            if fee_amount < input_amount * Uint128::from(cfg.platform_fee + cfg.tx_fee) / Uint128::from(THOUSAND) {
-                return Err(ContractError::InsufficientFee {  })
-            }
-            
-        }
-        TokenSelect::Token2 => {
-            if fee_amount < token_bought * Uint128::from(cfg.platform_fee + cfg.tx_fee) / Uint128::from(THOUSAND) {
-                return Err(ContractError::InsufficientFee {  })
-            }
+    if cfg.fee_in_kind {
+        accrue_protocol_fee(deps.storage, &env.block, pair_id, &input_token_enum, in_kind_fee)?;
+    } else {
+        // same fee rule as `execute_swap`
+        let required_fee = if input_token_enum == fee_token {
+            get_protocol_fee(&cfg, input_amount)
+        } else {
+            get_protocol_fee(&cfg, output_amount)
+        };
+        if fee_amount < required_fee {
+            return Err(ContractError::InsufficientFee {  })
         }
+
+        transfer_msgs.extend(get_fee_transfer_msgs(&fee_denom, fee_amount, &info.sender, &cfg.treasury_address)?);
+        record_daily_stats(deps.storage, &env.block, pair_id, |stats| add_treasury_fee(stats, &fee_token, fee_amount))?;
     }
 
-    // Create fee transfer message
-    transfer_msgs.push(
-        util::transfer_token_message(Denom::Native(cfg.usdc_denom), fee_amount, cfg.treasury_address.clone())?
-    );
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
+    update_swap_reserves(deps.storage, &env.block, pair_id, &input_token_enum, swap_amount, output_amount)?;
 
-    // Update token balances
-    input_token_item.update(
-        deps.storage,
-        |mut input_token| -> Result<_, ContractError> {
-            input_token.reserve = input_token
-                .reserve
-                .checked_add(input_amount)
-                .map_err(StdError::overflow)?;
-            Ok(input_token)
-        },
-    )?;
+    Ok(Response::new()
+        .add_messages(transfer_msgs)
+        .add_attributes(vec![
+            attr("action", "swap_exact_output"),
+            attr("pair_id", pair_id.to_string()),
+            attr("input_amount", input_amount),
+            attr("output_amount", output_amount),
+        ]))
+}
 
-    output_token_item.update(
-        deps.storage,
-        |mut output_token| -> Result<_, ContractError> {
-            output_token.reserve = output_token
-                .reserve
-                .checked_sub(token_bought)
-                .map_err(StdError::overflow)?;
-            Ok(output_token)
+pub fn execute_flash_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pair_id: u64,
+    token: TokenSelect,
+    amount: Uint128,
+    callback_msg: Binary,
+) -> Result<Response, ContractError> {
+    check_enabled(deps.storage)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let lent_token = match token {
+        TokenSelect::Token1 => TOKEN1.load(deps.storage, pair_id)?,
+        TokenSelect::Token2 => TOKEN2.load(deps.storage, pair_id)?,
+    };
+    if amount.is_zero() || amount > lent_token.reserve {
+        return Err(ContractError::InsufficientLiquidityError {
+            requested: amount,
+            available: lent_token.reserve,
+        });
+    }
+
+    // rounded up so that small loans are not free
+    let fee = (amount * Uint128::from(cfg.flash_loan_fee) + Uint128::from(THOUSAND - 1)) / Uint128::from(THOUSAND);
+    let balance_before = token::get_token_amount(deps.querier, lent_token.denom.clone(), env.contract.address)?;
+    FLASH_LOAN.save(deps.storage, &FlashLoan {
+        pair_id,
+        token,
+        balance_before,
+        fee,
+    })?;
+
+    let callback = SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: info.sender.to_string(),
+            msg: to_binary(&FlashLoanReceiverMsg::FlashLoanCallback {
+                pair_id,
+                denom: lent_token.denom.clone(),
+                amount,
+                fee,
+                msg: callback_msg,
+            })?,
+            funds: vec![],
         },
-    )?;
+        FLASH_LOAN_REPLY_ID,
+    );
 
     Ok(Response::new()
-        .add_messages(transfer_msgs)
+        .add_message(token::transfer_token_message(lent_token.denom, amount, info.sender.clone())?)
+        .add_submessage(callback)
         .add_attributes(vec![
-            attr("native_sold", input_amount),
-            attr("token_bought", token_bought),
+            attr("action", "flash_loan"),
+            attr("pair_id", pair_id.to_string()),
+            attr("receiver", info.sender.to_string()),
+            attr("amount", amount),
+            attr("fee", fee),
         ]))
 }
 
+/// Checks the flash loan was paid back with its fee and adds the fee to the reserve
+fn reply_flash_loan(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let loan = FLASH_LOAN.load(deps.storage)?;
+    FLASH_LOAN.remove(deps.storage);
+
+    let denom = match loan.token {
+        TokenSelect::Token1 => TOKEN1.load(deps.storage, loan.pair_id)?.denom,
+        TokenSelect::Token2 => TOKEN2.load(deps.storage, loan.pair_id)?.denom,
+    };
+    let balance_after = token::get_token_amount(deps.querier, denom, env.contract.address)?;
+    let required = loan.balance_before + loan.fee;
+    if balance_after < required {
+        return Err(ContractError::FlashLoanNotRepaid {
+            required,
+            available: balance_after,
+        });
+    }
+
+    update_price_cumulative(deps.storage, &env.block, loan.pair_id)?;
+    match loan.token {
+        TokenSelect::Token1 => add_liquidity_reserves(deps.storage, loan.pair_id, loan.fee, Uint128::zero())?,
+        TokenSelect::Token2 => add_liquidity_reserves(deps.storage, loan.pair_id, Uint128::zero(), loan.fee)?,
+    }
+    record_daily_stats(deps.storage, &env.block, loan.pair_id, |_| {})?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "flash_loan_repaid"),
+        attr("pair_id", loan.pair_id.to_string()),
+        attr("fee", loan.fee),
+    ]))
+}
 
 pub fn execute_add_token(
     deps: DepsMut,
     env: Env,
     info: &MessageInfo,
+    pair_id: u64,
     input_token_enum: TokenSelect,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
@@ -776,13 +3138,10 @@ pub fn execute_add_token(
         TokenSelect::Token1 => TOKEN1,
         TokenSelect::Token2 => TOKEN2,
     };
-    let input_token = input_token_item.load(deps.storage)?;
+    let input_token = input_token_item.load(deps.storage, pair_id)?;
     
     // validate input_amount if native input token
-    match input_token_enum.clone() {
-        TokenSelect::Token1 => validate_input_amount(&info.funds, amount, &input_token.denom)?,
-        TokenSelect::Token2 => {}
-    }
+    validate_input_amount(&info.funds, amount, &input_token.denom)?;
 
     // Create transfer from message
     let mut transfer_msgs = match input_token.denom.clone() {
@@ -795,10 +3154,15 @@ pub fn execute_add_token(
         Denom::Native(_) => vec![],
     };
 
+    let donations = record_donation(deps.storage, pair_id, &input_token_enum, amount)?;
+
     // Update token balances
+    update_price_cumulative(deps.storage, &env.block, pair_id)?;
     input_token_item.update(
         deps.storage,
-        |mut input_token| -> Result<_, ContractError> {
+        pair_id,
+        |input_token| -> Result<_, ContractError> {
+            let mut input_token = input_token.ok_or(ContractError::UnknownPair { pair_id })?;
             input_token.reserve = input_token
                 .reserve
                 .checked_add(amount)
@@ -806,26 +3170,78 @@ pub fn execute_add_token(
             Ok(input_token)
         },
     )?;
+    record_daily_stats(deps.storage, &env.block, pair_id, |_| {})?;
 
     Ok(Response::new()
         .add_messages(transfer_msgs)
         .add_attributes(vec![
-            attr("add_token", amount)
+            attr("pair_id", pair_id.to_string()),
+            attr("add_token", amount),
+            attr("donated_token1", donations.token1_amount),
+            attr("donated_token2", donations.token2_amount),
         ]))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} 
             => to_binary(&query_config(deps)?),
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
-        QueryMsg::Info {} => to_binary(&query_info(deps)?),
-        QueryMsg::Token1ForToken2Price { token1_amount } => {
-            to_binary(&query_token1_for_token2_price(deps, token1_amount)?)
+        QueryMsg::Info { pair_id } => to_binary(&query_info(deps, pair_id.unwrap_or(DEFAULT_PAIR_ID))?),
+        QueryMsg::Token1ForToken2Price { pair_id, token1_amount } => {
+            to_binary(&query_token1_for_token2_price(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID), token1_amount)?)
+        }
+        QueryMsg::Token2ForToken1Price { pair_id, token2_amount } => {
+            to_binary(&query_token2_for_token1_price(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID), token2_amount)?)
+        }
+        QueryMsg::Token1ForExactToken2 { pair_id, token2_amount } => {
+            to_binary(&query_token1_for_exact_token2(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID), token2_amount)?)
+        }
+        QueryMsg::Token2ForExactToken1 { pair_id, token1_amount } => {
+            to_binary(&query_token2_for_exact_token1(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID), token1_amount)?)
+        }
+        QueryMsg::SimulateSwap { pair_id, input_token, input_amount } => {
+            to_binary(&query_simulate_swap(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID), input_token, input_amount)?)
+        }
+        QueryMsg::Pairs { start_after, limit } => {
+            to_binary(&query_pairs(deps, env, start_after, limit)?)
+        }
+        QueryMsg::Deposit { pair_id, address } => {
+            to_binary(&query_deposit(deps, pair_id.unwrap_or(DEFAULT_PAIR_ID), address)?)
+        }
+        QueryMsg::Twap { pair_id, window_seconds } => {
+            to_binary(&query_twap(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID), window_seconds)?)
+        }
+        QueryMsg::Observations { pair_id, start_before, limit } => {
+            to_binary(&query_observations(deps, pair_id.unwrap_or(DEFAULT_PAIR_ID), start_before, limit)?)
+        }
+        QueryMsg::ProtocolFees { pair_id } => {
+            to_binary(&query_protocol_fees(deps, pair_id.unwrap_or(DEFAULT_PAIR_ID))?)
+        }
+        QueryMsg::ReserveBalances { pair_id } => {
+            to_binary(&query_reserve_balances(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID))?)
+        }
+        QueryMsg::DailyStats { pair_id, start_day, end_day, limit } => {
+            to_binary(&query_daily_stats(deps, pair_id.unwrap_or(DEFAULT_PAIR_ID), start_day, end_day, limit)?)
         }
-        QueryMsg::Token2ForToken1Price { token2_amount } => {
-            to_binary(&query_token2_for_token1_price(deps, token2_amount)?)
+        QueryMsg::Amp { pair_id } => to_binary(&query_amp(deps, env, pair_id)?),
+        QueryMsg::Farm { pair_id } => to_binary(&query_farm(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID))?),
+        QueryMsg::FarmStaker { pair_id, address } => {
+            to_binary(&query_farm_staker(deps, env, pair_id.unwrap_or(DEFAULT_PAIR_ID), address)?)
+        }
+        QueryMsg::Order { order_id } => to_binary(&query_order(deps, order_id)?),
+        QueryMsg::Orders { start_after, limit } => to_binary(&query_orders(deps, start_after, limit)?),
+        QueryMsg::OrdersByOwner { owner, start_after, limit } => {
+            to_binary(&query_orders_by_owner(deps, owner, start_after, limit)?)
+        }
+        QueryMsg::Ownership {} => {
+            let cfg = CONFIG.load(deps.storage)?;
+            to_binary(&access::query_ownership(deps.storage, cfg.owner)?)
+        }
+        QueryMsg::RoleMembers { role, start_after, limit } => {
+            let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+            to_binary(&access::query_role_members(deps.storage, role, start_after, limit)?)
         }
     }
 }
@@ -835,6 +3251,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     Ok(ConfigResponse {
         owner: cfg.owner,
+        lp_token_code_id: cfg.lp_token_code_id,
         bonding_code_id: cfg.bonding_code_id,
         bonding_contract_address: cfg.bonding_contract_address,
         fury_token_address: cfg.fury_token_address,
@@ -842,16 +3259,22 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         usdc_denom: cfg.usdc_denom,
         tx_fee: cfg.tx_fee,
         platform_fee: cfg.platform_fee,
+        lp_fee: cfg.lp_fee,
+        fee_in_kind: cfg.fee_in_kind,
+        enabled: cfg.enabled,
+        max_price_impact_bps: cfg.max_price_impact_bps,
+        flash_loan_fee: cfg.flash_loan_fee,
+        order_bounty: cfg.order_bounty,
         lock_seconds: cfg.lock_seconds,
         discount: cfg.discount,
         daily_vesting_amount: cfg.daily_vesting_amount
     })
 }
 
-pub fn query_info(deps: Deps) -> StdResult<InfoResponse> {
-    let token1 = TOKEN1.load(deps.storage)?;
-    let token2 = TOKEN2.load(deps.storage)?;
-    let lp_token_address = LP_TOKEN.load(deps.storage)?;
+pub fn query_info(deps: Deps, pair_id: u64) -> StdResult<InfoResponse> {
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let lp_token_address = LP_TOKEN.load(deps.storage, pair_id)?;
     // TODO get total supply
     Ok(InfoResponse {
         token1_reserve: token1.reserve,
@@ -865,50 +3288,381 @@ pub fn query_info(deps: Deps) -> StdResult<InfoResponse> {
 
 pub fn query_token1_for_token2_price(
     deps: Deps,
+    env: Env,
+    pair_id: u64,
     token1_amount: Uint128,
 ) -> StdResult<Token1ForToken2PriceResponse> {
-    let token1 = TOKEN1.load(deps.storage)?;
-    let token2 = TOKEN2.load(deps.storage)?;
-    let token2_amount = get_input_price(token1_amount, token1.reserve, token2.reserve)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let token2_amount = get_curve_input_price(
+        &get_curve(deps.storage, pair_id, env.block.time.seconds())?,
+        token1_amount - get_in_kind_fee(&cfg, token1_amount),
+        token1.reserve,
+        token2.reserve,
+        cfg.lp_fee,
+    )?;
     Ok(Token1ForToken2PriceResponse { token2_amount })
 }
 
 pub fn query_token2_for_token1_price(
     deps: Deps,
+    env: Env,
+    pair_id: u64,
     token2_amount: Uint128,
 ) -> StdResult<Token2ForToken1PriceResponse> {
-    let token1 = TOKEN1.load(deps.storage)?;
-    let token2 = TOKEN2.load(deps.storage)?;
-    let token1_amount = get_input_price(token2_amount, token2.reserve, token1.reserve)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let token1_amount = get_curve_input_price(
+        &get_curve(deps.storage, pair_id, env.block.time.seconds())?,
+        token2_amount - get_in_kind_fee(&cfg, token2_amount),
+        token2.reserve,
+        token1.reserve,
+        cfg.lp_fee,
+    )?;
     Ok(Token2ForToken1PriceResponse { token1_amount })
 }
 
+pub fn query_simulate_swap(
+    deps: Deps,
+    env: Env,
+    pair_id: u64,
+    input_token: TokenSelect,
+    input_amount: Uint128,
+) -> StdResult<SimulateSwapResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let fee_token = get_fee_token(deps.storage, pair_id)?;
+    let curve = get_curve(deps.storage, pair_id, env.block.time.seconds())?;
+    match input_token {
+        TokenSelect::Token1 => simulate_swap(&cfg, &curve, &fee_token, &input_token, &token1, &token2, input_amount),
+        TokenSelect::Token2 => simulate_swap(&cfg, &curve, &fee_token, &input_token, &token2, &token1, input_amount),
+    }
+}
+
+pub fn query_token1_for_exact_token2(
+    deps: Deps,
+    env: Env,
+    pair_id: u64,
+    token2_amount: Uint128,
+) -> StdResult<Token1ForExactToken2Response> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let curve = get_curve(deps.storage, pair_id, env.block.time.seconds())?;
+    let swap_amount = get_curve_output_price(&curve, token2_amount, token1.reserve, token2.reserve, cfg.lp_fee)?;
+    Ok(Token1ForExactToken2Response {
+        token1_amount: get_input_with_in_kind_fee(&cfg, swap_amount),
+    })
+}
+
+pub fn query_token2_for_exact_token1(
+    deps: Deps,
+    env: Env,
+    pair_id: u64,
+    token1_amount: Uint128,
+) -> StdResult<Token2ForExactToken1Response> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let token1 = TOKEN1.load(deps.storage, pair_id)?;
+    let token2 = TOKEN2.load(deps.storage, pair_id)?;
+    let curve = get_curve(deps.storage, pair_id, env.block.time.seconds())?;
+    let swap_amount = get_curve_output_price(&curve, token1_amount, token2.reserve, token1.reserve, cfg.lp_fee)?;
+    Ok(Token2ForExactToken1Response {
+        token2_amount: get_input_with_in_kind_fee(&cfg, swap_amount),
+    })
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn query_pairs(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PairsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let pairs: StdResult<Vec<_>> = LP_TOKEN
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (pair_id, lp_token_address) = item?;
+            let token1 = TOKEN1.load(deps.storage, pair_id)?;
+            let token2 = TOKEN2.load(deps.storage, pair_id)?;
+            Ok(PairResponse {
+                pair_id,
+                token1_reserve: token1.reserve,
+                token1_denom: token1.denom,
+                token2_reserve: token2.reserve,
+                token2_denom: token2.denom,
+                lp_token_address: lp_token_address.to_string(),
+                bonding_contract_address: get_pair_bonding_address(deps.storage, &cfg, pair_id)?,
+                fee_token: get_fee_token(deps.storage, pair_id)?,
+                curve: get_curve(deps.storage, pair_id, env.block.time.seconds())?,
+            })
+        })
+        .collect();
+
+    Ok(PairsResponse { pairs: pairs? })
+}
+
+pub fn query_farm(deps: Deps, env: Env, pair_id: u64) -> StdResult<FarmResponse> {
+    let farm = load_farm(deps.storage, pair_id, env.block.time.seconds())
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(FarmResponse {
+        lp_token_address: LP_TOKEN.load(deps.storage, pair_id)?,
+        reward_rate: farm.reward_rate,
+        reward_per_share: farm.reward_per_share,
+        total_staked: farm.total_staked,
+        undistributed: farm.undistributed,
+        unclaimed: farm.unclaimed,
+    })
+}
+
+pub fn query_farm_staker(deps: Deps, env: Env, pair_id: u64, address: String) -> StdResult<FarmStakerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let farm = load_farm(deps.storage, pair_id, env.block.time.seconds())
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let staker = load_farm_staker(deps.storage, pair_id, &address, &farm)?;
+    Ok(FarmStakerResponse {
+        staked: staker.amount,
+        pending_reward: staker.pending_reward,
+    })
+}
+
+fn to_order_response(order_id: u64, order: LimitOrder) -> OrderResponse {
+    OrderResponse {
+        order_id,
+        owner: order.owner,
+        pair_id: order.pair_id,
+        input_token: order.input_token,
+        input_amount: order.input_amount,
+        min_output: order.min_output,
+    }
+}
+
+pub fn query_order(deps: Deps, order_id: u64) -> StdResult<OrderResponse> {
+    let order = load_order(deps.storage, order_id).map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(to_order_response(order_id, order))
+}
+
+pub fn query_orders(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<OrdersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let orders: StdResult<Vec<_>> = LIMIT_ORDERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (order_id, order) = item?;
+            Ok(to_order_response(order_id, order))
+        })
+        .collect();
+
+    Ok(OrdersResponse { orders: orders? })
+}
+
+pub fn query_orders_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<OrdersResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let orders: StdResult<Vec<_>> = OWNER_ORDERS
+        .prefix(owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|order_id| {
+            let order_id = order_id?;
+            Ok(to_order_response(order_id, LIMIT_ORDERS.load(deps.storage, order_id)?))
+        })
+        .collect();
+
+    Ok(OrdersResponse { orders: orders? })
+}
+
+pub fn query_amp(deps: Deps, env: Env, pair_id: u64) -> StdResult<AmpResponse> {
+    let ramp = load_amp_ramp(deps.storage, pair_id).map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(AmpResponse {
+        amp: stableswap::get_amp(&ramp, env.block.time.seconds()),
+        initial_amp: ramp.initial_amp,
+        initial_time: ramp.initial_time,
+        future_amp: ramp.future_amp,
+        future_time: ramp.future_time,
+    })
+}
+
+pub fn query_deposit(deps: Deps, pair_id: u64, address: String) -> StdResult<DepositResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let amount = LIQUIDITY_DEPOSITS
+        .may_load(deps.storage, (pair_id, address))?
+        .unwrap_or_default();
+    Ok(DepositResponse { amount })
+}
+
+pub fn query_protocol_fees(deps: Deps, pair_id: u64) -> StdResult<ProtocolFeesResponse> {
+    let fees = PROTOCOL_FEES.may_load(deps.storage, pair_id)?.unwrap_or_default();
+    Ok(ProtocolFeesResponse {
+        token1_amount: fees.token1_amount,
+        token2_amount: fees.token2_amount,
+    })
+}
+
+pub fn query_reserve_balances(deps: Deps, env: Env, pair_id: u64) -> StdResult<ReserveBalancesResponse> {
+    get_reserve_balances(deps, &env, pair_id).map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+pub fn query_twap(
+    deps: Deps,
+    env: Env,
+    pair_id: u64,
+    window_seconds: u64,
+) -> StdResult<TwapResponse> {
+    if window_seconds == 0 {
+        return Err(StdError::generic_err("Twap window must be positive"));
+    }
+    let now = env.block.time.seconds();
+    let current = get_price_cumulative(deps.storage, pair_id, now)?;
+
+    // Latest observation at least `window_seconds` old
+    let (_, start) = OBSERVATIONS
+        .prefix(pair_id)
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::inclusive(now.saturating_sub(window_seconds))),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .ok_or_else(|| StdError::generic_err("Not enough price history for the twap window"))?;
+
+    let elapsed = Uint256::from(now - start.timestamp);
+    Ok(TwapResponse {
+        token1_price: Decimal256::new(
+            (current.token1_price_cumulative - start.token1_price_cumulative).atomics() / elapsed,
+        ),
+        token2_price: Decimal256::new(
+            (current.token2_price_cumulative - start.token2_price_cumulative).atomics() / elapsed,
+        ),
+        start_timestamp: start.timestamp,
+        end_timestamp: now,
+    })
+}
+
+pub fn query_observations(
+    deps: Deps,
+    pair_id: u64,
+    start_before: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ObservationsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_before.map(Bound::exclusive);
+
+    let observations: StdResult<Vec<_>> = OBSERVATIONS
+        .prefix(pair_id)
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            let (timestamp, observation) = item?;
+            Ok(ObservationResponse {
+                timestamp,
+                token1_price_cumulative: observation.token1_price_cumulative,
+                token2_price_cumulative: observation.token2_price_cumulative,
+            })
+        })
+        .collect();
+
+    Ok(ObservationsResponse { observations: observations? })
+}
+
+pub fn query_daily_stats(
+    deps: Deps,
+    pair_id: u64,
+    start_day: Option<u64>,
+    end_day: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DailyStatsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_day.map(Bound::inclusive);
+    let end = end_day.map(Bound::inclusive);
+
+    let stats: StdResult<Vec<_>> = DAILY_STATS
+        .prefix(pair_id)
+        .range(deps.storage, start, end, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (day, stats) = item?;
+            Ok(DayStatsResponse {
+                day,
+                token1_sold: stats.token1_sold,
+                token2_bought: stats.token2_bought,
+                token2_sold: stats.token2_sold,
+                token1_bought: stats.token1_bought,
+                swap_count: stats.swap_count,
+                token1_treasury_fee: stats.token1_treasury_fee,
+                token2_treasury_fee: stats.token2_treasury_fee,
+                lp_minted: stats.lp_minted,
+                lp_burned: stats.lp_burned,
+                token1_reserve: stats.token1_reserve,
+                token2_reserve: stats.token2_reserve,
+            })
+        })
+        .collect();
+
+    Ok(DailyStatsResponse { stats: stats? })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id == FLASH_LOAN_REPLY_ID {
+        return reply_flash_loan(deps, env);
+    }
     if msg.id != INSTANTIATE_LP_TOKEN_REPLY_ID && msg.id != INSTANTIATE_BONDING_ID {
         return Err(ContractError::UnknownReplyId { id: msg.id });
     };
     let res = parse_reply_instantiate_data(msg.clone());
     match res {
         Ok(res) => {
+            let pending = PENDING_PAIR.load(deps.storage)?;
             if msg.id == INSTANTIATE_LP_TOKEN_REPLY_ID {
                 // Validate contract address
                 let cw20_addr = deps.api.addr_validate(&res.contract_address)?;
 
                 // Save gov token
-                LP_TOKEN.save(deps.storage, &cw20_addr)?;
+                LP_TOKEN.save(deps.storage, pending.pair_id, &cw20_addr)?;
+
+                if !pending.with_bonding {
+                    PENDING_PAIR.remove(deps.storage);
+                    return Ok(Response::new());
+                }
 
                 //Instantiate bonding contract
 
                 let cfg = CONFIG.load(deps.storage)?;
                 let mut sub_msg: Vec<SubMsg> = vec![];
+                let label = if pending.pair_id == DEFAULT_PAIR_ID {
+                    String::from("USDC_Fury_LP_Bonding")
+                } else {
+                    format!("Fury_LP_Bonding_{}", pending.pair_id)
+                };
 
                 sub_msg.push(SubMsg {
                     msg: WasmMsg::Instantiate {
                         code_id: cfg.bonding_code_id,
                         funds: vec![],
                         admin: Some(cfg.owner.clone().into()),
-                        label: String::from("USDC_Fury_LP_Bonding"),
+                        label,
                         msg: to_binary(&BondingInstantiateMsg {
                             owner: cfg.owner.clone(),
                             pool_address: env.contract.address.clone(),
@@ -931,10 +3685,15 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
                 Ok(Response::new().add_submessages(sub_msg))
             } else if msg.id == INSTANTIATE_BONDING_ID {
                 let bonding_addr = deps.api.addr_validate(&res.contract_address)?;
-                let mut cfg = CONFIG.load(deps.storage)?;
-                cfg.bonding_contract_address = bonding_addr;
-                // Save gov token
-                CONFIG.save(deps.storage, &cfg)?;
+                if pending.pair_id == DEFAULT_PAIR_ID {
+                    let mut cfg = CONFIG.load(deps.storage)?;
+                    cfg.bonding_contract_address = bonding_addr;
+                    // Save gov token
+                    CONFIG.save(deps.storage, &cfg)?;
+                } else {
+                    PAIR_BONDING.save(deps.storage, pending.pair_id, &bonding_addr)?;
+                }
+                PENDING_PAIR.remove(deps.storage);
                 Ok(Response::new())
             } else {
                 Ok(Response::new())
@@ -946,12 +3705,58 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
-        });
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous_version = check_migration_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let from_v1 = previous_version < MULTI_PAIR_VERSION.parse()?;
+    match msg {
+        MigrateMsg::Upgrade {} if !from_v1 => {}
+        MigrateMsg::FromV1 { lp_token_code_id } if from_v1 => migrate_from_v1(deps.storage, lp_token_code_id)?,
+        _ => {
+            return Err(ContractError::UnsupportedMigration {
+                previous_version: previous_version.to_string(),
+            })
+        }
     }
-    Ok(Response::default())
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
+}
+
+fn migrate_from_v1(storage: &mut dyn Storage, lp_token_code_id: u64) -> Result<(), ContractError> {
+    let config = CONFIG_V1.load(storage)?;
+    CONFIG.save(storage, &Config {
+        owner: config.owner,
+        lp_token_code_id,
+        bonding_code_id: config.bonding_code_id,
+        bonding_contract_address: config.bonding_contract_address,
+        fury_token_address: config.fury_token_address,
+        treasury_address: config.treasury_address,
+        usdc_denom: config.usdc_denom,
+        tx_fee: config.tx_fee,
+        platform_fee: config.platform_fee,
+        lp_fee: DEFAULT_LP_FEE,
+        fee_in_kind: false,
+        enabled: true,
+        max_price_impact_bps: MAX_BPS,
+        flash_loan_fee: DEFAULT_FLASH_LOAN_FEE,
+        order_bounty: DEFAULT_ORDER_BOUNTY,
+        lock_seconds: config.lock_seconds,
+        discount: config.discount,
+        daily_vesting_amount: config.daily_vesting_amount,
+    })?;
+
+    let lp_token_addr = LP_TOKEN_V1.load(storage)?;
+    let token1 = TOKEN1_V1.load(storage)?;
+    let token2 = TOKEN2_V1.load(storage)?;
+    LP_TOKEN.save(storage, DEFAULT_PAIR_ID, &lp_token_addr)?;
+    TOKEN1.save(storage, DEFAULT_PAIR_ID, &token1)?;
+    TOKEN2.save(storage, DEFAULT_PAIR_ID, &token2)?;
+    LP_TOKEN_V1.remove(storage);
+    TOKEN1_V1.remove(storage);
+    TOKEN2_V1.remove(storage);
+    NEXT_PAIR_ID.save(storage, &(DEFAULT_PAIR_ID + 1))?;
+    Ok(())
 }
@@ -1,11 +1,16 @@
 use cosmwasm_std::{StdError, Uint128, Addr};
 use thiserror::Error;
 
+use fanfury_common::CommonError;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error(transparent)]
+    Common(#[from] CommonError),
+
     #[error("{0}")]
     Cw20Error(#[from] cw20_base::ContractError),
 
@@ -52,6 +57,30 @@ pub enum ContractError {
     #[error("Swap min error: min: {min}, available: {available}")]
     SwapMinError { min: Uint128, available: Uint128 },
 
+    #[error("Swap max error: max: {max}, required: {required}")]
+    SwapMaxError { max: Uint128, required: Uint128 },
+
+    #[error("Invalid swap route")]
+    InvalidSwapRoute {},
+
+    #[error("Price impact too high: {price_impact_bps} bps, max: {max_price_impact_bps} bps")]
+    PriceImpactTooHigh { price_impact_bps: u64, max_price_impact_bps: u64 },
+
+    #[error("Invalid price impact cap: {max_price_impact_bps} bps")]
+    InvalidPriceImpactCap { max_price_impact_bps: u64 },
+
+    #[error("Invalid discount: {discount}")]
+    InvalidDiscount { discount: u64 },
+
+    #[error("Flash loan in progress")]
+    FlashLoanInProgress {},
+
+    #[error("Flash loan not repaid: required: {required}, available: {available}")]
+    FlashLoanNotRepaid { required: Uint128, available: Uint128 },
+
+    #[error("Insufficient stake: requested: {requested}, staked: {staked}")]
+    InsufficientStake { requested: Uint128, staked: Uint128 },
+
     #[error("MsgExpirationError")]
     MsgExpirationError {},
 
@@ -75,18 +104,51 @@ pub enum ContractError {
     #[error("InsufficientFundsToStake")]
     InsufficientFundsToStake {},
 
-    #[error("Amount of the native coin inputed is zero")]
-    NativeInputZero {},
-
-    #[error("Amount of the cw20 coin inputed is zero")]
-    Cw20InputZero {},
-
     #[error("Token type mismatch")]
     TokenTypeMismatch {},
 
-    #[error("Disabled")]
-    Disabled {},
+    #[error("Unknown pair: {pair_id}")]
+    UnknownPair { pair_id: u64 },
+
+    #[error("Pair {pair_id} has no liquidity to zap into")]
+    ZapIntoEmptyPair { pair_id: u64 },
+
+    #[error("Pair already exists: {pair_id}")]
+    PairAlreadyExists { pair_id: u64 },
+
+    #[error("The two tokens of a pair must differ")]
+    IdenticalTokens {},
+
+    #[error("Bonding needs FURY as token2")]
+    BondingRequiresFury {},
+
+    #[error("Invalid amplification: {amp}")]
+    InvalidAmp { amp: u64 },
+
+    #[error("Amplification ramps must last at least a day and change it at most tenfold")]
+    InvalidAmpRamp {},
+
+    #[error("Pair {pair_id} is not a stable pair")]
+    NotStablePair { pair_id: u64 },
+
+    #[error("Unknown order: {order_id}")]
+    UnknownOrder { order_id: u64 },
+
+    #[error("Invalid fee: {fee}")]
+    InvalidFee { fee: u64 },
+
+    #[error("The fee is taken from the input, fee_amount must be zero")]
+    FeeTakenInKind {},
+
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+
+    #[error("No migration from version {previous_version} with this message")]
+    UnsupportedMigration { previous_version: String },
+}
 
-    #[error("Cannot migrate from different contract type: {previous_contract}")]
-    CannotMigrate { previous_contract: String },
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
 }
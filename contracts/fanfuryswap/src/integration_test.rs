@@ -1,16 +1,25 @@
 #![cfg(test)]
 
 use std::borrow::BorrowMut;
+use std::str::FromStr;
 
-use cosmwasm_std::{coins, Addr, Coin, Empty, Uint128};
-use cw20::Expiration;
+use cosmwasm_std::{
+    coins, from_binary, to_binary, Addr, Binary, Coin, Decimal256, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, StdResult, Uint128, WasmMsg,
+};
 
 use crate::{error::ContractError, msg::ConfigResponse};
 use cw20::{Cw20Coin, Cw20Contract, Cw20ExecuteMsg, Denom};
-use cw_multi_test::{App, Contract, ContractWrapper, Executor};
-use fanfurybonding;
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use fanfury_common::CommonError;
 
-use crate::msg::{ExecuteMsg, InfoResponse, InstantiateMsg, QueryMsg, TokenSelect};
+use crate::msg::{
+    AmpResponse, Curve, DailyStatsResponse, FarmResponse, FarmStakerResponse, DepositResponse, ExecuteMsg, FlashLoanReceiverMsg, InfoResponse, InstantiateMsg, ObservationsResponse, OrdersResponse, PairsResponse,
+    ProtocolFeesResponse, QueryMsg, ReceiveMsg, ReserveBalancesResponse, SimulateSwapResponse, SwapOperation, Token1ForExactToken2Response, Token1ForToken2PriceResponse,
+    Token2ForExactToken1Response, TokenSelect, TwapResponse, AccessMsg, OwnershipResponse, Role, RoleMembersResponse,
+};
+
+const NATIVE_TOKEN_DENOM: &str = "usdc";
 
 fn mock_app() -> App {
     App::default()
@@ -44,10 +53,70 @@ pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+/// What the flash loan receiver does in its callback
+#[derive(serde::Serialize, serde::Deserialize)]
+enum FlashLoanAction {
+    Repay,
+    RepayWithoutFee,
+    Reenter,
+    FundFarm { fury_token: String },
+}
+
+fn flash_loan_receiver_execute(
+    _deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: FlashLoanReceiverMsg,
+) -> Result<Response, ContractError> {
+    let FlashLoanReceiverMsg::FlashLoanCallback { pair_id, denom, amount, fee, msg } = msg;
+    match from_binary(&msg)? {
+        FlashLoanAction::Repay => Ok(Response::new()
+            .add_message(fanfury_common::token::transfer_token_message(denom, amount + fee, info.sender)?)),
+        FlashLoanAction::RepayWithoutFee => Ok(Response::new()
+            .add_message(fanfury_common::token::transfer_token_message(denom, amount, info.sender)?)),
+        FlashLoanAction::Reenter => Ok(Response::new().add_message(WasmMsg::Execute {
+            contract_addr: info.sender.to_string(),
+            msg: to_binary(&ExecuteMsg::FlashLoan {
+                pair_id: Some(pair_id),
+                token: TokenSelect::Token1,
+                amount,
+                callback_msg: to_binary(&FlashLoanAction::Repay)?,
+            })?,
+            funds: vec![],
+        })),
+        FlashLoanAction::FundFarm { fury_token } => Ok(Response::new().add_message(WasmMsg::Execute {
+            contract_addr: fury_token,
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: info.sender.to_string(),
+                amount,
+                msg: to_binary(&ReceiveMsg::FundFarm { pair_id: Some(pair_id) })?,
+            })?,
+            funds: vec![],
+        })),
+    }
+}
+
+fn flash_loan_receiver_instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn flash_loan_receiver_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_binary(&Empty {})
+}
+
+pub fn contract_flash_loan_receiver() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        flash_loan_receiver_execute,
+        flash_loan_receiver_instantiate,
+        flash_loan_receiver_query,
+    );
+    Box::new(contract)
+}
+
 fn get_info(router: &App, contract_addr: &Addr) -> InfoResponse {
     router
         .wrap()
-        .query_wasm_smart(contract_addr, &QueryMsg::Info {})
+        .query_wasm_smart(contract_addr, &QueryMsg::Info { pair_id: None })
         .unwrap()
 }
 
@@ -84,6 +153,10 @@ fn create_amm(router: &mut App, owner: &Addr, cash: &Cw20Contract, native_denom:
         discount: 5u64,
         tx_fee: 3u64,
         platform_fee: 10u64,
+        lp_fee: None,
+        fee_in_kind: None,
+        guardian: None,
+        max_price_impact_bps: None,
         daily_vesting_amount: Uint128::from(10000000000u128)
     };
     router
@@ -91,6 +164,17 @@ fn create_amm(router: &mut App, owner: &Addr, cash: &Cw20Contract, native_denom:
         .unwrap()
 }
 
+// Sends FURY to the bonding contract of the pool so it can cover the bonds it sells
+fn fund_bonding(router: &mut App, contract_addr: &Addr, cash: &Cw20Contract, sender: &Addr, amount: Uint128) {
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: get_bonding_address(router, contract_addr).to_string(),
+        amount,
+    };
+    router
+        .execute_contract(sender.clone(), cash.addr(), &transfer_msg, &[])
+        .unwrap();
+}
+
 // CreateCW20 create new cw20 with given initial balance belonging to owner
 fn create_cw20(
     router: &mut App,
@@ -118,6 +202,52 @@ fn create_cw20(
     Cw20Contract(addr)
 }
 
+// Sets up an app with the given bank balances, FURY minted to `fury_holder` and an amm owned by "owner"
+// pairing FURY with usdc
+fn setup_amm(balances: &[(&Addr, Vec<Coin>)], fury_holder: &Addr, fury_amount: u128) -> (App, Cw20Contract, Addr) {
+    let mut router = mock_app();
+    router.init_modules(|router, _, storage| {
+        for (address, coins) in balances {
+            router.bank.init_balance(storage, address, coins.clone()).unwrap();
+        }
+    });
+
+    let owner = Addr::unchecked("owner");
+    let cw20_token = create_cw20(
+        &mut router,
+        fury_holder,
+        "fury".to_string(),
+        "FURY".to_string(),
+        Uint128::new(fury_amount),
+    );
+    let amm_addr = create_amm(&mut router, &owner, &cw20_token, NATIVE_TOKEN_DENOM.into());
+    (router, cw20_token, amm_addr)
+}
+
+// Approves and adds `amount` of both tokens to the default pair, paying `fee_amount` usdc on top
+fn add_liquidity(router: &mut App, sender: &Addr, cash: &Cw20Contract, contract_addr: &Addr, amount: u128, fee_amount: u128) {
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: contract_addr.to_string(),
+        amount: Uint128::new(amount),
+        expires: None,
+    };
+    router
+        .execute_contract(sender.clone(), cash.addr(), &allowance_msg, &[])
+        .unwrap();
+
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(amount),
+        min_liquidity: Uint128::new(amount),
+        max_token2: Uint128::new(amount),
+        fee_amount: Uint128::new(fee_amount),
+        expiration: None,
+    };
+    router
+        .execute_contract(sender.clone(), contract_addr.clone(), &add_liquidity_msg, &coins(amount + fee_amount, NATIVE_TOKEN_DENOM))
+        .unwrap();
+}
+
 fn bank_balance(router: &mut App, addr: &Addr, denom: String) -> Coin {
     router
         .wrap()
@@ -128,25 +258,8 @@ fn bank_balance(router: &mut App, addr: &Addr, denom: String) -> Coin {
 #[test]
 // receive cw20 tokens and release upon approval
 fn test_instantiate() {
-    let mut router = mock_app();
-
-    const NATIVE_TOKEN_DENOM: &str = "usdc";
-
     let owner = Addr::unchecked("owner");
-    let funds = coins(2000000, NATIVE_TOKEN_DENOM);
-    router.borrow_mut().init_modules(|router, _, storage| {
-        router.bank.init_balance(storage, &owner, funds).unwrap()
-    });
-
-    let cw20_token = create_cw20(
-        &mut router,
-        &owner,
-        "fury".to_string(),
-        "FURY".to_string(),
-        Uint128::new(2000000),
-    );
-
-    let amm_addr = create_amm(&mut router, &owner, &cw20_token, NATIVE_TOKEN_DENOM.into());
+    let (router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(2000000, NATIVE_TOKEN_DENOM))], &owner, 2000000);
 
     assert_ne!(cw20_token.addr(), amm_addr);
 
@@ -158,25 +271,8 @@ fn test_instantiate() {
 #[test]
 // receive cw20 tokens and release upon approval
 fn amm_add_and_remove_liquidity() {
-    let mut router = mock_app();
-
-    const NATIVE_TOKEN_DENOM: &str = "usdc";
-
     let owner = Addr::unchecked("owner");
-    let funds = coins(2000, NATIVE_TOKEN_DENOM);
-    router.borrow_mut().init_modules(|router, _, storage| {
-        router.bank.init_balance(storage, &owner, funds).unwrap()
-    });
-
-    let cw20_token = create_cw20(
-        &mut router,
-        &owner,
-        "fury".to_string(),
-        "FURY".to_string(),
-        Uint128::new(5000),
-    );
-
-    let amm_addr = create_amm(&mut router, &owner, &cw20_token, NATIVE_TOKEN_DENOM.into());
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(2000, NATIVE_TOKEN_DENOM))], &owner, 5000);
 
     assert_ne!(cw20_token.addr(), amm_addr);
 
@@ -189,33 +285,7 @@ fn amm_add_and_remove_liquidity() {
     assert_eq!(owner_balance, Uint128::new(5000));
 
     // send tokens to contract address
-    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
-        spender: amm_addr.to_string(),
-        amount: Uint128::new(100u128),
-        expires: None,
-    };
-    let _res = router
-        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
-        .unwrap();
-
-    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
-        token1_amount: Uint128::new(100),
-        min_liquidity: Uint128::new(100),
-        max_token2: Uint128::new(100),
-        fee_amount: Uint128::new(13),
-        expiration: None,
-    };
-    let _res = router
-        .execute_contract(
-            owner.clone(),
-            amm_addr.clone(),
-            &add_liquidity_msg,
-            &[Coin {
-                denom: NATIVE_TOKEN_DENOM.into(),
-                amount: Uint128::new(113),
-            }],
-        )
-        .unwrap();
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 100, 13);
 
     // ensure balances updated
     let owner_balance = cw20_token.balance::<_,_,Empty>(&router, owner.clone()).unwrap();
@@ -236,6 +306,7 @@ fn amm_add_and_remove_liquidity() {
         .unwrap();
 
     let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
         token1_amount: Uint128::new(50),
         min_liquidity: Uint128::new(50),
         max_token2: Uint128::new(51),
@@ -275,6 +346,7 @@ fn amm_add_and_remove_liquidity() {
         .unwrap();
 
     let remove_liquidity_msg = ExecuteMsg::RemoveLiquidity {
+        pair_id: None,
         amount: Uint128::new(50),
         min_token1: Uint128::new(50),
         min_token2: Uint128::new(50),
@@ -312,6 +384,7 @@ fn amm_add_and_remove_liquidity() {
         .unwrap();
 
     let remove_liquidity_msg = ExecuteMsg::RemoveLiquidity {
+        pair_id: None,
         amount: Uint128::new(100),
         min_token1: Uint128::new(100),
         min_token2: Uint128::new(100),
@@ -341,30 +414,8 @@ fn amm_add_and_remove_liquidity() {
 
 #[test]
 fn swap_tokens_happy_path() {
-    let mut router = mock_app();
-
-    const NATIVE_TOKEN_DENOM: &str = "usdc";
-
     let owner = Addr::unchecked("owner");
-    let funds = coins(50000, NATIVE_TOKEN_DENOM);
-    router.borrow_mut().init_modules(|router, _, storage| {
-        router.bank.init_balance(storage, &owner, funds).unwrap()
-    });
-
-    let cw20_token = create_cw20(
-        &mut router,
-        &owner,
-        "fury".to_string(),
-        "FURY".to_string(),
-        Uint128::new(50000),
-    );
-
-    let amm_addr = create_amm(
-        &mut router,
-        &owner,
-        &cw20_token,
-        NATIVE_TOKEN_DENOM.to_string(),
-    );
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
 
     assert_ne!(cw20_token.addr(), amm_addr);
 
@@ -373,33 +424,7 @@ fn swap_tokens_happy_path() {
     assert_eq!(owner_balance, Uint128::new(50000));
 
     // send tokens to contract address
-    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
-        spender: amm_addr.to_string(),
-        amount: Uint128::new(20000u128),
-        expires: None,
-    };
-    let _res = router
-        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
-        .unwrap();
-
-    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
-        token1_amount: Uint128::new(20000),
-        min_liquidity: Uint128::new(20000),
-        max_token2: Uint128::new(20000),
-        fee_amount: Uint128::new(520),
-        expiration: None,
-    };
-    let res = router
-        .execute_contract(
-            owner.clone(),
-            amm_addr.clone(),
-            &add_liquidity_msg,
-            &[Coin {
-                denom: NATIVE_TOKEN_DENOM.into(),
-                amount: Uint128::new(20520),
-            }],
-        )
-        .unwrap();
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 20000, 520);
     
     let info = get_info(&router, &amm_addr);
     assert_eq!(info.token1_reserve, Uint128::new(20000));
@@ -412,13 +437,15 @@ fn swap_tokens_happy_path() {
     });
 
     let add_liquidity_msg = ExecuteMsg::Swap {
+        pair_id: None,
         input_token: TokenSelect::Token1,
         input_amount: Uint128::new(1000),
         min_output: Uint128::new(949),
         fee_amount: Uint128::new(13),
         expiration: None,
+        recipient: None,
     };
-    let res = router
+    router
         .execute_contract(
             buyer.clone(),
             amm_addr.clone(),
@@ -444,11 +471,13 @@ fn swap_tokens_happy_path() {
     assert_eq!(balance.amount, Uint128::new(18987));
 
     let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
         input_token: TokenSelect::Token1,
         input_amount: Uint128::new(5000),
         min_output: Uint128::new(3654),
         fee_amount: Uint128::new(65),
         expiration: None,
+        recipient: None,
     };
     let _res = router
         .execute_contract(
@@ -471,61 +500,19 @@ fn swap_tokens_happy_path() {
 
 #[test]
 fn bonding() {
-    let mut router = mock_app();
-
-    const NATIVE_TOKEN_DENOM: &str = "usdc";
-
     let owner = Addr::unchecked("owner");
     let bonder = Addr::unchecked("bonder");
-    let funds = coins(200000, NATIVE_TOKEN_DENOM);
-    router.borrow_mut().init_modules(|router, _, storage| {
-        router.bank.init_balance(storage, &bonder, funds).unwrap()
-    });
-
-    let token = create_cw20(
-        &mut router,
-        &bonder,
-        "fury".to_string(),
-        "FURY".to_string(),
-        Uint128::new(500000),
-    );
-
-    let amm = create_amm(&mut router, &owner, &token, NATIVE_TOKEN_DENOM.to_string());
+    let (mut router, token, amm) = setup_amm(&[(&bonder, coins(200000, NATIVE_TOKEN_DENOM))], &bonder, 500000);
+    fund_bonding(&mut router, &amm, &token, &bonder, Uint128::new(250000));
 
     // Add initial liquidity to pools
-    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
-        spender: amm.to_string(),
-        amount: Uint128::new(100000),
-        expires: None,
-    };
-    let _res = router
-        .execute_contract(bonder.clone(), token.addr(), &allowance_msg, &[])
-        .unwrap();
-
-    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
-        token1_amount: Uint128::new(100000),
-        min_liquidity: Uint128::new(100000),
-        max_token2: Uint128::new(100000),
-        fee_amount: Uint128::new(2600),
-        expiration: None,
-    };
-    let res = router
-        .execute_contract(
-            bonder.clone(),
-            amm.clone(),
-            &add_liquidity_msg,
-            &[Coin {
-                denom: NATIVE_TOKEN_DENOM.into(),
-                amount: Uint128::new(102600),
-            }],
-        )
-        .unwrap();
+    add_liquidity(&mut router, &bonder, &token, &amm, 100000, 2600);
     
     
     
     // ensure balances updated
     let token1_balance = token.balance::<_,_,Empty>(&router, bonder.clone()).unwrap();
-    assert_eq!(token1_balance, Uint128::new(400000));
+    assert_eq!(token1_balance, Uint128::new(150000));
 
     
     // check bonding contract info
@@ -533,7 +520,8 @@ fn bonding() {
         .wrap()
         .query_wasm_smart(get_bonding_address(&router, &amm), &fanfurybonding::msg::QueryMsg::Config { })
         .unwrap();
-    // println!("{:?}", res);
+    assert_eq!(res.pool_address, amm);
+    assert_eq!(res.fury_token_address, token.addr());
 
 
     // Check bonding record
@@ -541,4 +529,3014 @@ fn bonding() {
     // println!("{:?}", record);
 
     assert_eq!(record.list[0].amount, Uint128::new(201005));
-}
\ No newline at end of file
+    assert_eq!(record.unbond_amount, Uint128::zero());
+    assert_eq!(record.locked_amount, Uint128::new(201005));
+
+    // the bond vests linearly over the 7 lock seconds
+    router.update_block(|block| block.time = block.time.plus_seconds(3));
+    let record = get_bonding_info(&router, &amm, &bonder);
+    let vested = Uint128::new(201005).multiply_ratio(3u128, 7u128);
+    assert_eq!(record.vested_amount, vested);
+    assert_eq!(record.unbond_amount, vested);
+    assert_eq!(record.locked_amount, Uint128::new(201005) - vested);
+    assert!(record.fee_amount > Uint128::zero());
+
+    // the owner cannot withdraw the FURY owed to the bonder
+    let bonding = get_bonding_address(&router, &amm);
+    let solvency: fanfurybonding::msg::SolvencyResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::Solvency {})
+        .unwrap();
+    assert_eq!(solvency.balance, Uint128::new(250000));
+    assert_eq!(solvency.liabilities, Uint128::new(201005));
+    assert_eq!(solvency.surplus, Uint128::new(48995));
+    let err = router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::Withdraw { amount: Uint128::new(48996) }, &[])
+        .unwrap_err();
+    assert_eq!(
+        fanfurybonding::ContractError::Insolvent { balance: Uint128::new(201004), liabilities: Uint128::new(201005) },
+        err.downcast().unwrap()
+    );
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::Withdraw { amount: Uint128::new(48995) }, &[])
+        .unwrap();
+
+    let unbond_msg = fanfurybonding::msg::ExecuteMsg::Unbond {};
+    router
+        .execute_contract(bonder.clone(), bonding.clone(), &unbond_msg, &coins(record.fee_amount.u128(), NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let token1_balance = token.balance::<_,_,Empty>(&router, bonder.clone()).unwrap();
+    assert_eq!(token1_balance, Uint128::new(150000) + vested);
+    let solvency: fanfurybonding::msg::SolvencyResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::Solvency {})
+        .unwrap();
+    assert_eq!(solvency.liabilities, Uint128::new(201005) - vested);
+    assert_eq!(solvency.surplus, Uint128::zero());
+
+    // the vested part is claimed once
+    let record = get_bonding_info(&router, &amm, &bonder);
+    assert_eq!(record.list[0].claimed, vested);
+    assert_eq!(record.unbond_amount, Uint128::zero());
+    let err = router
+        .execute_contract(bonder.clone(), bonding.clone(), &unbond_msg, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::NothingToUnbond {}, err.downcast().unwrap());
+
+    // the rest is paid at maturity and the bond is dropped
+    router.update_block(|block| block.time = block.time.plus_seconds(4));
+    let record = get_bonding_info(&router, &amm, &bonder);
+    assert_eq!(record.unbond_amount, Uint128::new(201005) - vested);
+    assert_eq!(record.locked_amount, Uint128::zero());
+    router
+        .execute_contract(bonder.clone(), bonding.clone(), &unbond_msg, &coins(record.fee_amount.u128(), NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let token1_balance = token.balance::<_,_,Empty>(&router, bonder.clone()).unwrap();
+    assert_eq!(token1_balance, Uint128::new(351005));
+    let record = get_bonding_info(&router, &amm, &bonder);
+    assert!(record.list.is_empty());
+}
+#[test]
+fn bond_discount_follows_debt() {
+    let owner = Addr::unchecked("owner");
+    let bonder = Addr::unchecked("bonder");
+    let (mut router, token, amm) = setup_amm(&[(&bonder, coins(200000, NATIVE_TOKEN_DENOM))], &bonder, 500000);
+    let bonding = get_bonding_address(&router, &amm);
+    fund_bonding(&mut router, &amm, &token, &bonder, Uint128::new(250000));
+
+    // a small daily vesting amount makes the debt ratio move
+    let update_config_msg = fanfurybonding::msg::ExecuteMsg::UpdateConfig {
+        treasury_address: owner.clone(),
+        lock_seconds: 7u64,
+        discount: 50u64,
+        tx_fee: 3u64,
+        platform_fee: 10u64,
+        daily_vesting_amount: Uint128::new(1000000),
+    };
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &update_config_msg, &[])
+        .unwrap();
+
+    // only the owner sets the pricing, within valid bounds
+    let pricing_msg = fanfurybonding::msg::ExecuteMsg::UpdateBondPricing {
+        market_id: None,
+        control_variable: 100,
+        min_discount: 10,
+        max_discount: Some(40),
+        decay_seconds: 100,
+    };
+    let err = router
+        .execute_contract(bonder.clone(), bonding.clone(), &pricing_msg, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::UpdateBondPricing {
+            market_id: None,
+            control_variable: 100,
+            min_discount: 60,
+            max_discount: Some(40),
+            decay_seconds: 100,
+        }, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::InvalidDiscount { discount: 60 }, err.downcast().unwrap());
+    let err = router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::UpdateBondPricing {
+            market_id: None,
+            control_variable: 100,
+            min_discount: 10,
+            max_discount: Some(40),
+            decay_seconds: 0,
+        }, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::InvalidInput {}, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &pricing_msg, &[])
+        .unwrap();
+
+    // the cap leaves the terms of the default market as they are
+    let config: fanfurybonding::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.discount, 50);
+    let market: fanfurybonding::msg::BondMarketResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondMarket { market_id: 0 })
+        .unwrap();
+    assert_eq!(market.discount, 50);
+
+    // bond through the pool at the maximum discount
+    add_liquidity(&mut router, &bonder, &token, &amm, 100000, 2600);
+    let record = get_bonding_info(&router, &amm, &bonder);
+    assert_eq!(record.list[0].amount, Uint128::new(208333));
+
+    // the debt shrinks the discount of the next bond
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: None })
+        .unwrap();
+    assert_eq!(price.total_debt, Uint128::new(208333));
+    assert_eq!(price.debt_ratio, Uint128::new(208));
+    assert_eq!(price.max_discount, 40);
+    assert_eq!(price.discount, 20);
+    assert_eq!(price.bond_price, price.market_price.multiply_ratio(980u128, 1000u128));
+
+    // and recovers as the debt decays
+    router.update_block(|block| block.time = block.time.plus_seconds(50));
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: None })
+        .unwrap();
+    assert_eq!(price.total_debt, Uint128::new(104167));
+    assert_eq!(price.discount, 30);
+
+    router.update_block(|block| block.time = block.time.plus_seconds(50));
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: None })
+        .unwrap();
+    assert_eq!(price.total_debt, Uint128::zero());
+    assert_eq!(price.discount, 40);
+}
+#[test]
+fn bond_markets() {
+    let owner = Addr::unchecked("owner");
+    let bonder = Addr::unchecked("bonder");
+    let (mut router, token, amm) = setup_amm(&[(&bonder, coins(300000, NATIVE_TOKEN_DENOM))], &bonder, 500000);
+    fund_bonding(&mut router, &amm, &token, &bonder, Uint128::new(250000));
+
+    add_liquidity(&mut router, &bonder, &token, &amm, 100000, 2600);
+
+    // a native bonding contract priced by the pool
+    let bonding_id = router.store_code(contract_bonding());
+    let bonding = router
+        .instantiate_contract(bonding_id, owner.clone(), &fanfurybonding::msg::InstantiateMsg {
+            owner: owner.clone(),
+            pool_address: amm.clone(),
+            treasury_address: owner.clone(),
+            fury_token_address: token.addr(),
+            usdc_denom: NATIVE_TOKEN_DENOM.to_string(),
+            lock_seconds: 7u64,
+            discount: 5u64,
+            tx_fee: 3u64,
+            platform_fee: 10u64,
+            daily_vesting_amount: Uint128::from(10000000000u128),
+            is_native_bonding: true
+        }, &[], "bonding", None)
+        .unwrap();
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: bonding.to_string(),
+        amount: Uint128::new(10000),
+    };
+    router
+        .execute_contract(bonder.clone(), token.addr(), &transfer_msg, &[])
+        .unwrap();
+
+    // only the owner opens markets, with a valid discount
+    let now = router.block_info().time.seconds();
+    let create_market_msg = fanfurybonding::msg::ExecuteMsg::CreateBondMarket {
+        lock_seconds: 30u64,
+        discount: 120u64,
+        capacity: Some(Uint128::new(5000)),
+        open_time: None,
+        close_time: Some(now + 100),
+    };
+    let err = router
+        .execute_contract(bonder.clone(), bonding.clone(), &create_market_msg, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::CreateBondMarket {
+            lock_seconds: 30u64,
+            discount: 1000u64,
+            capacity: None,
+            open_time: None,
+            close_time: None,
+        }, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::InvalidDiscount { discount: 1000 }, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &create_market_msg, &[])
+        .unwrap();
+
+    let markets: fanfurybonding::msg::BondMarketsResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondMarkets { start_after: None, limit: None })
+        .unwrap();
+    assert_eq!(markets.markets.len(), 2);
+    assert_eq!(markets.markets[0].market_id, 0);
+    assert_eq!(markets.markets[0].lock_seconds, 7);
+    assert_eq!(markets.markets[0].discount, 5);
+    assert_eq!(markets.markets[1].market_id, 1);
+    assert_eq!(markets.markets[1].discount, 120);
+    let markets: fanfurybonding::msg::BondMarketsResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondMarkets { start_after: Some(0), limit: None })
+        .unwrap();
+    assert_eq!(markets.markets.len(), 1);
+
+    // bonds are priced and locked with the terms of their market
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: Some(1) })
+        .unwrap();
+    assert_eq!(price.discount, 120);
+    let bond_msg = fanfurybonding::msg::ExecuteMsg::Bond { market_id: Some(1), amount: Uint128::new(1000) };
+    router
+        .execute_contract(bonder.clone(), bonding.clone(), &bond_msg, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let record: fanfurybonding::msg::BondStateResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondState { address: bonder.clone() })
+        .unwrap();
+    assert_eq!(record.list[0].market_id, 1);
+    assert_eq!(record.list[0].timestamp, now + 30);
+    let market: fanfurybonding::msg::BondMarketResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondMarket { market_id: 1 })
+        .unwrap();
+    assert_eq!(market.sold_amount, record.list[0].amount);
+
+    let err = router
+        .execute_contract(bonder.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::Bond { market_id: Some(5), amount: Uint128::new(1000) }, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::UnknownBondMarket { market_id: 5 }, err.downcast().unwrap());
+
+    // a market cannot sell past its capacity
+    let err = router
+        .execute_contract(bonder.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::Bond { market_id: Some(1), amount: Uint128::new(5000) }, &coins(5065, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::BondMarketCapacityExceeded { market_id: 1 }, err.downcast().unwrap());
+
+    // nor after it closed
+    router.update_block(|block| block.time = block.time.plus_seconds(100));
+    let err = router
+        .execute_contract(bonder.clone(), bonding.clone(), &bond_msg, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::BondMarketClosed { market_id: 1 }, err.downcast().unwrap());
+
+    // while the default market keeps its own terms
+    router
+        .execute_contract(bonder.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::Bond { market_id: None, amount: Uint128::new(1000) }, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let record: fanfurybonding::msg::BondStateResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondState { address: bonder.clone() })
+        .unwrap();
+    assert_eq!(record.list[1].market_id, 0);
+    assert_eq!(record.list[1].timestamp, now + 107);
+}
+#[test]
+fn bond_pricing_per_market() {
+    let owner = Addr::unchecked("owner");
+    let bonder = Addr::unchecked("bonder");
+    let (mut router, token, amm) = setup_amm(&[(&bonder, coins(300000, NATIVE_TOKEN_DENOM))], &bonder, 500000);
+    fund_bonding(&mut router, &amm, &token, &bonder, Uint128::new(250000));
+
+    add_liquidity(&mut router, &bonder, &token, &amm, 100000, 2600);
+
+    // a native bonding contract with a small daily vesting amount, so the debt ratio moves
+    let bonding_id = router.store_code(contract_bonding());
+    let bonding = router
+        .instantiate_contract(bonding_id, owner.clone(), &fanfurybonding::msg::InstantiateMsg {
+            owner: owner.clone(),
+            pool_address: amm.clone(),
+            treasury_address: owner.clone(),
+            fury_token_address: token.addr(),
+            usdc_denom: NATIVE_TOKEN_DENOM.to_string(),
+            lock_seconds: 7u64,
+            discount: 5u64,
+            tx_fee: 3u64,
+            platform_fee: 10u64,
+            daily_vesting_amount: Uint128::new(10000),
+            is_native_bonding: true
+        }, &[], "bonding", None)
+        .unwrap();
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: bonding.to_string(),
+        amount: Uint128::new(10000),
+    };
+    router
+        .execute_contract(bonder.clone(), token.addr(), &transfer_msg, &[])
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::CreateBondMarket {
+            lock_seconds: 30u64,
+            discount: 120u64,
+            capacity: None,
+            open_time: None,
+            close_time: None,
+        }, &[])
+        .unwrap();
+
+    // a floor above the discount of the market is refused rather than dropped
+    let err = router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::UpdateBondPricing {
+            market_id: Some(0),
+            control_variable: 1000,
+            min_discount: 10,
+            max_discount: None,
+            decay_seconds: 100,
+        }, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::InvalidDiscount { discount: 10 }, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::UpdateBondPricing {
+            market_id: Some(1),
+            control_variable: 1000,
+            min_discount: 100,
+            max_discount: None,
+            decay_seconds: 100,
+        }, &[])
+        .unwrap();
+
+    // a bond in the new market adds to its debt only
+    router
+        .execute_contract(bonder.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::Bond { market_id: Some(1), amount: Uint128::new(1000) }, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let record: fanfurybonding::msg::BondStateResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondState { address: bonder.clone() })
+        .unwrap();
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: Some(1) })
+        .unwrap();
+    assert_eq!(price.total_debt, record.list[0].amount);
+    assert_eq!(price.min_discount, 100);
+    assert_eq!(price.discount, 100);
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: None })
+        .unwrap();
+    assert_eq!(price.total_debt, Uint128::zero());
+    assert_eq!(price.min_discount, 0);
+    assert_eq!(price.discount, 5);
+
+    // and a bond in the default market leaves the debt of the new one as it is
+    router
+        .execute_contract(bonder.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::Bond { market_id: None, amount: Uint128::new(1000) }, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let record: fanfurybonding::msg::BondStateResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondState { address: bonder.clone() })
+        .unwrap();
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: None })
+        .unwrap();
+    assert_eq!(price.total_debt, record.list[1].amount);
+    assert_eq!(price.discount, 5);
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: Some(1) })
+        .unwrap();
+    assert_eq!(price.total_debt, record.list[0].amount);
+    assert_eq!(price.discount, 100);
+}
+#[test]
+fn rolling_bond_capacity() {
+    let owner = Addr::unchecked("owner");
+    let bonder = Addr::unchecked("bonder");
+    let (mut router, token, amm) = setup_amm(&[(&bonder, coins(1000000, NATIVE_TOKEN_DENOM))], &bonder, 1000000);
+    let bonding = get_bonding_address(&router, &amm);
+    fund_bonding(&mut router, &amm, &token, &bonder, Uint128::new(600000));
+
+    let update_config_msg = fanfurybonding::msg::ExecuteMsg::UpdateConfig {
+        treasury_address: owner.clone(),
+        lock_seconds: 7u64,
+        discount: 5u64,
+        tx_fee: 3u64,
+        platform_fee: 10u64,
+        daily_vesting_amount: Uint128::new(300000),
+    };
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &update_config_msg, &[])
+        .unwrap();
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert_eq!(capacity.remaining, Uint128::new(300000));
+    assert_eq!(capacity.window_seconds, 86400);
+    assert!(!capacity.in_usdc);
+
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm.to_string(),
+        amount: Uint128::new(400000),
+        expires: None,
+    };
+    router
+        .execute_contract(bonder.clone(), token.addr(), &allowance_msg, &[])
+        .unwrap();
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(100000),
+        min_liquidity: Uint128::new(100000),
+        max_token2: Uint128::new(100000),
+        fee_amount: Uint128::new(2600),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            bonder.clone(),
+            amm.clone(),
+            &add_liquidity_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(102600),
+            }],
+        )
+        .unwrap();
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert_eq!(capacity.remaining, Uint128::new(98995));
+
+    // the next bond does not fit in what is left of the window
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(50000),
+        min_liquidity: Uint128::new(1),
+        max_token2: Uint128::new(60000),
+        fee_amount: Uint128::new(1300),
+        expiration: None,
+    };
+    let err = router
+        .execute_contract(bonder.clone(), amm.clone(), &add_liquidity_msg, &coins(51300, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::MaxBondingExceed {}, err.downcast().unwrap());
+
+    // half a window later half of the window amount is back
+    router.update_block(|block| block.time = block.time.plus_seconds(43200));
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert_eq!(capacity.remaining, Uint128::new(248995));
+    router
+        .execute_contract(bonder.clone(), amm.clone(), &add_liquidity_msg, &coins(51300, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let record = get_bonding_info(&router, &amm, &bonder);
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert_eq!(capacity.remaining, Uint128::new(248995) - record.list[1].amount);
+
+    // unused capacity is only carried up to the owner's limit
+    router.update_block(|block| block.time = block.time.plus_seconds(86400 * 2));
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert_eq!(capacity.remaining, Uint128::new(300000));
+
+    let update_capacity_msg = fanfurybonding::msg::ExecuteMsg::UpdateCapacity {
+        window_seconds: 86400,
+        max_carry_over: Uint128::new(100000),
+        in_usdc: false,
+        usdc_window_amount: Uint128::zero(),
+    };
+    let err = router
+        .execute_contract(bonder.clone(), bonding.clone(), &update_capacity_msg, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::Unauthorized {}, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &update_capacity_msg, &[])
+        .unwrap();
+    router.update_block(|block| block.time = block.time.plus_seconds(86400));
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert_eq!(capacity.remaining, Uint128::new(400000));
+
+    // switching to usdc starts a window of the usdc amount, which cannot be empty
+    let err = router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::UpdateCapacity {
+            window_seconds: 86400,
+            max_carry_over: Uint128::zero(),
+            in_usdc: true,
+            usdc_window_amount: Uint128::zero(),
+        }, &[])
+        .unwrap_err();
+    assert_eq!(fanfurybonding::ContractError::InvalidInput {}, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), bonding.clone(), &fanfurybonding::msg::ExecuteMsg::UpdateCapacity {
+            window_seconds: 86400,
+            max_carry_over: Uint128::zero(),
+            in_usdc: true,
+            usdc_window_amount: Uint128::new(200000),
+        }, &[])
+        .unwrap();
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert!(capacity.in_usdc);
+    assert_eq!(capacity.remaining, Uint128::new(200000));
+    assert_eq!(capacity.window_amount, Uint128::new(200000));
+
+    // where the FURY deposited counts at the pool price, as the usdc paid for a native bond does
+    let res = router
+        .execute_contract(bonder.clone(), amm.clone(), &add_liquidity_msg, &coins(51300, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let bond_fury_amount: Uint128 = res.events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "bond_fury_amount")
+        .unwrap()
+        .value
+        .parse()
+        .unwrap();
+    let price: fanfurybonding::msg::BondPriceResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::BondPrice { market_id: None })
+        .unwrap();
+    let capacity: fanfurybonding::msg::RemainingCapacityResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding, &fanfurybonding::msg::QueryMsg::RemainingCapacity {})
+        .unwrap();
+    assert_eq!(
+        capacity.remaining,
+        Uint128::new(200000) - bond_fury_amount.multiply_ratio(price.market_price, 1000000u128)
+    );
+}
+#[test]
+fn create_pair_and_swap() {
+    const ATOM_DENOM: &str = "uatom";
+
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(
+        &[
+            (&owner, vec![
+                Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(50000) },
+                Coin { denom: ATOM_DENOM.into(), amount: Uint128::new(50000) },
+            ]),
+        ],
+        &owner,
+        50000,
+    );
+
+    // only the owner can register pairs
+    let create_pair_msg = ExecuteMsg::CreatePair {
+        token1_denom: Denom::Native(ATOM_DENOM.into()),
+        token2_denom: Denom::Cw20(cw20_token.addr()),
+        with_bonding: true,
+        fee_token: None,
+        curve: None,
+    };
+    let err = router
+        .execute_contract(Addr::unchecked("buyer"), amm_addr.clone(), &create_pair_msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // lp bonds are paid in FURY, so a bonded pair needs FURY as token2
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::CreatePair {
+            token1_denom: Denom::Cw20(cw20_token.addr()),
+            token2_denom: Denom::Native(ATOM_DENOM.into()),
+            with_bonding: true,
+            fee_token: None,
+            curve: None,
+        }, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::BondingRequiresFury {}, err.downcast().unwrap());
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &create_pair_msg, &[])
+        .unwrap();
+
+    // the same pair cannot be registered twice
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &create_pair_msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::PairAlreadyExists { pair_id: 1 }, err.downcast().unwrap());
+
+    let pairs: PairsResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Pairs { start_after: None, limit: None })
+        .unwrap();
+    assert_eq!(pairs.pairs.len(), 2);
+    assert_eq!(pairs.pairs[0].token1_denom, Denom::Native(NATIVE_TOKEN_DENOM.into()));
+    assert_eq!(pairs.pairs[1].pair_id, 1);
+    assert_eq!(pairs.pairs[1].token1_denom, Denom::Native(ATOM_DENOM.into()));
+    assert_ne!(pairs.pairs[0].lp_token_address, pairs.pairs[1].lp_token_address);
+    assert!(pairs.pairs[1].bonding_contract_address.is_some());
+    assert_ne!(pairs.pairs[0].bonding_contract_address, pairs.pairs[1].bonding_contract_address);
+
+    let pairs: PairsResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Pairs { start_after: Some(0), limit: None })
+        .unwrap();
+    assert_eq!(pairs.pairs.len(), 1);
+    assert_eq!(pairs.pairs[0].pair_id, 1);
+
+    // provide liquidity to the new pair
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(20000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
+        .unwrap();
+
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: Some(1),
+        token1_amount: Uint128::new(20000),
+        min_liquidity: Uint128::new(20000),
+        max_token2: Uint128::new(20000),
+        fee_amount: Uint128::new(520),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &add_liquidity_msg,
+            &[Coin {
+                denom: ATOM_DENOM.into(),
+                amount: Uint128::new(20520),
+            }],
+        )
+        .unwrap();
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(info.token1_reserve, Uint128::new(20000));
+    assert_eq!(info.token2_reserve, Uint128::new(20000));
+    assert_eq!(info.lp_token_supply, Uint128::new(20000));
+
+    // the default pair is untouched
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::zero());
+    assert_eq!(info.token2_reserve, Uint128::zero());
+
+    let buyer = Addr::unchecked("buyer");
+    router.borrow_mut().init_modules(|router, _, storage| {
+        router.bank.init_balance(storage, &buyer, coins(20000, ATOM_DENOM)).unwrap()
+    });
+
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: Some(1),
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::new(949),
+        fee_amount: Uint128::new(13),
+        expiration: None,
+        recipient: None,
+    };
+    router
+        .execute_contract(
+            buyer.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: ATOM_DENOM.into(),
+                amount: Uint128::new(1013),
+            }],
+        )
+        .unwrap();
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(info.token1_reserve, Uint128::new(21000));
+    assert_eq!(info.token2_reserve, Uint128::new(19051));
+
+    let buyer_balance = cw20_token.balance::<_,_,Empty>(&router, buyer.clone()).unwrap();
+    assert_eq!(buyer_balance, Uint128::new(949));
+
+    // the fee is paid in the pair's token1
+    let balance: Coin = bank_balance(&mut router, &owner, ATOM_DENOM.to_string());
+    assert_eq!(balance.amount, Uint128::new(50000 - 20520 + 520 + 13));
+}
+
+#[test]
+fn receive_swap_and_add_liquidity() {
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let (mut router, cw20_token, amm_addr) = setup_amm(
+        &[
+            (&owner, coins(50000, NATIVE_TOKEN_DENOM)),
+            (&user, coins(50000, NATIVE_TOKEN_DENOM)),
+        ],
+        &owner,
+        50000,
+    );
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &Cw20ExecuteMsg::Transfer {
+            recipient: user.to_string(),
+            amount: Uint128::new(10000),
+        }, &[])
+        .unwrap();
+
+    // owner provides the initial liquidity with the fury sent through the hook
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(20000),
+        msg: to_binary(&ReceiveMsg::AddLiquidity { pair_id: None }).unwrap(),
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &send_msg, &[])
+        .unwrap();
+    let deposit: DepositResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Deposit { pair_id: None, address: owner.to_string() })
+        .unwrap();
+    assert_eq!(deposit.amount, Uint128::new(20000));
+
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(20000),
+        min_liquidity: Uint128::new(20000),
+        max_token2: Uint128::new(20000),
+        fee_amount: Uint128::new(520),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &add_liquidity_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(20520),
+            }],
+        )
+        .unwrap();
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20000));
+    assert_eq!(info.token2_reserve, Uint128::new(20000));
+    let deposit: DepositResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Deposit { pair_id: None, address: owner.to_string() })
+        .unwrap();
+    assert_eq!(deposit.amount, Uint128::zero());
+
+    // user sells fury without any allowance, the fee is taken from the output
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(1000),
+        msg: to_binary(&ReceiveMsg::Swap { pair_id: None, min_output: Uint128::new(937), expiration: None, recipient: None }).unwrap(),
+    };
+    router
+        .execute_contract(user.clone(), cw20_token.addr(), &send_msg, &[])
+        .unwrap();
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(19051));
+    assert_eq!(info.token2_reserve, Uint128::new(21000));
+    // 949 bought, 12 of it is the fee
+    let balance: Coin = bank_balance(&mut router, &user, NATIVE_TOKEN_DENOM.to_string());
+    assert_eq!(balance.amount, Uint128::new(50937));
+    let user_balance = cw20_token.balance::<_,_,Empty>(&router, user.clone()).unwrap();
+    assert_eq!(user_balance, Uint128::new(9000));
+
+    // min output is checked against the amount after fee
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(1000),
+        msg: to_binary(&ReceiveMsg::Swap { pair_id: None, min_output: Uint128::new(900), expiration: None, recipient: None }).unwrap(),
+    };
+    let err = router
+        .execute_contract(user.clone(), cw20_token.addr(), &send_msg, &[])
+        .unwrap_err();
+    assert!(matches!(err.downcast().unwrap(), ContractError::SwapMinError { .. }));
+
+    // a deposit that is not used can be withdrawn
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(500),
+        msg: to_binary(&ReceiveMsg::AddLiquidity { pair_id: None }).unwrap(),
+    };
+    router
+        .execute_contract(user.clone(), cw20_token.addr(), &send_msg, &[])
+        .unwrap();
+    let user_balance = cw20_token.balance::<_,_,Empty>(&router, user.clone()).unwrap();
+    assert_eq!(user_balance, Uint128::new(8500));
+    router
+        .execute_contract(user.clone(), amm_addr.clone(), &ExecuteMsg::WithdrawDeposit { pair_id: None }, &[])
+        .unwrap();
+    let user_balance = cw20_token.balance::<_,_,Empty>(&router, user.clone()).unwrap();
+    assert_eq!(user_balance, Uint128::new(9000));
+
+    // only the owner can fund the reserves
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(100),
+        msg: to_binary(&ReceiveMsg::Fund { pair_id: None }).unwrap(),
+    };
+    let err = router
+        .execute_contract(user.clone(), cw20_token.addr(), &send_msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &send_msg, &[])
+        .unwrap();
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token2_reserve, Uint128::new(21100));
+}
+
+#[test]
+fn twap_follows_reserve_changes() {
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+    let start = router.block_info().time.seconds();
+
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 20000, 520);
+
+    router.update_block(|block| block.time = block.time.plus_seconds(100));
+
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::new(949),
+        fee_amount: Uint128::new(13),
+        expiration: None,
+        recipient: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1013),
+            }],
+        )
+        .unwrap();
+
+    router.update_block(|block| block.time = block.time.plus_seconds(100));
+
+    let observations: ObservationsResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Observations { pair_id: None, start_before: None, limit: None })
+        .unwrap();
+    assert_eq!(observations.observations.len(), 2);
+    assert_eq!(observations.observations[0].timestamp, start + 100);
+    assert_eq!(observations.observations[0].token1_price_cumulative, Decimal256::from_ratio(100u128, 1u128));
+    assert_eq!(observations.observations[1].timestamp, start);
+
+    // 100 seconds at 1:1 then 100 seconds at 21000:19051
+    let twap: TwapResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Twap { pair_id: None, window_seconds: 200 })
+        .unwrap();
+    assert_eq!(twap.start_timestamp, start);
+    assert_eq!(twap.end_timestamp, start + 200);
+    assert_eq!(twap.token1_price, Decimal256::from_str("0.953595238095238095").unwrap());
+    assert_eq!(twap.token2_price, Decimal256::from_str("1.051152170489738071").unwrap());
+
+    let twap: TwapResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Twap { pair_id: None, window_seconds: 100 })
+        .unwrap();
+    assert_eq!(twap.start_timestamp, start + 100);
+    assert_eq!(twap.token1_price, Decimal256::from_str("0.907190476190476190").unwrap());
+
+    // no observation old enough
+    let res: StdResult<TwapResponse> = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Twap { pair_id: None, window_seconds: 1000 });
+    assert!(res.is_err());
+}
+
+#[test]
+fn protocol_fee_taken_in_kind() {
+    let owner = Addr::unchecked("owner");
+    let treasury = Addr::unchecked("treasury");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+    let bonding_addr = get_bonding_address(&router, &amm_addr);
+
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 20000, 520);
+
+    // lp fee must stay below 100%
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                bonding_contract_address: bonding_addr.clone(),
+                treasury_address: treasury.clone(),
+                lp_fee: Some(1000),
+                fee_in_kind: Some(true),
+                max_price_impact_bps: None,
+                tx_fee: None,
+                platform_fee: None,
+                lock_seconds: None,
+                discount: None,
+                daily_vesting_amount: None,
+                update_bonding: None,
+                flash_loan_fee: None,
+                order_bounty: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidFee { fee: 1000 }, err.downcast().unwrap());
+
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                bonding_contract_address: bonding_addr,
+                treasury_address: treasury.clone(),
+                lp_fee: Some(5),
+                fee_in_kind: Some(true),
+                max_price_impact_bps: None,
+                tx_fee: None,
+                platform_fee: None,
+                lock_seconds: None,
+                discount: None,
+                daily_vesting_amount: None,
+                update_bonding: None,
+                flash_loan_fee: None,
+                order_bounty: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    // no separate fee payment once fees are taken in kind
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::zero(),
+        fee_amount: Uint128::new(13),
+        expiration: None,
+        recipient: None,
+    };
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1013),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::FeeTakenInKind {}, err.downcast().unwrap());
+
+    let quote: Token1ForToken2PriceResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Token1ForToken2Price { pair_id: None, token1_amount: Uint128::new(1000) })
+        .unwrap();
+    // 13 of the 1000 input is kept as fee, the remaining 987 is swapped with a 0.5% lp fee
+    assert_eq!(quote.token2_amount, Uint128::new(936));
+
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::new(936),
+        fee_amount: Uint128::zero(),
+        expiration: None,
+        recipient: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1000),
+            }],
+        )
+        .unwrap();
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20987));
+    assert_eq!(info.token2_reserve, Uint128::new(19064));
+
+    let fees: ProtocolFeesResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::ProtocolFees { pair_id: None })
+        .unwrap();
+    assert_eq!(fees.token1_amount, Uint128::new(13));
+    assert_eq!(fees.token2_amount, Uint128::zero());
+
+    let err = router
+        .execute_contract(
+            Addr::unchecked("someone"),
+            amm_addr.clone(),
+            &ExecuteMsg::CollectProtocolFees { pair_id: None },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+
+    router
+        .execute_contract(treasury.clone(), amm_addr.clone(), &ExecuteMsg::CollectProtocolFees { pair_id: None }, &[])
+        .unwrap();
+    assert_eq!(bank_balance(&mut router, &treasury, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(13));
+
+    let fees: ProtocolFeesResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::ProtocolFees { pair_id: None })
+        .unwrap();
+    assert_eq!(fees.token1_amount, Uint128::zero());
+}
+
+#[test]
+fn swap_exact_output() {
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(30000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
+        .unwrap();
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(20000),
+        min_liquidity: Uint128::new(20000),
+        max_token2: Uint128::new(20000),
+        fee_amount: Uint128::new(520),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &add_liquidity_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(20520),
+            }],
+        )
+        .unwrap();
+
+    let quote: Token1ForExactToken2Response = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Token1ForExactToken2 { pair_id: None, token2_amount: Uint128::new(1000) })
+        .unwrap();
+    assert_eq!(quote.token1_amount, Uint128::new(1056));
+
+    // max input too low
+    let swap_msg = ExecuteMsg::SwapExactOutput {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        output_amount: Uint128::new(1000),
+        max_input: Uint128::new(1000),
+        fee_amount: Uint128::new(13),
+        expiration: None,
+    };
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1013),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::SwapMaxError { max: Uint128::new(1000), required: Uint128::new(1056) },
+        err.downcast().unwrap()
+    );
+
+    // fee below platform + tx fee of the actual input
+    let swap_msg = ExecuteMsg::SwapExactOutput {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        output_amount: Uint128::new(1000),
+        max_input: Uint128::new(1100),
+        fee_amount: Uint128::new(10),
+        expiration: None,
+    };
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1110),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InsufficientFee {}, err.downcast().unwrap());
+
+    let swap_msg = ExecuteMsg::SwapExactOutput {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        output_amount: Uint128::new(1000),
+        max_input: Uint128::new(1100),
+        fee_amount: Uint128::new(14),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1114),
+            }],
+        )
+        .unwrap();
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(21056));
+    assert_eq!(info.token2_reserve, Uint128::new(19000));
+    // the unused 44 of the max input is refunded, fees come back as the owner is the treasury
+    assert_eq!(bank_balance(&mut router, &owner, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(28944));
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap(), Uint128::new(31000));
+
+    let quote: Token2ForExactToken1Response = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Token2ForExactToken1 { pair_id: None, token1_amount: Uint128::new(500) })
+        .unwrap();
+    assert_eq!(quote.token2_amount, Uint128::new(464));
+
+    // cw20 input is pulled for the exact amount needed, fee is paid in token1 on the output
+    let swap_msg = ExecuteMsg::SwapExactOutput {
+        pair_id: None,
+        input_token: TokenSelect::Token2,
+        output_amount: Uint128::new(500),
+        max_input: Uint128::new(500),
+        fee_amount: Uint128::new(6),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(6),
+            }],
+        )
+        .unwrap();
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20556));
+    assert_eq!(info.token2_reserve, Uint128::new(19464));
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap(), Uint128::new(30536));
+}
+
+#[test]
+fn simulate_swap_matches_execution() {
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(30000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
+        .unwrap();
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(20000),
+        min_liquidity: Uint128::new(20000),
+        max_token2: Uint128::new(20000),
+        fee_amount: Uint128::new(520),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &add_liquidity_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(20520),
+            }],
+        )
+        .unwrap();
+
+    let simulation: SimulateSwapResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &amm_addr,
+            &QueryMsg::SimulateSwap { pair_id: None, input_token: TokenSelect::Token1, input_amount: Uint128::new(1000) },
+        )
+        .unwrap();
+    assert_eq!(
+        simulation,
+        SimulateSwapResponse {
+            output_amount: Uint128::new(949),
+            lp_fee_amount: Uint128::new(3),
+            fee_amount: Uint128::new(13),
+            fee_denom: Denom::Native(NATIVE_TOKEN_DENOM.into()),
+            fee_in_kind: false,
+            spot_price_before: Decimal256::one(),
+            spot_price_after: Decimal256::from_ratio(19051u128, 21000u128),
+            price_impact_bps: 929,
+        }
+    );
+
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: simulation.output_amount,
+        fee_amount: simulation.fee_amount - Uint128::new(1),
+        expiration: None,
+        recipient: None,
+    };
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1012),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InsufficientFee {}, err.downcast().unwrap());
+
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: simulation.output_amount,
+        fee_amount: simulation.fee_amount,
+        expiration: None,
+        recipient: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &swap_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(1013),
+            }],
+        )
+        .unwrap();
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(21000));
+    assert_eq!(info.token2_reserve, Uint128::new(19051));
+
+    // selling token2 owes the fee in token1, on the output
+    let simulation: SimulateSwapResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &amm_addr,
+            &QueryMsg::SimulateSwap { pair_id: None, input_token: TokenSelect::Token2, input_amount: Uint128::new(1000) },
+        )
+        .unwrap();
+    assert_eq!(simulation.output_amount, Uint128::new(1044));
+    assert_eq!(simulation.fee_amount, Uint128::new(13));
+    assert_eq!(simulation.fee_denom, Denom::Native(NATIVE_TOKEN_DENOM.into()));
+}
+
+#[test]
+fn multi_hop_swap_to_recipient() {
+    const ATOM_DENOM: &str = "uatom";
+
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+    let gamer = Addr::unchecked("gamer");
+    let (mut router, cw20_token, amm_addr) = setup_amm(
+        &[
+            (&owner, vec![
+                Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(50000) },
+                Coin { denom: ATOM_DENOM.into(), amount: Uint128::new(50000) },
+            ]),
+            (&buyer, coins(5000, NATIVE_TOKEN_DENOM)),
+        ],
+        &owner,
+        50000,
+    );
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::CreatePair {
+                token1_denom: Denom::Native(ATOM_DENOM.into()),
+                token2_denom: Denom::Cw20(cw20_token.addr()),
+                with_bonding: false,
+                fee_token: None,
+                curve: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(40000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
+        .unwrap();
+    for (pair_id, denom) in [(0u64, NATIVE_TOKEN_DENOM), (1u64, ATOM_DENOM)] {
+        let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+            pair_id: Some(pair_id),
+            token1_amount: Uint128::new(20000),
+            min_liquidity: Uint128::new(20000),
+            max_token2: Uint128::new(20000),
+            fee_amount: Uint128::new(520),
+            expiration: None,
+        };
+        router
+            .execute_contract(
+                owner.clone(),
+                amm_addr.clone(),
+                &add_liquidity_msg,
+                &[Coin {
+                    denom: denom.into(),
+                    amount: Uint128::new(20520),
+                }],
+            )
+            .unwrap();
+    }
+
+    // each hop must sell what the previous one bought
+    let err = router
+        .execute_contract(
+            buyer.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::MultiHopSwap {
+                operations: vec![
+                    SwapOperation { pair_id: 0, input_token: TokenSelect::Token1 },
+                    SwapOperation { pair_id: 1, input_token: TokenSelect::Token1 },
+                ],
+                offer_amount: Uint128::new(1000),
+                minimum_receive: Uint128::zero(),
+                recipient: None,
+                expiration: None,
+            },
+            &coins(1000, NATIVE_TOKEN_DENOM),
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidSwapRoute {}, err.downcast().unwrap());
+
+    // usdc -> FURY -> atom, delivered to the gamer
+    let operations = vec![
+        SwapOperation { pair_id: 0, input_token: TokenSelect::Token1 },
+        SwapOperation { pair_id: 1, input_token: TokenSelect::Token2 },
+    ];
+    let err = router
+        .execute_contract(
+            buyer.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::MultiHopSwap {
+                operations: operations.clone(),
+                offer_amount: Uint128::new(1000),
+                minimum_receive: Uint128::new(882),
+                recipient: Some(gamer.to_string()),
+                expiration: None,
+            },
+            &coins(1000, NATIVE_TOKEN_DENOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::SwapMinError { min: Uint128::new(882), available: Uint128::new(881) },
+        err.downcast().unwrap()
+    );
+
+    router
+        .execute_contract(
+            buyer.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::MultiHopSwap {
+                operations,
+                offer_amount: Uint128::new(1000),
+                minimum_receive: Uint128::new(881),
+                recipient: Some(gamer.to_string()),
+                expiration: None,
+            },
+            &coins(1000, NATIVE_TOKEN_DENOM),
+        )
+        .unwrap();
+
+    assert_eq!(bank_balance(&mut router, &gamer, ATOM_DENOM.to_string()).amount, Uint128::new(881));
+    assert_eq!(bank_balance(&mut router, &buyer, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(4000));
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20987));
+    assert_eq!(info.token2_reserve, Uint128::new(19063));
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(info.token1_reserve, Uint128::new(19119));
+    assert_eq!(info.token2_reserve, Uint128::new(20925));
+
+    // hop fees are kept in kind on each pair
+    let fees: ProtocolFeesResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::ProtocolFees { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(fees.token2_amount, Uint128::new(12));
+
+    // single swap to a third party
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::zero(),
+        fee_amount: Uint128::new(13),
+        expiration: None,
+        recipient: Some(gamer.to_string()),
+    };
+    router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &swap_msg, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    assert!(!cw20_token.balance::<_, _, Empty>(&router, gamer.clone()).unwrap().is_zero());
+    assert!(cw20_token.balance::<_, _, Empty>(&router, buyer.clone()).unwrap().is_zero());
+}
+
+#[test]
+fn zap_in_and_out() {
+    let owner = Addr::unchecked("owner");
+    let bonder = Addr::unchecked("bonder");
+    let (mut router, cw20_token, amm_addr) = setup_amm(
+        &[
+            (&owner, coins(50000, NATIVE_TOKEN_DENOM)),
+            (&bonder, coins(5000, NATIVE_TOKEN_DENOM)),
+        ],
+        &owner,
+        50000,
+    );
+    fund_bonding(&mut router, &amm_addr, &cw20_token, &owner, Uint128::new(10000));
+
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 20000, 520);
+
+    let zap_in_msg = ExecuteMsg::ZapIn {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        amount: Uint128::new(2000),
+        min_liquidity: Uint128::new(1000),
+        expiration: None,
+    };
+    let err = router
+        .execute_contract(bonder.clone(), amm_addr.clone(), &zap_in_msg, &coins(2000, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MinLiquidityError { min_liquidity: Uint128::new(1000), liquidity_available: Uint128::new(944) },
+        err.downcast().unwrap()
+    );
+
+    let zap_in_msg = ExecuteMsg::ZapIn {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        amount: Uint128::new(2000),
+        min_liquidity: Uint128::new(900),
+        expiration: None,
+    };
+    router
+        .execute_contract(bonder.clone(), amm_addr.clone(), &zap_in_msg, &coins(2000, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(21962));
+    assert_eq!(info.token2_reserve, Uint128::new(19977));
+    assert_eq!(info.lp_token_supply, Uint128::new(20944));
+
+    // what could not be added is refunded
+    assert_eq!(bank_balance(&mut router, &bonder, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(3001));
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, bonder.clone()).unwrap(), Uint128::new(23));
+
+    // bonded like a regular AddLiquidity
+    let record = get_bonding_info(&router, &amm_addr, &bonder);
+    assert_eq!(record.list[0].amount, Uint128::new(1811));
+
+    // the fee of the swapped part is kept in kind
+    let fees: ProtocolFeesResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::ProtocolFees { pair_id: None })
+        .unwrap();
+    assert_eq!(fees.token1_amount, Uint128::new(12));
+
+    // the owner holds the lp tokens
+    let lp_token = Cw20Contract(Addr::unchecked(info.lp_token_address.clone()));
+    assert_eq!(lp_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap(), Uint128::new(20944));
+    router
+        .execute_contract(
+            owner.clone(),
+            lp_token.addr(),
+            &Cw20ExecuteMsg::IncreaseAllowance { spender: amm_addr.to_string(), amount: Uint128::new(1000), expires: None },
+            &[],
+        )
+        .unwrap();
+    let before = cw20_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap();
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::ZapOut {
+                pair_id: None,
+                amount: Uint128::new(1000),
+                output_token: TokenSelect::Token2,
+                min_output: Uint128::zero(),
+                expiration: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let after = cw20_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap();
+    // 953 withdrawn plus 894 bought with the withdrawn 1048 token1
+    assert_eq!(after - before, Uint128::new(1847));
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(21949));
+    assert_eq!(info.token2_reserve, Uint128::new(18130));
+    assert_eq!(info.lp_token_supply, Uint128::new(19944));
+}
+
+#[test]
+fn pause_and_price_impact_cap() {
+    let owner = Addr::unchecked("owner");
+    let guardian = Addr::unchecked("guardian");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+    let bonding_addr = get_bonding_address(&router, &amm_addr);
+
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(20000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
+        .unwrap();
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(20000),
+        min_liquidity: Uint128::new(20000),
+        max_token2: Uint128::new(20000),
+        fee_amount: Uint128::new(520),
+        expiration: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &add_liquidity_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(20520),
+            }],
+        )
+        .unwrap();
+
+    let update_config_msg = |max_price_impact_bps: Option<u64>| ExecuteMsg::UpdateConfig {
+        bonding_contract_address: bonding_addr.clone(),
+        treasury_address: owner.clone(),
+        lp_fee: None,
+        fee_in_kind: None,
+        max_price_impact_bps,
+        tx_fee: None,
+        platform_fee: None,
+        lock_seconds: None,
+        discount: None,
+        daily_vesting_amount: None,
+        update_bonding: None,
+        flash_loan_fee: None,
+        order_bounty: None,
+    };
+    let grant_pauser_msg = ExecuteMsg::Access(AccessMsg::GrantRole {
+        role: Role::Pauser,
+        address: guardian.to_string(),
+    });
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &grant_pauser_msg, &[])
+        .unwrap();
+
+    // the pauser can pause but not resume
+    router
+        .execute_contract(guardian.clone(), amm_addr.clone(), &ExecuteMsg::UpdateEnabled { enabled: false }, &[])
+        .unwrap();
+    let err = router
+        .execute_contract(guardian.clone(), amm_addr.clone(), &ExecuteMsg::UpdateEnabled { enabled: true }, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+
+    let swap_msg = |input_amount: u128| ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(input_amount),
+        min_output: Uint128::zero(),
+        fee_amount: Uint128::new(input_amount * 13 / 1000),
+        expiration: None,
+        recipient: None,
+    };
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &swap_msg(1000), &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Disabled {}), err.downcast().unwrap());
+
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &add_liquidity_msg,
+            &[Coin {
+                denom: NATIVE_TOKEN_DENOM.into(),
+                amount: Uint128::new(20520),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Disabled {}), err.downcast().unwrap());
+
+    // liquidity can still be removed
+    let info = get_info(&router, &amm_addr);
+    let lp_token = Cw20Contract(Addr::unchecked(info.lp_token_address));
+    router
+        .execute_contract(
+            owner.clone(),
+            lp_token.addr(),
+            &Cw20ExecuteMsg::IncreaseAllowance { spender: amm_addr.to_string(), amount: Uint128::new(1000), expires: None },
+            &[],
+        )
+        .unwrap();
+    let remove_liquidity_msg = ExecuteMsg::RemoveLiquidity {
+        pair_id: None,
+        amount: Uint128::new(1000),
+        min_token1: Uint128::zero(),
+        min_token2: Uint128::zero(),
+        expiration: None,
+    };
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &remove_liquidity_msg, &[])
+        .unwrap();
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::UpdateEnabled { enabled: true }, &[])
+        .unwrap();
+
+    // cap a single swap to a 5% price move
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &update_config_msg(Some(10001)), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidPriceImpactCap { max_price_impact_bps: 10001 }, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &update_config_msg(Some(500)), &[])
+        .unwrap();
+
+    let simulation: SimulateSwapResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &amm_addr,
+            &QueryMsg::SimulateSwap { pair_id: None, input_token: TokenSelect::Token1, input_amount: Uint128::new(2000) },
+        )
+        .unwrap();
+    assert!(simulation.price_impact_bps > 500);
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &swap_msg(2000), &coins(2026, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::PriceImpactTooHigh { price_impact_bps: simulation.price_impact_bps, max_price_impact_bps: 500 },
+        err.downcast().unwrap()
+    );
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &swap_msg(200), &coins(202, NATIVE_TOKEN_DENOM))
+        .unwrap();
+}
+
+#[test]
+fn update_config_propagates_to_bonding() {
+    let owner = Addr::unchecked("owner");
+    let treasury = Addr::unchecked("treasury");
+    let (mut router, _, amm_addr) = setup_amm(&[], &owner, 50000);
+    let bonding_addr = get_bonding_address(&router, &amm_addr);
+
+    let update_config_msg = |discount: u64, update_bonding: bool| ExecuteMsg::UpdateConfig {
+        bonding_contract_address: bonding_addr.clone(),
+        treasury_address: treasury.clone(),
+        lp_fee: None,
+        fee_in_kind: None,
+        max_price_impact_bps: None,
+        tx_fee: Some(4),
+        platform_fee: Some(12),
+        lock_seconds: Some(86400),
+        discount: Some(discount),
+        daily_vesting_amount: Some(Uint128::new(5000)),
+        update_bonding: Some(update_bonding),
+        flash_loan_fee: None,
+        order_bounty: None,
+    };
+
+    let err = router
+        .execute_contract(Addr::unchecked("someone"), amm_addr.clone(), &update_config_msg(50, true), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &update_config_msg(1000, true), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidDiscount { discount: 1000 }, err.downcast().unwrap());
+
+    // without forwarding the child keeps its config
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &update_config_msg(40, false), &[])
+        .unwrap();
+    let bonding_config: fanfurybonding::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding_addr, &fanfurybonding::msg::QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(bonding_config.discount, 5);
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &update_config_msg(50, true), &[])
+        .unwrap();
+
+    let config: ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.tx_fee, 4);
+    assert_eq!(config.platform_fee, 12);
+    assert_eq!(config.discount, 50);
+
+    let bonding_config: fanfurybonding::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(&bonding_addr, &fanfurybonding::msg::QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(bonding_config.treasury_address, treasury);
+    assert_eq!(bonding_config.tx_fee, 4);
+    assert_eq!(bonding_config.platform_fee, 12);
+    assert_eq!(bonding_config.lock_seconds, 86400);
+    assert_eq!(bonding_config.discount, 50);
+    assert_eq!(bonding_config.daily_vesting_amount, Uint128::new(5000));
+}
+
+#[test]
+fn flash_loan_repaid_with_fee() {
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 20000, 520);
+
+    // the receiver holds a little usdc to pay the fee with
+    let receiver_id = router.store_code(contract_flash_loan_receiver());
+    let receiver_addr = router
+        .instantiate_contract(receiver_id, owner.clone(), &Empty {}, &[], "receiver", None)
+        .unwrap();
+    router
+        .send_tokens(owner.clone(), receiver_addr.clone(), &coins(100, NATIVE_TOKEN_DENOM))
+        .unwrap();
+
+    let flash_loan_msg = |amount: u128, action: FlashLoanAction| ExecuteMsg::FlashLoan {
+        pair_id: None,
+        token: TokenSelect::Token1,
+        amount: Uint128::new(amount),
+        callback_msg: to_binary(&action).unwrap(),
+    };
+
+    // more than the reserve cannot be borrowed
+    let err = router
+        .execute_contract(receiver_addr.clone(), amm_addr.clone(), &flash_loan_msg(20001, FlashLoanAction::Repay), &[])
+        .unwrap_err();
+    assert!(matches!(err.downcast().unwrap(), ContractError::InsufficientLiquidityError { .. }));
+
+    // 1 permille of 10000, the fee goes to the reserve
+    router
+        .execute_contract(receiver_addr.clone(), amm_addr.clone(), &flash_loan_msg(10000, FlashLoanAction::Repay), &[])
+        .unwrap();
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20010));
+    assert_eq!(info.token2_reserve, Uint128::new(20000));
+    let receiver_balance = bank_balance(&mut router, &receiver_addr, NATIVE_TOKEN_DENOM.to_string());
+    assert_eq!(receiver_balance.amount, Uint128::new(90));
+
+    // the fee is rounded up
+    router
+        .execute_contract(receiver_addr.clone(), amm_addr.clone(), &flash_loan_msg(1, FlashLoanAction::Repay), &[])
+        .unwrap();
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20011));
+
+    // the whole loan is reverted when the fee is not paid back
+    let err = router
+        .execute_contract(
+            receiver_addr.clone(),
+            amm_addr.clone(),
+            &flash_loan_msg(10000, FlashLoanAction::RepayWithoutFee),
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(err.downcast().unwrap(), ContractError::FlashLoanNotRepaid { .. }));
+
+    // the pool cannot be used from within the callback
+    let err = router
+        .execute_contract(receiver_addr.clone(), amm_addr.clone(), &flash_loan_msg(10000, FlashLoanAction::Reenter), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::FlashLoanInProgress {}, err.downcast().unwrap());
+
+    // not even to fund a farm as an operator
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::Access(AccessMsg::GrantRole { role: Role::Operator, address: receiver_addr.to_string() }), &[])
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &Cw20ExecuteMsg::Transfer { recipient: receiver_addr.to_string(), amount: Uint128::new(100) }, &[])
+        .unwrap();
+    let err = router
+        .execute_contract(
+            receiver_addr.clone(),
+            amm_addr.clone(),
+            &flash_loan_msg(100, FlashLoanAction::FundFarm { fury_token: cw20_token.addr().to_string() }),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::FlashLoanInProgress {}, err.downcast().unwrap());
+
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20011));
+    let receiver_balance = bank_balance(&mut router, &receiver_addr, NATIVE_TOKEN_DENOM.to_string());
+    assert_eq!(receiver_balance.amount, Uint128::new(89));
+
+    // the fee can be changed by the owner
+    let bonding_addr = get_bonding_address(&router, &amm_addr);
+    let update_config_msg = ExecuteMsg::UpdateConfig {
+        bonding_contract_address: bonding_addr,
+        treasury_address: owner.clone(),
+        lp_fee: None,
+        fee_in_kind: None,
+        max_price_impact_bps: None,
+        tx_fee: None,
+        platform_fee: None,
+        lock_seconds: None,
+        discount: None,
+        daily_vesting_amount: None,
+        update_bonding: None,
+        flash_loan_fee: Some(5),
+        order_bounty: None,
+    };
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &update_config_msg, &[])
+        .unwrap();
+    router
+        .execute_contract(receiver_addr.clone(), amm_addr.clone(), &flash_loan_msg(10000, FlashLoanAction::Repay), &[])
+        .unwrap();
+    let info = get_info(&router, &amm_addr);
+    assert_eq!(info.token1_reserve, Uint128::new(20061));
+}
+
+#[test]
+fn sync_skim_and_donations() {
+    let owner = Addr::unchecked("owner");
+    let recipient = Addr::unchecked("recipient");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 20000, 520);
+
+    let reserve_balances = |router: &App| -> ReserveBalancesResponse {
+        router
+            .wrap()
+            .query_wasm_smart(&amm_addr, &QueryMsg::ReserveBalances { pair_id: None })
+            .unwrap()
+    };
+
+    // tokens sent straight to the contract are not part of the reserves
+    router
+        .send_tokens(owner.clone(), amm_addr.clone(), &coins(100, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: amm_addr.to_string(),
+        amount: Uint128::new(50),
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &transfer_msg, &[])
+        .unwrap();
+    let balances = reserve_balances(&router);
+    assert_eq!(balances.token1_reserve, Uint128::new(20000));
+    assert_eq!(balances.token1_balance, Uint128::new(20100));
+    assert_eq!(balances.token1_excess, Uint128::new(100));
+    assert_eq!(balances.token2_reserve, Uint128::new(20000));
+    assert_eq!(balances.token2_excess, Uint128::new(50));
+
+    // only the owner can skim
+    let skim_msg = ExecuteMsg::Skim {
+        pair_id: None,
+        recipient: recipient.clone(),
+    };
+    let err = router
+        .execute_contract(recipient.clone(), amm_addr.clone(), &skim_msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &skim_msg, &[])
+        .unwrap();
+    let recipient_balance = bank_balance(&mut router, &recipient, NATIVE_TOKEN_DENOM.to_string());
+    assert_eq!(recipient_balance.amount, Uint128::new(100));
+    let recipient_balance = cw20_token.balance::<_, _, Empty>(&router, recipient.clone()).unwrap();
+    assert_eq!(recipient_balance, Uint128::new(50));
+    let balances = reserve_balances(&router);
+    assert_eq!(balances.token1_excess, Uint128::zero());
+    assert_eq!(balances.token2_excess, Uint128::zero());
+    assert_eq!(balances.token1_reserve, Uint128::new(20000));
+
+    // sync moves the excess into the reserve
+    router
+        .send_tokens(owner.clone(), amm_addr.clone(), &coins(30, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::Sync { pair_id: None }, &[])
+        .unwrap();
+    let balances = reserve_balances(&router);
+    assert_eq!(balances.token1_reserve, Uint128::new(20030));
+    assert_eq!(balances.token1_excess, Uint128::zero());
+    assert_eq!(balances.token2_reserve, Uint128::new(20000));
+    assert_eq!(balances.token1_donated, Uint128::zero());
+
+    // donations are added to the reserve and tracked
+    let add_token_msg = ExecuteMsg::AddToken {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        amount: Uint128::new(200),
+    };
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &add_token_msg, &coins(200, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let balances = reserve_balances(&router);
+    assert_eq!(balances.token1_reserve, Uint128::new(20230));
+    assert_eq!(balances.token1_excess, Uint128::zero());
+    assert_eq!(balances.token1_donated, Uint128::new(200));
+    assert_eq!(balances.token2_donated, Uint128::zero());
+}
+
+#[test]
+fn zap_in_needs_liquidity() {
+    const ATOM_DENOM: &str = "uatom";
+
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, ATOM_DENOM))], &owner, 50000);
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::CreatePair {
+            token1_denom: Denom::Native(ATOM_DENOM.into()),
+            token2_denom: Denom::Cw20(cw20_token.addr()),
+            with_bonding: false,
+            fee_token: Some(TokenSelect::Token2),
+            curve: None,
+        }, &[])
+        .unwrap();
+
+    // reserves donated before any liquidity is added mint no lp tokens
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::AddToken {
+            pair_id: Some(1),
+            input_token: TokenSelect::Token1,
+            amount: Uint128::new(1000),
+        }, &coins(1000, ATOM_DENOM))
+        .unwrap();
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(1000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::AddToken {
+            pair_id: Some(1),
+            input_token: TokenSelect::Token2,
+            amount: Uint128::new(1000),
+        }, &[])
+        .unwrap();
+
+    // so there is nothing to zap into
+    let zap_in_msg = ExecuteMsg::ZapIn {
+        pair_id: Some(1),
+        input_token: TokenSelect::Token1,
+        amount: Uint128::new(500),
+        min_liquidity: Uint128::zero(),
+        expiration: None,
+    };
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &zap_in_msg, &coins(500, ATOM_DENOM))
+        .unwrap_err();
+    assert_eq!(ContractError::ZapIntoEmptyPair { pair_id: 1 }, err.downcast().unwrap());
+}
+
+#[test]
+fn daily_stats_track_activity() {
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+    let info = get_info(&router, &amm_addr);
+    let lp_token = Cw20Contract(Addr::unchecked(info.lp_token_address));
+
+    add_liquidity(&mut router, &owner, &cw20_token, &amm_addr, 20000, 520);
+
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::new(949),
+        fee_amount: Uint128::new(13),
+        expiration: None,
+        recipient: None,
+    };
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &swap_msg, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap();
+
+    let first_day = router.block_info().time.seconds() / 86400;
+    router.update_block(|block| block.time = block.time.plus_seconds(86400));
+
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(1000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), lp_token.addr(), &allowance_msg, &[])
+        .unwrap();
+    let remove_liquidity_msg = ExecuteMsg::RemoveLiquidity {
+        pair_id: None,
+        amount: Uint128::new(1000),
+        min_token1: Uint128::new(1050),
+        min_token2: Uint128::new(952),
+        expiration: None,
+    };
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &remove_liquidity_msg, &[])
+        .unwrap();
+
+    let daily_stats = |router: &App, start_day: Option<u64>, limit: Option<u32>| -> DailyStatsResponse {
+        router
+            .wrap()
+            .query_wasm_smart(
+                &amm_addr,
+                &QueryMsg::DailyStats { pair_id: None, start_day, end_day: None, limit },
+            )
+            .unwrap()
+    };
+
+    let stats = daily_stats(&router, None, None).stats;
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].day, first_day);
+    assert_eq!(stats[0].swap_count, 1);
+    assert_eq!(stats[0].token1_sold, Uint128::new(1000));
+    assert_eq!(stats[0].token2_bought, Uint128::new(949));
+    assert_eq!(stats[0].token2_sold, Uint128::zero());
+    assert_eq!(stats[0].token1_treasury_fee, Uint128::new(533));
+    assert_eq!(stats[0].lp_minted, Uint128::new(20000));
+    assert_eq!(stats[0].token1_reserve, Uint128::new(21000));
+    assert_eq!(stats[0].token2_reserve, Uint128::new(19051));
+
+    assert_eq!(stats[1].day, first_day + 1);
+    assert_eq!(stats[1].swap_count, 0);
+    assert_eq!(stats[1].lp_burned, Uint128::new(1000));
+    assert_eq!(stats[1].token1_reserve, Uint128::new(19950));
+    assert_eq!(stats[1].token2_reserve, Uint128::new(18099));
+
+    // pagination and date range
+    let stats = daily_stats(&router, None, Some(1)).stats;
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].day, first_day);
+    let stats = daily_stats(&router, Some(first_day + 1), None).stats;
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].day, first_day + 1);
+}
+
+#[test]
+fn cw20_and_native_pairs() {
+    const ATOM_DENOM: &str = "uatom";
+
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+    let (mut router, fury_token, amm_addr) = setup_amm(
+        &[
+            (&owner, vec![
+                Coin { denom: ATOM_DENOM.into(), amount: Uint128::new(50000) },
+                Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(50000) },
+            ]),
+            (&buyer, vec![
+                Coin { denom: ATOM_DENOM.into(), amount: Uint128::new(12) },
+                Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(1000) },
+            ]),
+        ],
+        &owner,
+        50000,
+    );
+    let club_token = create_cw20(&mut router, &owner, "club".to_string(), "CLUB".to_string(), Uint128::new(50000));
+
+    // both sides of a pair must differ
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::CreatePair {
+            token1_denom: Denom::Native(ATOM_DENOM.into()),
+            token2_denom: Denom::Native(ATOM_DENOM.into()),
+            with_bonding: false,
+            fee_token: None,
+            curve: None,
+        }, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::IdenticalTokens {}, err.downcast().unwrap());
+
+    // FURY/CLUB charges its fees in CLUB, ATOM/USDC in ATOM
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::CreatePair {
+            token1_denom: Denom::Cw20(fury_token.addr()),
+            token2_denom: Denom::Cw20(club_token.addr()),
+            with_bonding: false,
+            fee_token: Some(TokenSelect::Token2),
+            curve: None,
+        }, &[])
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::CreatePair {
+            token1_denom: Denom::Native(ATOM_DENOM.into()),
+            token2_denom: Denom::Native(NATIVE_TOKEN_DENOM.into()),
+            with_bonding: false,
+            fee_token: None,
+            curve: None,
+        }, &[])
+        .unwrap();
+
+    let pairs: PairsResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Pairs { start_after: None, limit: None })
+        .unwrap();
+    assert_eq!(pairs.pairs[0].fee_token, TokenSelect::Token1);
+    assert_eq!(pairs.pairs[1].fee_token, TokenSelect::Token2);
+    assert_eq!(pairs.pairs[2].fee_token, TokenSelect::Token1);
+
+    // cw20/cw20: both tokens and the fee are pulled with allowances
+    for (token, amount) in [(&fury_token, 20000u128), (&club_token, 20520u128)] {
+        router
+            .execute_contract(owner.clone(), token.addr(), &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: amm_addr.to_string(),
+                amount: Uint128::new(amount),
+                expires: None,
+            }, &[])
+            .unwrap();
+    }
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::AddLiquidity {
+            pair_id: Some(1),
+            token1_amount: Uint128::new(20000),
+            min_liquidity: Uint128::new(20000),
+            max_token2: Uint128::new(20000),
+            fee_amount: Uint128::new(520),
+            expiration: None,
+        }, &[])
+        .unwrap();
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(info.token1_reserve, Uint128::new(20000));
+    assert_eq!(info.token2_reserve, Uint128::new(20000));
+
+    router
+        .execute_contract(owner.clone(), fury_token.addr(), &Cw20ExecuteMsg::Transfer {
+            recipient: buyer.to_string(),
+            amount: Uint128::new(1000),
+        }, &[])
+        .unwrap();
+    for (token, amount) in [(&fury_token, 1000u128), (&club_token, 12u128)] {
+        router
+            .execute_contract(buyer.clone(), token.addr(), &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: amm_addr.to_string(),
+                amount: Uint128::new(amount),
+                expires: None,
+            }, &[])
+            .unwrap();
+    }
+
+    // selling FURY, the fee is charged on the CLUB bought
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: Some(1),
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::new(949),
+        fee_amount: Uint128::new(11),
+        expiration: None,
+        recipient: None,
+    };
+    let err = router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &swap_msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::InsufficientFee {}, err.downcast().unwrap());
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: Some(1),
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::new(949),
+        fee_amount: Uint128::new(12),
+        expiration: None,
+        recipient: None,
+    };
+    router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &swap_msg, &[])
+        .unwrap();
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(info.token1_reserve, Uint128::new(21000));
+    assert_eq!(info.token2_reserve, Uint128::new(19051));
+    assert_eq!(fury_token.balance::<_, _, Empty>(&router, buyer.clone()).unwrap(), Uint128::zero());
+    assert_eq!(club_token.balance::<_, _, Empty>(&router, buyer.clone()).unwrap(), Uint128::new(937));
+    // the treasury is the owner, which paid the liquidity fee to itself
+    assert_eq!(club_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap(), Uint128::new(30012));
+
+    // CLUB sold through the hook pays its fee in kind
+    router
+        .execute_contract(buyer.clone(), club_token.addr(), &Cw20ExecuteMsg::Send {
+            contract: amm_addr.to_string(),
+            amount: Uint128::new(937),
+            msg: to_binary(&ReceiveMsg::Swap { pair_id: Some(1), min_output: Uint128::zero(), expiration: None, recipient: None }).unwrap(),
+        }, &[])
+        .unwrap();
+    let fees: ProtocolFeesResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::ProtocolFees { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(fees.token1_amount, Uint128::zero());
+    assert_eq!(fees.token2_amount, Uint128::new(12));
+    assert_eq!(club_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap(), Uint128::new(30012));
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(info.token2_reserve, Uint128::new(19051 + 937 - 12));
+    assert_eq!(fury_token.balance::<_, _, Empty>(&router, buyer.clone()).unwrap(), Uint128::new(21000) - info.token1_reserve);
+
+    // native/native: both tokens and the fee come with the funds
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::AddLiquidity {
+            pair_id: Some(2),
+            token1_amount: Uint128::new(20000),
+            min_liquidity: Uint128::new(20000),
+            max_token2: Uint128::new(20000),
+            fee_amount: Uint128::new(520),
+            expiration: None,
+        }, &[
+            Coin { denom: ATOM_DENOM.into(), amount: Uint128::new(20520) },
+            Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(20000) },
+        ])
+        .unwrap();
+
+    // selling USDC, the fee is paid in ATOM next to the input
+    let swap_msg = ExecuteMsg::Swap {
+        pair_id: Some(2),
+        input_token: TokenSelect::Token2,
+        input_amount: Uint128::new(1000),
+        min_output: Uint128::new(949),
+        fee_amount: Uint128::new(12),
+        expiration: None,
+        recipient: None,
+    };
+    let err = router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &swap_msg, &coins(1000, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(ContractError::InsufficientFunds {}, err.downcast().unwrap());
+    router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &swap_msg, &[
+            Coin { denom: ATOM_DENOM.into(), amount: Uint128::new(12) },
+            Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(1000) },
+        ])
+        .unwrap();
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(2) })
+        .unwrap();
+    assert_eq!(info.token1_reserve, Uint128::new(19051));
+    assert_eq!(info.token2_reserve, Uint128::new(21000));
+    assert_eq!(bank_balance(&mut router, &buyer, ATOM_DENOM.to_string()).amount, Uint128::new(949));
+    assert_eq!(bank_balance(&mut router, &buyer, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::zero());
+    assert_eq!(bank_balance(&mut router, &owner, ATOM_DENOM.to_string()).amount, Uint128::new(50000 - 20520 + 520 + 12));
+}
+
+#[test]
+fn stable_pair_swaps_and_amp_ramp() {
+    const USDT_DENOM: &str = "uusdt";
+
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+    let (mut router, _, amm_addr) = setup_amm(
+        &[
+            (&owner, vec![
+                Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(1000000) },
+                Coin { denom: USDT_DENOM.into(), amount: Uint128::new(1000000) },
+            ]),
+            (&buyer, vec![
+                Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(1000) },
+                Coin { denom: USDT_DENOM.into(), amount: Uint128::new(1013) },
+            ]),
+        ],
+        &owner,
+        50000,
+    );
+
+    let create_pair_msg = |amp: u64| ExecuteMsg::CreatePair {
+        token1_denom: Denom::Native(USDT_DENOM.into()),
+        token2_denom: Denom::Native(NATIVE_TOKEN_DENOM.into()),
+        with_bonding: false,
+        fee_token: None,
+        curve: Some(Curve::Stable { amp }),
+    };
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &create_pair_msg(0), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidAmp { amp: 0 }, err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &create_pair_msg(100), &[])
+        .unwrap();
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::AddLiquidity {
+            pair_id: Some(1),
+            token1_amount: Uint128::new(100000),
+            min_liquidity: Uint128::new(100000),
+            max_token2: Uint128::new(100000),
+            fee_amount: Uint128::new(2600),
+            expiration: None,
+        }, &[
+            Coin { denom: NATIVE_TOKEN_DENOM.into(), amount: Uint128::new(100000) },
+            Coin { denom: USDT_DENOM.into(), amount: Uint128::new(102600) },
+        ])
+        .unwrap();
+
+    // near the peg the stable curve gives much more than the 987 of a constant product pool
+    let simulation: SimulateSwapResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::SimulateSwap {
+            pair_id: Some(1),
+            input_token: TokenSelect::Token1,
+            input_amount: Uint128::new(1000),
+        })
+        .unwrap();
+    assert_eq!(simulation.output_amount, Uint128::new(996));
+    assert_eq!(simulation.spot_price_before, Decimal256::one());
+    assert_eq!(simulation.price_impact_bps, 1);
+
+    router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &ExecuteMsg::Swap {
+            pair_id: Some(1),
+            input_token: TokenSelect::Token1,
+            input_amount: Uint128::new(1000),
+            min_output: simulation.output_amount,
+            fee_amount: Uint128::new(13),
+            expiration: None,
+            recipient: None,
+        }, &coins(1013, USDT_DENOM))
+        .unwrap();
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Info { pair_id: Some(1) })
+        .unwrap();
+    assert_eq!(info.token1_reserve, Uint128::new(101000));
+    assert_eq!(info.token2_reserve, Uint128::new(100000) - simulation.output_amount);
+    assert_eq!(
+        bank_balance(&mut router, &buyer, NATIVE_TOKEN_DENOM.to_string()).amount,
+        Uint128::new(1000) + simulation.output_amount
+    );
+
+    // the reverse quote buys at least what was asked
+    let quote: Token2ForExactToken1Response = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Token2ForExactToken1 { pair_id: Some(1), token1_amount: Uint128::new(500) })
+        .unwrap();
+    let simulation: SimulateSwapResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::SimulateSwap {
+            pair_id: Some(1),
+            input_token: TokenSelect::Token2,
+            input_amount: quote.token2_amount,
+        })
+        .unwrap();
+    assert_eq!(quote.token2_amount, Uint128::new(502));
+    assert_eq!(simulation.output_amount, Uint128::new(500));
+
+    // single sided deposits are balanced along the stable curve
+    router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &ExecuteMsg::ZapIn {
+            pair_id: Some(1),
+            input_token: TokenSelect::Token2,
+            amount: Uint128::new(1000),
+            min_liquidity: Uint128::new(1),
+            expiration: None,
+        }, &coins(1000, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    // what could not be added after the liquidity fee is refunded
+    assert_eq!(bank_balance(&mut router, &buyer, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(1996 - 1000 + 13));
+    assert_eq!(bank_balance(&mut router, &buyer, USDT_DENOM.to_string()).amount, Uint128::new(1));
+
+    // amplification ramps
+    let ramp_msg = |future_amp: u64, future_time: u64| ExecuteMsg::RampAmp { pair_id: 1, future_amp, future_time };
+    let now = router.block_info().time.seconds();
+    let err = router
+        .execute_contract(buyer.clone(), amm_addr.clone(), &ramp_msg(1000, now + 2 * 86400), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::RampAmp { pair_id: 0, future_amp: 1000, future_time: now + 2 * 86400 }, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::NotStablePair { pair_id: 0 }, err.downcast().unwrap());
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ramp_msg(1000, now + 3600), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidAmpRamp {}, err.downcast().unwrap());
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ramp_msg(1001, now + 2 * 86400), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidAmpRamp {}, err.downcast().unwrap());
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ramp_msg(1000, now + 2 * 86400), &[])
+        .unwrap();
+    router.update_block(|block| block.time = block.time.plus_seconds(86400));
+    let amp: AmpResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Amp { pair_id: 1 })
+        .unwrap();
+    assert_eq!(amp.amp, 550);
+    assert_eq!(amp.future_amp, 1000);
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::StopRampAmp { pair_id: 1 }, &[])
+        .unwrap();
+    router.update_block(|block| block.time = block.time.plus_seconds(86400));
+    let pairs: PairsResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Pairs { start_after: None, limit: None })
+        .unwrap();
+    assert_eq!(pairs.pairs[0].curve, Curve::ConstantProduct {});
+    assert_eq!(pairs.pairs[1].curve, Curve::Stable { amp: 550 });
+}
+
+#[test]
+fn lp_farming_rewards() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+    let lp_token = Cw20Contract(Addr::unchecked(get_info(&router, &amm_addr).lp_token_address));
+
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &Cw20ExecuteMsg::IncreaseAllowance {
+            spender: amm_addr.to_string(),
+            amount: Uint128::new(20000),
+            expires: None,
+        }, &[])
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::AddLiquidity {
+            pair_id: None,
+            token1_amount: Uint128::new(20000),
+            min_liquidity: Uint128::new(20000),
+            max_token2: Uint128::new(20000),
+            fee_amount: Uint128::new(520),
+            expiration: None,
+        }, &coins(20520, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    for (staker, amount) in [(&alice, 4000u128), (&bob, 1000u128)] {
+        router
+            .execute_contract(owner.clone(), lp_token.addr(), &Cw20ExecuteMsg::Transfer {
+                recipient: staker.to_string(),
+                amount: Uint128::new(amount),
+            }, &[])
+            .unwrap();
+    }
+
+    // only the owner sets the rate and funds the rewards, in FURY
+    let rate_msg = ExecuteMsg::SetFarmRewardRate { pair_id: None, reward_rate: Uint128::new(10) };
+    let err = router
+        .execute_contract(alice.clone(), amm_addr.clone(), &rate_msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &rate_msg, &[])
+        .unwrap();
+
+    let fund_msg = |amount: u128| Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_binary(&ReceiveMsg::FundFarm { pair_id: None }).unwrap(),
+    };
+    let err = router
+        .execute_contract(owner.clone(), lp_token.addr(), &fund_msg(100), &[])
+        .unwrap_err();
+    assert!(matches!(err.downcast().unwrap(), ContractError::InvalidToken { .. }));
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &fund_msg(5000), &[])
+        .unwrap();
+
+    // the funded rewards are not part of the reserve excess
+    let balances: ReserveBalancesResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::ReserveBalances { pair_id: None })
+        .unwrap();
+    assert_eq!(balances.token2_balance, Uint128::new(25000));
+    assert_eq!(balances.token2_excess, Uint128::zero());
+
+    let stake_msg = |amount: u128| Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_binary(&ReceiveMsg::StakeLp { pair_id: None }).unwrap(),
+    };
+    let err = router
+        .execute_contract(owner.clone(), cw20_token.addr(), &stake_msg(100), &[])
+        .unwrap_err();
+    assert!(matches!(err.downcast().unwrap(), ContractError::InvalidToken { .. }));
+    router
+        .execute_contract(alice.clone(), lp_token.addr(), &stake_msg(4000), &[])
+        .unwrap();
+
+    let pending = |router: &App, staker: &Addr| -> FarmStakerResponse {
+        router
+            .wrap()
+            .query_wasm_smart(&amm_addr, &QueryMsg::FarmStaker { pair_id: None, address: staker.to_string() })
+            .unwrap()
+    };
+    router.update_block(|block| block.time = block.time.plus_seconds(100));
+    assert_eq!(pending(&router, &alice).pending_reward, Uint128::new(1000));
+
+    // the stream is shared by stake once bob joins
+    router
+        .execute_contract(bob.clone(), lp_token.addr(), &stake_msg(1000), &[])
+        .unwrap();
+    router.update_block(|block| block.time = block.time.plus_seconds(100));
+    assert_eq!(pending(&router, &alice).pending_reward, Uint128::new(1800));
+    assert_eq!(pending(&router, &bob), FarmStakerResponse { staked: Uint128::new(1000), pending_reward: Uint128::new(200) });
+
+    router
+        .execute_contract(alice.clone(), amm_addr.clone(), &ExecuteMsg::ClaimFarmRewards { pair_id: None }, &[])
+        .unwrap();
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, alice.clone()).unwrap(), Uint128::new(1800));
+    assert_eq!(pending(&router, &alice).pending_reward, Uint128::zero());
+
+    let err = router
+        .execute_contract(bob.clone(), amm_addr.clone(), &ExecuteMsg::UnstakeLp { pair_id: None, amount: Uint128::new(1001) }, &[])
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InsufficientStake { requested: Uint128::new(1001), staked: Uint128::new(1000) },
+        err.downcast().unwrap()
+    );
+    router
+        .execute_contract(bob.clone(), amm_addr.clone(), &ExecuteMsg::UnstakeLp { pair_id: None, amount: Uint128::new(1000) }, &[])
+        .unwrap();
+    assert_eq!(lp_token.balance::<_, _, Empty>(&router, bob.clone()).unwrap(), Uint128::new(1000));
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, bob.clone()).unwrap(), Uint128::new(200));
+
+    // streaming stops once the funded rewards run out
+    router.update_block(|block| block.time = block.time.plus_seconds(1000));
+    assert_eq!(pending(&router, &alice).pending_reward, Uint128::new(3000));
+    let farm: FarmResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Farm { pair_id: None })
+        .unwrap();
+    assert_eq!(farm.total_staked, Uint128::new(4000));
+    assert_eq!(farm.undistributed, Uint128::zero());
+    assert_eq!(farm.unclaimed, Uint128::new(3000));
+}
+
+#[test]
+fn limit_orders_filled_by_keeper() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let keeper = Addr::unchecked("keeper");
+    let (mut router, cw20_token, amm_addr) = setup_amm(
+        &[
+            (&owner, coins(50000, NATIVE_TOKEN_DENOM)),
+            (&bob, coins(5000, NATIVE_TOKEN_DENOM)),
+        ],
+        &owner,
+        50000,
+    );
+
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &Cw20ExecuteMsg::IncreaseAllowance {
+            spender: amm_addr.to_string(),
+            amount: Uint128::new(20000),
+            expires: None,
+        }, &[])
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::AddLiquidity {
+            pair_id: None,
+            token1_amount: Uint128::new(20000),
+            min_liquidity: Uint128::new(20000),
+            max_token2: Uint128::new(20000),
+            fee_amount: Uint128::new(520),
+            expiration: None,
+        }, &coins(20520, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &Cw20ExecuteMsg::Transfer {
+            recipient: alice.to_string(),
+            amount: Uint128::new(2000),
+        }, &[])
+        .unwrap();
+
+    // alice sells FURY at two limits, bob buys FURY at a limit the pool will not reach
+    let sell_fury = |min_output: u128| Cw20ExecuteMsg::Send {
+        contract: amm_addr.to_string(),
+        amount: Uint128::new(1000),
+        msg: to_binary(&ReceiveMsg::PlaceOrder { pair_id: None, min_output: Uint128::new(min_output) }).unwrap(),
+    };
+    router
+        .execute_contract(alice.clone(), cw20_token.addr(), &sell_fury(950), &[])
+        .unwrap();
+    router
+        .execute_contract(alice.clone(), cw20_token.addr(), &sell_fury(900), &[])
+        .unwrap();
+    let buy_fury = |amount: u128, min_output: u128| ExecuteMsg::PlaceOrder {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(amount),
+        min_output: Uint128::new(min_output),
+    };
+    let err = router
+        .execute_contract(bob.clone(), amm_addr.clone(), &buy_fury(2000, 4000), &coins(1000, NATIVE_TOKEN_DENOM))
+        .unwrap_err();
+    assert_eq!(ContractError::InsufficientFunds {}, err.downcast().unwrap());
+    router
+        .execute_contract(bob.clone(), amm_addr.clone(), &buy_fury(2000, 4000), &coins(2000, NATIVE_TOKEN_DENOM))
+        .unwrap();
+
+    // the escrowed inputs are not part of the reserve excess
+    let balances: ReserveBalancesResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::ReserveBalances { pair_id: None })
+        .unwrap();
+    assert_eq!(balances.token1_excess, Uint128::zero());
+    assert_eq!(balances.token2_excess, Uint128::zero());
+
+    let orders_of = |router: &App, owner: &Addr| -> Vec<u64> {
+        let resp: OrdersResponse = router
+            .wrap()
+            .query_wasm_smart(&amm_addr, &QueryMsg::OrdersByOwner { owner: owner.to_string(), start_after: None, limit: None })
+            .unwrap();
+        resp.orders.iter().map(|order| order.order_id).collect()
+    };
+    assert_eq!(orders_of(&router, &alice), vec![0, 1]);
+    assert_eq!(orders_of(&router, &bob), vec![2]);
+
+    // only the order at 900 is met, 999 FURY after the bounty give 937 USDC
+    let execute_msg = |start_after: Option<u64>, limit: Option<u32>| ExecuteMsg::ExecuteOrders { start_after, limit };
+    let last_scanned = |res: &AppResponse| -> String {
+        res.events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == "last_scanned")
+            .unwrap()
+            .value
+            .clone()
+    };
+    let res = router
+        .execute_contract(keeper.clone(), amm_addr.clone(), &execute_msg(None, None), &[])
+        .unwrap();
+    assert_eq!(last_scanned(&res), "2");
+    assert_eq!(bank_balance(&mut router, &alice, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(937));
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, keeper.clone()).unwrap(), Uint128::new(1));
+    assert_eq!(orders_of(&router, &alice), vec![0]);
+
+    let err = router
+        .execute_contract(alice.clone(), amm_addr.clone(), &ExecuteMsg::CancelOrder { order_id: 2 }, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    router
+        .execute_contract(bob.clone(), amm_addr.clone(), &ExecuteMsg::CancelOrder { order_id: 2 }, &[])
+        .unwrap();
+    assert_eq!(bank_balance(&mut router, &bob, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(5000));
+    let err = router
+        .execute_contract(bob.clone(), amm_addr.clone(), &ExecuteMsg::CancelOrder { order_id: 2 }, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::UnknownOrder { order_id: 2 }, err.downcast().unwrap());
+
+    // a large buy moves the price, the resting sell is filled on the next call.
+    // the unfillable sell ahead of it uses up the first page, the keeper pages on from its cursor
+    router
+        .execute_contract(bob.clone(), amm_addr.clone(), &buy_fury(3000, 1), &coins(3000, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let res = router
+        .execute_contract(keeper.clone(), amm_addr.clone(), &execute_msg(None, Some(1)), &[])
+        .unwrap();
+    assert_eq!(last_scanned(&res), "0");
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, bob.clone()).unwrap(), Uint128::zero());
+    router
+        .execute_contract(keeper.clone(), amm_addr.clone(), &execute_msg(Some(0), Some(1)), &[])
+        .unwrap();
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, bob.clone()).unwrap(), Uint128::new(2812));
+    assert_eq!(bank_balance(&mut router, &keeper, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(3));
+    assert_eq!(orders_of(&router, &alice), vec![0]);
+
+    router
+        .execute_contract(keeper.clone(), amm_addr.clone(), &execute_msg(None, Some(1)), &[])
+        .unwrap();
+    assert_eq!(bank_balance(&mut router, &alice, NATIVE_TOKEN_DENOM.to_string()).amount, Uint128::new(937 + 1131));
+    assert_eq!(cw20_token.balance::<_, _, Empty>(&router, keeper.clone()).unwrap(), Uint128::new(2));
+    let resp: OrdersResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Orders { start_after: None, limit: None })
+        .unwrap();
+    assert!(resp.orders.is_empty());
+}
+
+#[test]
+fn ownership_transfer_and_roles() {
+    let owner = Addr::unchecked("owner");
+    let new_owner = Addr::unchecked("new_owner");
+    let fee_manager = Addr::unchecked("fee_manager");
+    let (mut router, _, amm_addr) = setup_amm(&[], &owner, 50000);
+    let bonding_addr = get_bonding_address(&router, &amm_addr);
+
+    let update_config_msg = |treasury_address: &Addr, tx_fee: Option<u64>| ExecuteMsg::UpdateConfig {
+        bonding_contract_address: bonding_addr.clone(),
+        treasury_address: treasury_address.clone(),
+        lp_fee: None,
+        fee_in_kind: None,
+        max_price_impact_bps: None,
+        tx_fee,
+        platform_fee: None,
+        lock_seconds: None,
+        discount: None,
+        daily_vesting_amount: None,
+        update_bonding: None,
+        flash_loan_fee: None,
+        order_bounty: None,
+    };
+
+    // a fee manager changes the fees but not the treasury
+    let err = router
+        .execute_contract(fee_manager.clone(), amm_addr.clone(), &update_config_msg(&owner, Some(5)), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+    router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::Access(AccessMsg::GrantRole { role: Role::FeeManager, address: fee_manager.to_string() }),
+            &[],
+        )
+        .unwrap();
+    router
+        .execute_contract(fee_manager.clone(), amm_addr.clone(), &update_config_msg(&owner, Some(5)), &[])
+        .unwrap();
+    let err = router
+        .execute_contract(fee_manager.clone(), amm_addr.clone(), &update_config_msg(&fee_manager, None), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let config: ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.tx_fee, 5);
+    let resp: RoleMembersResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::RoleMembers { role: Role::FeeManager, start_after: None, limit: None })
+        .unwrap();
+    assert_eq!(resp.members, vec![fee_manager.clone()]);
+
+    // the ownership only moves once the new owner accepts it, before the offer expires
+    let err = router
+        .execute_contract(owner.clone(), amm_addr.clone(), &ExecuteMsg::Access(AccessMsg::ProposeNewOwner { new_owner: new_owner.to_string(), expires_in: u64::MAX }), &[])
+        .unwrap_err();
+    assert!(matches!(err.downcast::<ContractError>().unwrap(), ContractError::Common(CommonError::Std(StdError::Overflow { .. }))));
+    let propose_msg = ExecuteMsg::Access(AccessMsg::ProposeNewOwner { new_owner: new_owner.to_string(), expires_in: 100 });
+    let err = router
+        .execute_contract(fee_manager.clone(), amm_addr.clone(), &propose_msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &propose_msg, &[])
+        .unwrap();
+    let err = router
+        .execute_contract(fee_manager.clone(), amm_addr.clone(), &ExecuteMsg::Access(AccessMsg::AcceptOwnership {}), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+    router.update_block(|block| block.time = block.time.plus_seconds(100));
+    let err = router
+        .execute_contract(new_owner.clone(), amm_addr.clone(), &ExecuteMsg::Access(AccessMsg::AcceptOwnership {}), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::OwnershipProposalExpired {}), err.downcast().unwrap());
+
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &propose_msg, &[])
+        .unwrap();
+    let ownership: OwnershipResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Ownership {})
+        .unwrap();
+    assert_eq!(ownership.owner, owner);
+    assert_eq!(ownership.pending_owner, Some(new_owner.clone()));
+    router
+        .execute_contract(new_owner.clone(), amm_addr.clone(), &ExecuteMsg::Access(AccessMsg::AcceptOwnership {}), &[])
+        .unwrap();
+    let ownership: OwnershipResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Ownership {})
+        .unwrap();
+    assert_eq!(ownership.owner, new_owner);
+    assert_eq!(ownership.pending_owner, None);
+
+    // the previous owner keeps no role
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::Access(AccessMsg::RevokeRole { role: Role::FeeManager, address: fee_manager.to_string() }),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+    router
+        .execute_contract(
+            new_owner.clone(),
+            amm_addr.clone(),
+            &ExecuteMsg::Access(AccessMsg::RevokeRole { role: Role::FeeManager, address: fee_manager.to_string() }),
+            &[],
+        )
+        .unwrap();
+    let err = router
+        .execute_contract(fee_manager.clone(), amm_addr.clone(), &update_config_msg(&owner, Some(3)), &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Common(CommonError::Unauthorized {}), err.downcast().unwrap());
+}
+
+#[test]
+fn shared_swap_messages_match_the_amm() {
+    use fanfury_common::swap::{SwapExecuteMsg, SwapFeeConfigResponse, SwapQueryMsg};
+
+    let owner = Addr::unchecked("owner");
+    let (mut router, cw20_token, amm_addr) = setup_amm(&[(&owner, coins(50000, NATIVE_TOKEN_DENOM))], &owner, 50000);
+
+    let allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: amm_addr.to_string(),
+        amount: Uint128::new(20000u128),
+        expires: None,
+    };
+    router
+        .execute_contract(owner.clone(), cw20_token.addr(), &allowance_msg, &[])
+        .unwrap();
+    let add_liquidity_msg = ExecuteMsg::AddLiquidity {
+        pair_id: None,
+        token1_amount: Uint128::new(20000),
+        min_liquidity: Uint128::new(20000),
+        max_token2: Uint128::new(20000),
+        fee_amount: Uint128::new(520),
+        expiration: None,
+    };
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &add_liquidity_msg, &coins(20520, NATIVE_TOKEN_DENOM))
+        .unwrap();
+
+    let fees: SwapFeeConfigResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &SwapQueryMsg::Config {})
+        .unwrap();
+    assert_eq!(fees, SwapFeeConfigResponse { tx_fee: 3, platform_fee: 10, fee_in_kind: false });
+
+    let quote: Token1ForToken2PriceResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &SwapQueryMsg::Token1ForToken2Price { pair_id: None, token1_amount: Uint128::new(1000) })
+        .unwrap();
+    let amm_quote: Token1ForToken2PriceResponse = router
+        .wrap()
+        .query_wasm_smart(&amm_addr, &QueryMsg::Token1ForToken2Price { pair_id: None, token1_amount: Uint128::new(1000) })
+        .unwrap();
+    assert_eq!(quote, amm_quote);
+
+    let before = cw20_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap();
+    let swap_msg = SwapExecuteMsg::Swap {
+        pair_id: None,
+        input_token: TokenSelect::Token1,
+        input_amount: Uint128::new(1000),
+        min_output: quote.token2_amount,
+        fee_amount: Uint128::new(13),
+        expiration: None,
+        recipient: None,
+    };
+    router
+        .execute_contract(owner.clone(), amm_addr.clone(), &swap_msg, &coins(1013, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let after = cw20_token.balance::<_, _, Empty>(&router, owner.clone()).unwrap();
+    assert_eq!(after - before, quote.token2_amount);
+}
+
+#[test]
+fn migrate_single_pair_state() {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use crate::contract::{migrate, CONTRACT_NAME, CONTRACT_VERSION};
+    use crate::msg::MigrateMsg;
+    use crate::state::{ConfigV1, Token, CONFIG, CONFIG_V1, DEFAULT_PAIR_ID, LP_TOKEN, LP_TOKEN_V1, NEXT_PAIR_ID, TOKEN1, TOKEN1_V1, TOKEN2, TOKEN2_V1};
+
+    let mut deps = mock_dependencies();
+    let owner = Addr::unchecked("owner");
+    let token1 = Token { reserve: Uint128::new(500), denom: Denom::Native("usdc".to_string()) };
+    let token2 = Token { reserve: Uint128::new(700), denom: Denom::Cw20(Addr::unchecked("fury")) };
+    cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "1.0.0").unwrap();
+    CONFIG_V1
+        .save(deps.as_mut().storage, &ConfigV1 {
+            owner: owner.clone(),
+            bonding_code_id: 2,
+            bonding_contract_address: Addr::unchecked("bonding"),
+            fury_token_address: Addr::unchecked("fury"),
+            treasury_address: owner.clone(),
+            usdc_denom: "usdc".to_string(),
+            tx_fee: 3,
+            platform_fee: 10,
+            lock_seconds: 7,
+            discount: 5,
+            daily_vesting_amount: Uint128::new(100),
+        })
+        .unwrap();
+    LP_TOKEN_V1.save(deps.as_mut().storage, &Addr::unchecked("lp_token")).unwrap();
+    TOKEN1_V1.save(deps.as_mut().storage, &token1).unwrap();
+    TOKEN2_V1.save(deps.as_mut().storage, &token2).unwrap();
+
+    // the stored layout differs, only the typed migration reads it
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+    assert_eq!(ContractError::UnsupportedMigration { previous_version: "1.0.0".to_string() }, err);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::FromV1 { lp_token_code_id: 1 }).unwrap();
+
+    let storage = deps.as_ref().storage;
+    assert_eq!(LP_TOKEN.load(storage, DEFAULT_PAIR_ID).unwrap(), Addr::unchecked("lp_token"));
+    assert_eq!(TOKEN1.load(storage, DEFAULT_PAIR_ID).unwrap(), token1);
+    assert_eq!(TOKEN2.load(storage, DEFAULT_PAIR_ID).unwrap(), token2);
+    assert_eq!(TOKEN1_V1.may_load(storage).unwrap(), None);
+    assert_eq!(NEXT_PAIR_ID.load(storage).unwrap(), 1);
+    let config = CONFIG.load(storage).unwrap();
+    assert_eq!(config.lp_token_code_id, 1);
+    assert_eq!(config.lp_fee, 3);
+    assert!(config.enabled);
+    assert_eq!(config.daily_vesting_amount, Uint128::new(100));
+    assert_eq!(cw2::get_contract_version(storage).unwrap().version, CONTRACT_VERSION);
+
+    // later releases upgrade in place, and the code can not be downgraded
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::FromV1 { lp_token_code_id: 1 }).unwrap_err();
+    assert_eq!(ContractError::UnsupportedMigration { previous_version: CONTRACT_VERSION.to_string() }, err);
+    cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "9.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+    assert_eq!(
+        ContractError::Common(CommonError::CannotMigrateVersion {
+            previous_version: "9.0.0".to_string(),
+            new_version: CONTRACT_VERSION.to_string(),
+        }),
+        err
+    );
+}
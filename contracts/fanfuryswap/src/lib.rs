@@ -1,6 +1,6 @@
 pub mod contract;
 pub mod error;
 pub mod msg;
+pub mod stableswap;
 pub mod state;
-pub mod util;
 mod integration_test;
\ No newline at end of file
@@ -1,10 +1,13 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw20::Cw20ReceiveMsg;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal256, Uint128};
 
 use cw20::{Denom, Expiration};
 
+pub use fanfury_common::access::{AccessMsg, OwnershipResponse, Role, RoleMembersResponse};
+pub use fanfury_common::swap::{Token1ForToken2PriceResponse, Token2ForToken1PriceResponse, TokenSelect};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub lp_token_code_id: u64,
@@ -20,59 +23,276 @@ pub struct InstantiateMsg {
     pub tx_fee: u64,
     pub platform_fee: u64,
     pub daily_vesting_amount: Uint128,
+    /// Permille of the swap input kept by the pool, 3 when not set
+    pub lp_fee: Option<u64>,
+    pub fee_in_kind: Option<bool>,
+    /// Granted the pauser role
+    pub guardian: Option<Addr>,
+    /// Price impact cap of a single swap in basis points, no cap when not set
+    pub max_price_impact_bps: Option<u64>,
 }
 
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+/// Hook messages accepted through cw20 `Send`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum StakeReceiveMsg {
-    Stake {
+pub enum ReceiveMsg {
+    /// Sells the received token. The platform and tx fee is taken from the output when the pair
+    /// charges it in the other token, and in kind from the input otherwise.
+    Swap {
+        pair_id: Option<u64>,
+        min_output: Uint128,
+        expiration: Option<Expiration>,
+        /// Receiver of the output, the sender when not set
+        recipient: Option<String>,
+    },
+    /// `ExecuteMsg::ZapIn` with the received token
+    ZapIn {
+        pair_id: Option<u64>,
+        min_liquidity: Uint128,
+        expiration: Option<Expiration>,
+    },
+    /// `ExecuteMsg::MultiHopSwap` starting with the received token
+    MultiHopSwap {
+        operations: Vec<SwapOperation>,
+        minimum_receive: Uint128,
+        recipient: Option<String>,
+        expiration: Option<Expiration>,
     },
-    LpStake {
-        address: Addr
+    /// Deposits the received token2 for the next `AddLiquidity` of the sender on the pair,
+    /// which then uses it instead of an allowance.
+    AddLiquidity {
+        pair_id: Option<u64>,
+    },
+    /// Adds the received token to the pair reserve without minting lp tokens. Only callable by the owner.
+    Fund {
+        pair_id: Option<u64>,
+    },
+    /// Stakes the received lp token in the farm of its pair
+    StakeLp {
+        pair_id: Option<u64>,
+    },
+    /// Adds the received FURY to the rewards streamed by the farm of the pair. Only callable by an operator.
+    FundFarm {
+        pair_id: Option<u64>,
+    },
+    /// Escrows the received token in a limit order, see `ExecuteMsg::PlaceOrder`
+    PlaceOrder {
+        pair_id: Option<u64>,
+        min_output: Uint128,
+    },
+}
+
+/// Invariant followed by the reserves of a pair
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// `x * y = k`
+    ConstantProduct {},
+    /// Curve StableSwap for pegged tokens with the same decimals.
+    /// The higher `amp`, the flatter the price around the peg.
+    Stable { amp: u64 },
+}
+
+/// Message sent to the receiver of a flash loan once the lent tokens are transferred
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLoanReceiverMsg {
+    FlashLoanCallback {
+        pair_id: u64,
+        denom: Denom,
+        amount: Uint128,
+        fee: Uint128,
+        msg: Binary,
     },
-    Fund {},
 }
 
+/// One hop of a `MultiHopSwap`: sells `input_token` of the pair for its other token
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub enum TokenSelect {
-    Token1,
-    Token2,
+pub struct SwapOperation {
+    pub pair_id: u64,
+    pub input_token: TokenSelect,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    /// Callable by the owner or a fee manager.
+    /// A fee manager may not change the addresses, `lock_seconds`, `discount` or `daily_vesting_amount`.
     UpdateConfig {
-        owner: Addr,
         bonding_contract_address: Addr,
-        treasury_address: Addr
+        treasury_address: Addr,
+        lp_fee: Option<u64>,
+        fee_in_kind: Option<bool>,
+        max_price_impact_bps: Option<u64>,
+        tx_fee: Option<u64>,
+        platform_fee: Option<u64>,
+        lock_seconds: Option<u64>,
+        discount: Option<u64>,
+        daily_vesting_amount: Option<Uint128>,
+        /// Also sends the treasury, fees, lock, discount and vesting amount to the bonding children
+        update_bonding: Option<bool>,
+        flash_loan_fee: Option<u64>,
+        order_bounty: Option<u64>,
+    },
+    /// Pauses or resumes swaps and liquidity adds.
+    /// A pauser can only pause, the owner can do both.
+    UpdateEnabled {
+        enabled: bool,
+    },
+    /// Registers a new pair. Only callable by the owner.
+    /// The lp token (and the bonding child if requested) are instantiated through reply.
+    CreatePair {
+        token1_denom: Denom,
+        token2_denom: Denom,
+        /// Lp bonds are paid in FURY, so only pairs with FURY as token2 can have a bonding child
+        with_bonding: bool,
+        /// Side in which the platform and tx fees are paid when not taken in kind, token1 when not set.
+        /// A cw20 fee is pulled from the sender with an allowance.
+        fee_token: Option<TokenSelect>,
+        /// Constant product when not set
+        curve: Option<Curve>,
+    },
+    /// Moves the amplification of a stable pair linearly to `future_amp`, reached at `future_time`.
+    /// Only callable by the owner.
+    RampAmp {
+        pair_id: u64,
+        future_amp: u64,
+        future_time: u64,
+    },
+    /// Stops an amplification ramp at the current value. Only callable by the owner.
+    StopRampAmp {
+        pair_id: u64,
     },
     AddLiquidity {
+        pair_id: Option<u64>,
         token1_amount: Uint128,
         min_liquidity: Uint128,
         max_token2: Uint128,
         fee_amount: Uint128,
         expiration: Option<Expiration>,
     },
+    /// Donates protocol owned liquidity to the reserve without minting lp tokens. Only callable by the owner.
     AddToken {
+        pair_id: Option<u64>,
         input_token: TokenSelect,
         amount: Uint128
     },
     RemoveLiquidity {
+        pair_id: Option<u64>,
         amount: Uint128,
         min_token1: Uint128,
         min_token2: Uint128,
         expiration: Option<Expiration>,
     },
+    /// Adds liquidity from a single token: swaps the part of `amount` that balances the pool
+    /// and adds the rest, bonding like `AddLiquidity`. Fees are taken from the input and
+    /// what cannot be added is refunded.
+    ZapIn {
+        pair_id: Option<u64>,
+        input_token: TokenSelect,
+        amount: Uint128,
+        min_liquidity: Uint128,
+        expiration: Option<Expiration>,
+    },
+    /// Removes liquidity and swaps the whole withdrawal into `output_token`
+    ZapOut {
+        pair_id: Option<u64>,
+        amount: Uint128,
+        output_token: TokenSelect,
+        min_output: Uint128,
+        expiration: Option<Expiration>,
+    },
     Swap {
+        pair_id: Option<u64>,
         input_token: TokenSelect,
         input_amount: Uint128,
         min_output: Uint128,
         fee_amount: Uint128,
         expiration: Option<Expiration>,
+        /// Receiver of the output, the sender when not set
+        recipient: Option<String>,
     },
-
+    /// Swaps `offer_amount` through the given pairs, each hop selling the output of the previous one.
+    /// Every hop keeps its platform and tx fee from its input, collected with `CollectProtocolFees`.
+    MultiHopSwap {
+        operations: Vec<SwapOperation>,
+        offer_amount: Uint128,
+        minimum_receive: Uint128,
+        recipient: Option<String>,
+        expiration: Option<Expiration>,
+    },
+    /// Buys exactly `output_amount` of the other token, spending at most `max_input`.
+    /// Native input is sent as `max_input` (plus the fee) and the unused part is refunded.
+    SwapExactOutput {
+        pair_id: Option<u64>,
+        input_token: TokenSelect,
+        output_amount: Uint128,
+        max_input: Uint128,
+        fee_amount: Uint128,
+        expiration: Option<Expiration>,
+    },
+    Receive(Cw20ReceiveMsg),
+    /// Returns the token2 deposited through the `AddLiquidity` hook and not used yet
+    WithdrawDeposit {
+        pair_id: Option<u64>,
+    },
+    /// Lends `amount` of the pair reserve to the sender, which must be a contract handling
+    /// `FlashLoanReceiverMsg`. The loan plus the flash loan fee has to be sent back to this
+    /// contract before the callback returns.
+    FlashLoan {
+        pair_id: Option<u64>,
+        token: TokenSelect,
+        amount: Uint128,
+        callback_msg: Binary,
+    },
+    /// Sets the pair reserves to the tokens the contract actually holds for them. Only callable by the owner.
+    Sync {
+        pair_id: Option<u64>,
+    },
+    /// Sends the tokens held above the pair reserves to `recipient`. Only callable by the owner.
+    Skim {
+        pair_id: Option<u64>,
+        recipient: Addr,
+    },
+    /// Sends the fees taken in kind to the treasury. Only callable by a fee manager or the treasury.
+    CollectProtocolFees {
+        pair_id: Option<u64>,
+    },
+    /// Sets the FURY streamed per second to the lp stakers of the pair. Only callable by an operator.
+    SetFarmRewardRate {
+        pair_id: Option<u64>,
+        reward_rate: Uint128,
+    },
+    /// Returns staked lp tokens along with the pending farm rewards
+    UnstakeLp {
+        pair_id: Option<u64>,
+        amount: Uint128,
+    },
+    ClaimFarmRewards {
+        pair_id: Option<u64>,
+    },
+    /// Escrows `input_amount` of `input_token` until the pool gives at least `min_output` for it.
+    /// A cw20 input is pulled with an allowance, cw20s can also be sent with `ReceiveMsg::PlaceOrder`.
+    PlaceOrder {
+        pair_id: Option<u64>,
+        input_token: TokenSelect,
+        input_amount: Uint128,
+        min_output: Uint128,
+    },
+    /// Returns the escrowed input of a resting order. Only callable by its owner.
+    CancelOrder {
+        order_id: u64,
+    },
+    /// Scans up to `limit` resting orders after `start_after`, oldest first, and fills those whose minimum output
+    /// the pool meets. The `last_scanned` attribute is the cursor of the next page.
+    /// Callable by anyone, the caller receives the order bounty from the input of each filled order.
+    ExecuteOrders {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Ownership transfer and role management
+    Access(AccessMsg),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -83,13 +303,105 @@ pub enum QueryMsg {
     Balance {
         address: String,
     },
-    Info {},
+    Info {
+        pair_id: Option<u64>,
+    },
     Token1ForToken2Price {
+        pair_id: Option<u64>,
         token1_amount: Uint128,
     },
     Token2ForToken1Price {
+        pair_id: Option<u64>,
+        token2_amount: Uint128,
+    },
+    /// Token1 needed to buy exactly `token2_amount`
+    Token1ForExactToken2 {
+        pair_id: Option<u64>,
         token2_amount: Uint128,
     },
+    /// Token2 needed to buy exactly `token1_amount`
+    Token2ForExactToken1 {
+        pair_id: Option<u64>,
+        token1_amount: Uint128,
+    },
+    /// Outcome of a `Swap` with the current reserves, computed the same way as the execution
+    SimulateSwap {
+        pair_id: Option<u64>,
+        input_token: TokenSelect,
+        input_amount: Uint128,
+    },
+    /// Lists the registered pairs ordered by id. Supports pagination.
+    Pairs {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the token2 deposited by the address for the next `AddLiquidity` on the pair
+    Deposit {
+        pair_id: Option<u64>,
+        address: String,
+    },
+    /// Time weighted average prices of the pair over at least the last `window_seconds`
+    Twap {
+        pair_id: Option<u64>,
+        window_seconds: u64,
+    },
+    /// Lists the price observations of the pair from the newest. Supports pagination.
+    Observations {
+        pair_id: Option<u64>,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Fees taken in kind and not collected yet
+    ProtocolFees {
+        pair_id: Option<u64>,
+    },
+    /// Pair reserves against the balances held by the contract, and the donations made to them
+    ReserveBalances {
+        pair_id: Option<u64>,
+    },
+    /// Lists the daily activity of the pair from `start_day` to `end_day` included, oldest first.
+    /// Days are counted since the unix epoch. Query the next page from the last returned day + 1.
+    DailyStats {
+        pair_id: Option<u64>,
+        start_day: Option<u64>,
+        end_day: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Amplification ramp of a stable pair
+    Amp {
+        pair_id: u64,
+    },
+    /// Lp token farm of the pair, with the rewards streamed up to now
+    Farm {
+        pair_id: Option<u64>,
+    },
+    /// Lp tokens staked by the address and the rewards it can claim
+    FarmStaker {
+        pair_id: Option<u64>,
+        address: String,
+    },
+    Order {
+        order_id: u64,
+    },
+    /// Lists the resting orders by id
+    Orders {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lists the resting orders of `owner` by id
+    OrdersByOwner {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Owner and the pending ownership offer
+    Ownership {},
+    /// Addresses granted the role, the owner not included
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -103,20 +415,166 @@ pub struct InfoResponse {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Token1ForToken2PriceResponse {
+pub struct PairResponse {
+    pub pair_id: u64,
+    pub token1_reserve: Uint128,
+    pub token1_denom: Denom,
+    pub token2_reserve: Uint128,
+    pub token2_denom: Denom,
+    pub lp_token_address: String,
+    pub bonding_contract_address: Option<Addr>,
+    pub fee_token: TokenSelect,
+    /// Stable pairs report their current amplification
+    pub curve: Curve,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairsResponse {
+    pub pairs: Vec<PairResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AmpResponse {
+    pub amp: u64,
+    pub initial_amp: u64,
+    pub initial_time: u64,
+    pub future_amp: u64,
+    pub future_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FarmResponse {
+    pub lp_token_address: Addr,
+    pub reward_rate: Uint128,
+    pub reward_per_share: Decimal256,
+    pub total_staked: Uint128,
+    pub undistributed: Uint128,
+    pub unclaimed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FarmStakerResponse {
+    pub staked: Uint128,
+    pub pending_reward: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OrderResponse {
+    pub order_id: u64,
+    pub owner: Addr,
+    pub pair_id: u64,
+    pub input_token: TokenSelect,
+    pub input_amount: Uint128,
+    pub min_output: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OrdersResponse {
+    pub orders: Vec<OrderResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolFeesResponse {
+    pub token1_amount: Uint128,
     pub token2_amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Token2ForToken1PriceResponse {
+pub struct ReserveBalancesResponse {
+    pub token1_reserve: Uint128,
+    /// Contract balance of token1, shared by every pair, fee and deposit in that denom
+    pub token1_balance: Uint128,
+    /// Part of `token1_balance` not owed to any reserve, protocol fee or deposit
+    pub token1_excess: Uint128,
+    pub token1_donated: Uint128,
+    pub token2_reserve: Uint128,
+    pub token2_balance: Uint128,
+    pub token2_excess: Uint128,
+    pub token2_donated: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapResponse {
+    /// Average token2 per token1
+    pub token1_price: Decimal256,
+    /// Average token1 per token2
+    pub token2_price: Decimal256,
+    /// Timestamp of the observation the average starts from
+    pub start_timestamp: u64,
+    pub end_timestamp: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObservationResponse {
+    pub timestamp: u64,
+    pub token1_price_cumulative: Decimal256,
+    pub token2_price_cumulative: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObservationsResponse {
+    pub observations: Vec<ObservationResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DayStatsResponse {
+    pub day: u64,
+    pub token1_sold: Uint128,
+    pub token2_bought: Uint128,
+    pub token2_sold: Uint128,
+    pub token1_bought: Uint128,
+    pub swap_count: u64,
+    pub token1_treasury_fee: Uint128,
+    pub token2_treasury_fee: Uint128,
+    pub lp_minted: Uint128,
+    pub lp_burned: Uint128,
+    pub token1_reserve: Uint128,
+    pub token2_reserve: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DailyStatsResponse {
+    pub stats: Vec<DayStatsResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateSwapResponse {
+    pub output_amount: Uint128,
+    /// Part of the input left in the pool for liquidity providers
+    pub lp_fee_amount: Uint128,
+    /// Platform and tx fee due on the swap, in `fee_denom`
+    pub fee_amount: Uint128,
+    pub fee_denom: Denom,
+    /// The fee is kept from the input instead of being attached as `fee_amount`
+    pub fee_in_kind: bool,
+    /// Output token per input token before the swap
+    pub spot_price_before: Decimal256,
+    /// Output token per input token after the swap
+    pub spot_price_after: Decimal256,
+    pub price_impact_bps: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Token1ForExactToken2Response {
     pub token1_amount: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Token2ForExactToken1Response {
+    pub token2_amount: Uint128,
+}
+
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct ConfigResponse {
     pub owner: Addr,
+    pub lp_token_code_id: u64,
     pub bonding_code_id: u64,
     pub bonding_contract_address: Addr,
     pub fury_token_address: Addr,
@@ -124,10 +582,25 @@ pub struct ConfigResponse {
     pub usdc_denom: String,
     pub tx_fee: u64,
     pub platform_fee: u64,
+    pub lp_fee: u64,
+    pub fee_in_kind: bool,
+    pub enabled: bool,
+    pub max_price_impact_bps: u64,
+    pub flash_loan_fee: u64,
+    pub order_bounty: u64,
     pub lock_seconds: u64,
     pub discount: u64,
     pub daily_vesting_amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    /// Records the new contract version, for releases keeping the stored state as it is
+    Upgrade {},
+    /// Moves the pair of the single pair 1.0 release under the default pair id. The config fields
+    /// added since take their instantiation defaults, the lp token code id was not stored by 1.0.
+    FromV1 {
+        lp_token_code_id: u64,
+    },
+}
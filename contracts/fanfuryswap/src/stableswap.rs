@@ -0,0 +1,179 @@
+//! StableSwap invariant of Curve for the two tokens of a pair:
+//! `4A(x + y) + D = 4AD + D^3 / (4xy)`
+//!
+//! Both tokens are assumed to have the same decimals, the reserves are used as they are.
+
+use cosmwasm_std::{Decimal256, Fraction, StdError, StdResult, Uint128, Uint256};
+use std::convert::TryFrom;
+
+use crate::state::AmpRamp;
+use fanfury_common::token::THOUSAND;
+
+pub const MAX_AMP: u64 = 1_000_000;
+/// Largest factor the amplification may be multiplied or divided by in one ramp
+pub const MAX_AMP_CHANGE: u64 = 10;
+pub const MIN_RAMP_SECONDS: u64 = 86400;
+
+const N_COINS: u64 = 2;
+const MAX_ITERATIONS: usize = 255;
+
+/// Amplification at `now`, moving linearly along the ramp
+pub fn get_amp(ramp: &AmpRamp, now: u64) -> u64 {
+    if now >= ramp.future_time || ramp.future_time <= ramp.initial_time {
+        return ramp.future_amp;
+    }
+    let elapsed = now.saturating_sub(ramp.initial_time) as u128;
+    let duration = (ramp.future_time - ramp.initial_time) as u128;
+    let (initial_amp, future_amp) = (ramp.initial_amp as u128, ramp.future_amp as u128);
+    if future_amp > initial_amp {
+        (initial_amp + (future_amp - initial_amp) * elapsed / duration) as u64
+    } else {
+        (initial_amp - (initial_amp - future_amp) * elapsed / duration) as u64
+    }
+}
+
+fn get_ann(amp: u64) -> Uint256 {
+    Uint256::from(amp * N_COINS * N_COINS)
+}
+
+fn abs_diff(a: Uint256, b: Uint256) -> Uint256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+/// `D^3 / (4xy)`, computed in steps so it does not overflow
+fn get_d_product(d: Uint256, x: Uint256, y: Uint256) -> Uint256 {
+    let n = Uint256::from(N_COINS);
+    d * d / (x * n) * d / (y * n)
+}
+
+/// Invariant of the reserves, found with Newton's method
+fn compute_d(amp: u64, x: Uint128, y: Uint128) -> StdResult<Uint256> {
+    if x.is_zero() || y.is_zero() {
+        return Err(StdError::generic_err("No liquidity"));
+    }
+    let (x, y) = (Uint256::from(x), Uint256::from(y));
+    let n = Uint256::from(N_COINS);
+    let ann = get_ann(amp);
+    let sum = x + y;
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        let d_product = get_d_product(d, x, y);
+        let previous = d;
+        d = (ann * sum + d_product * n) * d / ((ann - Uint256::one()) * d + (n + Uint256::one()) * d_product);
+        if abs_diff(d, previous) <= Uint256::one() {
+            return Ok(d);
+        }
+    }
+    Err(StdError::generic_err("StableSwap invariant did not converge"))
+}
+
+/// Reserve of the other token keeping the invariant `d` once this reserve is `x`
+fn get_y(amp: u64, x: Uint256, d: Uint256) -> StdResult<Uint256> {
+    let n = Uint256::from(N_COINS);
+    let ann = get_ann(amp);
+    let c = d * d / (x * n) * d / (ann * n);
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        y = (y * y + c) / (y * n + b - d);
+        if abs_diff(y, previous) <= Uint256::one() {
+            return Ok(y);
+        }
+    }
+    Err(StdError::generic_err("StableSwap invariant did not converge"))
+}
+
+/// Output of selling `input_amount`, the lp fee being kept from the input. Rounded down.
+pub fn get_input_price(
+    amp: u64,
+    input_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    lp_fee: u64,
+) -> StdResult<Uint128> {
+    let d = compute_d(amp, input_reserve, output_reserve)?;
+    let input_amount_with_fee = input_amount * Uint128::from(THOUSAND - lp_fee) / Uint128::from(THOUSAND);
+    let output_reserve_after = get_y(amp, Uint256::from(input_reserve + input_amount_with_fee), d)?;
+    let output_amount = Uint256::from(output_reserve)
+        .checked_sub(output_reserve_after + Uint256::one())
+        .unwrap_or_default();
+    Ok(Uint128::try_from(output_amount)?)
+}
+
+/// Input needed to buy exactly `output_amount`, rounded up
+pub fn get_output_price(
+    amp: u64,
+    output_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    lp_fee: u64,
+) -> StdResult<Uint128> {
+    if output_amount >= output_reserve {
+        return Err(StdError::generic_err("Insufficient liquidity"));
+    }
+    let d = compute_d(amp, input_reserve, output_reserve)?;
+    let input_reserve_after = get_y(amp, Uint256::from(output_reserve - output_amount), d)?;
+    let input_amount_with_fee = input_reserve_after
+        .checked_sub(Uint256::from(input_reserve))
+        .unwrap_or_default()
+        + Uint256::one();
+    let kept = Uint256::from(THOUSAND - lp_fee);
+    let input_amount = (input_amount_with_fee * Uint256::from(THOUSAND) + kept - Uint256::one()) / kept;
+    Ok(Uint128::try_from(input_amount)?)
+}
+
+/// Output token per input token for an infinitesimal swap:
+/// `(y / x) * (4Ax + D^3 / 4xy) / (4Ay + D^3 / 4xy)`
+pub fn get_spot_price(amp: u64, input_reserve: Uint128, output_reserve: Uint128) -> StdResult<Decimal256> {
+    let d = compute_d(amp, input_reserve, output_reserve)?;
+    let (x, y) = (Uint256::from(input_reserve), Uint256::from(output_reserve));
+    let ann = get_ann(amp);
+    let d_product = get_d_product(d, x, y);
+    Ok(Decimal256::from_ratio(y, x) * Decimal256::from_ratio(ann * x + d_product, ann * y + d_product))
+}
+
+/// Basis points the spot price of the input token drops by when `input_amount` is added
+/// to the input reserve and `output_amount` taken from the output reserve
+pub fn get_price_impact_bps(
+    amp: u64,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    input_amount: Uint128,
+    output_amount: Uint128,
+    max_bps: u64,
+) -> StdResult<u64> {
+    let before = get_spot_price(amp, input_reserve, output_reserve)?;
+    let after = get_spot_price(amp, input_reserve + input_amount, output_reserve - output_amount)?;
+    let ratio = after.numerator() * Uint256::from(max_bps) / before.numerator();
+    Ok(max_bps.saturating_sub(Uint128::try_from(ratio)?.u128() as u64))
+}
+
+/// Largest part of a single sided deposit to swap so that what is left is at least in the
+/// ratio of the reserves with what is bought. There is no closed form, it is searched.
+pub fn get_zap_swap_amount(
+    amp: u64,
+    amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    fee: u64,
+) -> StdResult<Uint128> {
+    let (mut low, mut high) = (Uint128::zero(), amount);
+    while low < high {
+        let middle = low + (high - low + Uint128::new(1)) / Uint128::new(2);
+        let bought = get_input_price(amp, middle, input_reserve, output_reserve, fee)?;
+        let left = Uint256::from(amount - middle) * Uint256::from(output_reserve - bought);
+        if left >= Uint256::from(bought) * Uint256::from(input_reserve + middle) {
+            low = middle;
+        } else {
+            high = middle - Uint128::new(1);
+        }
+    }
+    Ok(low)
+}
@@ -1,11 +1,16 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Decimal256, Empty, Uint128};
 use cw20::Denom;
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
 
-pub const LP_TOKEN: Item<Addr> = Item::new("lp_token");
+use crate::msg::TokenSelect;
+
+/// The pair created at instantiation (USDC/FURY). Messages without a `pair_id` target it.
+pub const DEFAULT_PAIR_ID: u64 = 0;
+
+pub const LP_TOKEN: Map<u64, Addr> = Map::new("pair_lp_token");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Token {
@@ -13,12 +18,197 @@ pub struct Token {
     pub denom: Denom,
 }
 
-pub const TOKEN1: Item<Token> = Item::new("token1");
-pub const TOKEN2: Item<Token> = Item::new("token2");
+pub const TOKEN1: Map<u64, Token> = Map::new("pair_token1");
+pub const TOKEN2: Map<u64, Token> = Map::new("pair_token2");
+
+/// Bonding child of every pair other than the default one, which keeps it in `Config`.
+pub const PAIR_BONDING: Map<u64, Addr> = Map::new("pair_bonding");
+
+/// Side of the pair in which the platform and tx fees are paid when they are not taken in kind.
+/// Pairs without an entry, like the default one, use token1.
+pub const PAIR_FEE_TOKEN: Map<u64, TokenSelect> = Map::new("pair_fee_token");
+
+/// Amplification of a stable pair, moving linearly from `initial_amp` to `future_amp`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AmpRamp {
+    pub initial_amp: u64,
+    pub initial_time: u64,
+    pub future_amp: u64,
+    pub future_time: u64,
+}
+
+/// Pairs following the StableSwap invariant. Pairs without an entry are constant product.
+pub const STABLE_AMP: Map<u64, AmpRamp> = Map::new("stable_amp");
+
+/// Token2 sent through the cw20 `AddLiquidity` hook, waiting to be added by its owner
+pub const LIQUIDITY_DEPOSITS: Map<(u64, Addr), Uint128> = Map::new("liquidity_deposits");
+
+/// Running sums of the pair prices multiplied by the seconds they were in effect
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceCumulative {
+    /// Sum of token2 per token1 (`token2.reserve / token1.reserve`) over time
+    pub token1_price_cumulative: Decimal256,
+    /// Sum of token1 per token2 (`token1.reserve / token2.reserve`) over time
+    pub token2_price_cumulative: Decimal256,
+    pub timestamp: u64,
+}
+
+pub const PRICE_CUMULATIVE: Map<u64, PriceCumulative> = Map::new("price_cumulative");
+
+/// Snapshots of `PRICE_CUMULATIVE` keyed by (pair id, timestamp), used for twap queries
+pub const OBSERVATIONS: Map<(u64, u64), PriceCumulative> = Map::new("observations");
+/// Minimum number of seconds between two observations of a pair
+pub const OBSERVATION_INTERVAL: u64 = 60;
+/// Observations older than this many seconds are pruned as new ones are recorded
+pub const OBSERVATION_RETENTION: u64 = 7 * 86400;
+
+/// Platform and tx fees taken in kind from swap inputs, waiting to be collected by the treasury
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ProtocolFees {
+    pub token1_amount: Uint128,
+    pub token2_amount: Uint128,
+}
+
+pub const PROTOCOL_FEES: Map<u64, ProtocolFees> = Map::new("protocol_fees");
+
+/// Running totals of the protocol owned liquidity given to a pair with `AddToken`, kept for reporting
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Donations {
+    pub token1_amount: Uint128,
+    pub token2_amount: Uint128,
+}
+
+pub const DONATIONS: Map<u64, Donations> = Map::new("donations");
+
+pub const SECONDS_PER_DAY: u64 = 86400;
+
+/// Activity of a pair during one day
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct DailyStats {
+    /// Token1 swapped into the reserve, after any fee taken in kind
+    pub token1_sold: Uint128,
+    /// Token2 paid out of the reserve for `token1_sold`
+    pub token2_bought: Uint128,
+    pub token2_sold: Uint128,
+    pub token1_bought: Uint128,
+    pub swap_count: u64,
+    /// Platform and tx fees owed to the treasury, whether sent right away or taken in kind
+    pub token1_treasury_fee: Uint128,
+    pub token2_treasury_fee: Uint128,
+    pub lp_minted: Uint128,
+    pub lp_burned: Uint128,
+    /// Reserves after the last change of the day
+    pub token1_reserve: Uint128,
+    pub token2_reserve: Uint128,
+}
+
+/// Daily buckets keyed by (pair id, days since the unix epoch). Days without activity have none.
+pub const DAILY_STATS: Map<(u64, u64), DailyStats> = Map::new("daily_stats");
+
+/// Lp token farm of a pair, streaming FURY to its stakers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Farm {
+    /// FURY per second shared by the stakers
+    pub reward_rate: Uint128,
+    /// FURY earned per staked lp token since the farm started
+    pub reward_per_share: Decimal256,
+    pub total_staked: Uint128,
+    /// FURY funded and not streamed yet. Streaming stops when it runs out.
+    pub undistributed: Uint128,
+    /// FURY streamed and not claimed yet
+    pub unclaimed: Uint128,
+    pub last_update: u64,
+}
+
+pub const FARMS: Map<u64, Farm> = Map::new("farms");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct FarmStaker {
+    pub amount: Uint128,
+    /// `reward_per_share` of the farm when the pending reward was last updated
+    pub reward_per_share_paid: Decimal256,
+    pub pending_reward: Uint128,
+}
+
+pub const FARM_STAKERS: Map<(u64, Addr), FarmStaker> = Map::new("farm_stakers");
+
+/// Resting order selling `input_amount` of a side of the pair once the pool gives at least
+/// `min_output` for it. The input is escrowed by the contract until it is filled or cancelled.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LimitOrder {
+    pub owner: Addr,
+    pub pair_id: u64,
+    pub input_token: TokenSelect,
+    pub input_amount: Uint128,
+    pub min_output: Uint128,
+}
+
+pub const LIMIT_ORDERS: Map<u64, LimitOrder> = Map::new("limit_orders");
+
+/// Ids of the resting orders of each owner
+pub const OWNER_ORDERS: Map<(Addr, u64), Empty> = Map::new("owner_orders");
+
+/// Id that will be given to the next placed order
+pub const NEXT_ORDER_ID: Item<u64> = Item::new("next_order_id");
+
+/// Id that will be given to the next registered pair
+pub const NEXT_PAIR_ID: Item<u64> = Item::new("next_pair_id");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPair {
+    pub pair_id: u64,
+    pub with_bonding: bool,
+}
+
+/// Pair whose lp token / bonding child instantiation reply is outstanding
+pub const PENDING_PAIR: Item<PendingPair> = Item::new("pending_pair");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoan {
+    pub pair_id: u64,
+    pub token: TokenSelect,
+    /// Contract balance of the lent token before the loan was sent
+    pub balance_before: Uint128,
+    pub fee: Uint128,
+}
+
+/// Flash loan whose callback reply is outstanding. The pool cannot be used while it is set.
+pub const FLASH_LOAN: Item<FlashLoan> = Item::new("flash_loan");
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
+    pub owner: Addr,
+    pub lp_token_code_id: u64,
+    pub bonding_code_id: u64,
+    pub bonding_contract_address: Addr,
+    pub fury_token_address: Addr,
+    pub treasury_address: Addr,
+    pub usdc_denom: String,
+    pub tx_fee: u64,
+    pub platform_fee: u64,
+    /// Permille of the swap input left in the pool for liquidity providers
+    pub lp_fee: u64,
+    /// Take the platform and tx fee from the swap input instead of a separate payment in token1
+    pub fee_in_kind: bool,
+    /// Swaps and liquidity adds are rejected while disabled, removing liquidity is still allowed
+    pub enabled: bool,
+    /// Largest price move a single swap may cause, in basis points
+    pub max_price_impact_bps: u64,
+    /// Permille of a flash loan paid back on top of it, added to the reserve
+    pub flash_loan_fee: u64,
+    /// Permille of a filled limit order input paid to the keeper filling it
+    pub order_bounty: u64,
+    pub lock_seconds: u64,
+    pub discount: u64,
+    pub daily_vesting_amount: Uint128,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Config of the single pair 1.0 release, only read by its migration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV1 {
     pub owner: Addr,
     pub bonding_code_id: u64,
     pub bonding_contract_address: Addr,
@@ -32,4 +222,7 @@ pub struct Config {
     pub daily_vesting_amount: Uint128,
 }
 
-pub const CONFIG: Item<Config> = Item::new("config");
\ No newline at end of file
+pub const CONFIG_V1: Item<ConfigV1> = Item::new("config");
+pub const LP_TOKEN_V1: Item<Addr> = Item::new("lp_token");
+pub const TOKEN1_V1: Item<Token> = Item::new("token1");
+pub const TOKEN2_V1: Item<Token> = Item::new("token2");
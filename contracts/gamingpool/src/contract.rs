@@ -12,6 +12,8 @@ use cw20::Cw20QueryMsg;
 use cw2::set_contract_version;
 
 use crate::error::ContractError;
+use fanfury_common::access;
+use fanfury_common::migrate::check_migration_version;
 use crate::execute::{cancel_game, claim_refund, claim_reward, create_pool, execute_sweep,
                      game_pool_bid_submit, game_pool_reward_distribute, lock_game,
                      save_team_details, set_platform_fee_wallets,
@@ -157,6 +159,16 @@ pub fn execute(
             amount,
             pool_id, max_spread
         } => swap(deps, env, info, amount, pool_id, max_spread),
+        ExecuteMsg::Access(msg) => {
+            let config = CONFIG.load(deps.storage)?;
+            Ok(access::execute_access_msg(deps, &env, &info, &config.admin_address, msg, |storage, new_owner| {
+                CONFIG.update(storage, |mut config| -> StdResult<_> {
+                    config.admin_address = new_owner.clone();
+                    Ok(config)
+                })?;
+                Ok(())
+            })?)
+        }
     }
 }
 
@@ -166,7 +178,16 @@ pub fn execute(
 // We can add expose specific state properties to
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+    let previous_version = check_migration_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    match msg {
+        MigrateMsg::Upgrade {} => {}
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -217,7 +238,15 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         } => to_binary(&query_total_fees(
             deps,
             amount,
-        )?)
+        )?),
+        QueryMsg::Ownership {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&access::query_ownership(deps.storage, config.admin_address)?)
+        }
+        QueryMsg::RoleMembers { role, start_after, limit } => {
+            let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+            to_binary(&access::query_role_members(deps.storage, role, start_after, limit)?)
+        }
     }
 }
 
@@ -1,11 +1,16 @@
 use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
+use fanfury_common::CommonError;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error(transparent)]
+    Common(#[from] CommonError),
+
     #[error("UnauthorizedInvoker")]
     UnauthorizedInvoker {
         invoker: String
@@ -76,16 +81,4 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
-
-    #[error("Disabled")]
-    Disabled {},
-    
-    #[error("Amount of the native coin inputed is zero")]
-    NativeInputZero {},
-    
-    #[error("Token type mismatch")]
-    TokenTypeMismatch {},
-
-    #[error("Amount of the cw20 coin inputed is zero")]
-    Cw20InputZero {},
 }
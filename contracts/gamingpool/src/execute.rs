@@ -14,7 +14,7 @@ use crate::contract::{CLAIMED_REFUND, CLAIMED_REWARD, DUMMY_WALLET, GAME_CANCELL
                       INITIAL_TEAM_RANK, NINETY_NINE_NINE_PERCENT, REWARDS_DISTRIBUTED,
                       REWARDS_NOT_DISTRIBUTED, UNCLAIMED_REFUND, UNCLAIMED_REWARD};
 use crate::ContractError;
-use crate::msg::{BalanceResponse, ReceivedMsg};
+use crate::msg::{BalanceResponse, ReceivedMsg, Role};
 use crate::query::{get_team_count_for_user_in_pool_type,
                    query_pool_details, query_pool_type_details, query_swap_data_for_pool};
 use crate::state::{CONFIG, CONTRACT_POOL_COUNT, CURRENT_REWARD_FOR_POOL, FeeDetails,
@@ -23,9 +23,9 @@ use crate::state::{CONFIG, CONTRACT_POOL_COUNT, CURRENT_REWARD_FOR_POOL, FeeDeta
                    PoolTypeDetails, SWAP_BALANCE_INFO, SwapBalanceDetails,
                    WalletPercentage, WalletTransferDetails};
                 
-use fanfuryswap::msg::{QueryMsg as FanfuryswapQueryMsg, ExecuteMsg as FanfuryswapExecuteMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse, ConfigResponse as FanfuryswapConfigResponse, TokenSelect};
-use crate::util;
-use crate::util::{NORMAL_DECIMAL, THOUSAND};
+use fanfury_common::swap::{SwapExecuteMsg, SwapFeeConfigResponse, SwapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse, TokenSelect};
+use fanfury_common::access;
+use fanfury_common::token::{self, NORMAL_DECIMAL, THOUSAND};
 
 pub fn set_platform_fee_wallets(
     deps: DepsMut,
@@ -33,7 +33,7 @@ pub fn set_platform_fee_wallets(
     wallet_percentages: Vec<WalletPercentage>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin_address {
+    if access::check_role(deps.storage, &config.admin_address, &info.sender, Role::FeeManager).is_err() {
         return Err(ContractError::UnauthorizedInvoker {
             invoker: info.sender.to_string(),
         });
@@ -65,7 +65,7 @@ pub fn set_pool_type_params(
     wallet_percentages: Vec<WalletPercentage>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin_address {
+    if access::check_role(deps.storage, &config.admin_address, &info.sender, Role::FeeManager).is_err() {
         return Err(ContractError::UnauthorizedInvoker {
             invoker: info.sender.to_string(),
         });
@@ -180,7 +180,7 @@ pub fn cancel_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Respon
 
 pub fn lock_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin_address {
+    if access::check_role(deps.storage, &config.admin_address, &info.sender, Role::Operator).is_err() {
         return Err(ContractError::UnauthorizedInvoker {
             invoker: info.sender.to_string(),
         });
@@ -263,7 +263,7 @@ pub fn create_pool(
     pool_type: String,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin_address {
+    if access::check_role(deps.storage, &config.admin_address, &info.sender, Role::Operator).is_err() {
         return Err(ContractError::UnauthorizedInvoker {
             invoker: info.sender.to_string(),
         });
@@ -419,7 +419,7 @@ pub fn game_pool_bid_submit(
         }
 
         let balance = Balance::from(info.funds);
-        let usdc_amount = util::get_amount_of_denom(balance, Denom::Native(config.usdc_ibc_symbol.clone()))?;
+        let usdc_amount = token::get_amount_of_denom(balance, Denom::Native(config.usdc_ibc_symbol.clone()))?;
         if usdc_amount < required_platform_fee_ust + transaction_fee {
             return Err(ContractError::InsufficientFeesUst {});
         }
@@ -432,7 +432,8 @@ pub fn game_pool_bid_submit(
         
         let pool_fee_response: Token1ForToken2PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: config.clone().pool_address.to_string(),
-            msg: to_binary(&FanfuryswapQueryMsg::Token1ForToken2Price {
+            msg: to_binary(&SwapQueryMsg::Token1ForToken2Price {
+                pair_id: None,
                 token1_amount: pool_type_details.pool_fee
             })?,
         }))?;
@@ -563,33 +564,45 @@ pub fn game_pool_bid_submit(
     //         amount: platform_fees_for_swap,
     //     }],
     // }));
-    let swap_config_response: FanfuryswapConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+    let swap_config_response: SwapFeeConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: config.clone().pool_address.to_string(),
-        msg: to_binary(&FanfuryswapQueryMsg::Config {  } )?,
+        msg: to_binary(&SwapQueryMsg::Config {  } )?,
     }))?;
 
     let expected_usdc_response: Token2ForToken1PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: config.clone().pool_address.to_string(),
-        msg: to_binary(&FanfuryswapQueryMsg::Token2ForToken1Price { 
+        msg: to_binary(&SwapQueryMsg::Token2ForToken1Price { 
+            pair_id: None,
             token2_amount: amount
         } )?,
     }))?;
 
-    let platform_fees_for_swap = expected_usdc_response.token1_amount * Uint128::from(swap_config_response.platform_fee + swap_config_response.tx_fee) / Uint128::from(THOUSAND);
+    // Fees taken in kind come out of the swapped FURY itself
+    let platform_fees_for_swap = if swap_config_response.fee_in_kind {
+        Uint128::zero()
+    } else {
+        expected_usdc_response.token1_amount * Uint128::from(swap_config_response.platform_fee + swap_config_response.tx_fee) / Uint128::from(THOUSAND)
+    };
+    let mut swap_funds: Vec<Coin> = vec![];
+    if !platform_fees_for_swap.is_zero() {
+        swap_funds.push(Coin {
+            denom: config.usdc_ibc_symbol.clone(),
+            amount: platform_fees_for_swap,
+        });
+    }
 
     messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: config.clone().pool_address.to_string(),
-        msg: to_binary(&FanfuryswapExecuteMsg::Swap {
+        msg: to_binary(&SwapExecuteMsg::Swap {
+                pair_id: None,
                 input_token: TokenSelect::Token2,
                 input_amount: amount,
                 min_output: expected_usdc_response.token1_amount,
                 fee_amount: platform_fees_for_swap,
-                expiration: None
+                expiration: None,
+                recipient: None,
             })?,
-        funds: vec![Coin {
-            denom: config.usdc_ibc_symbol.clone(),
-            amount: platform_fees_for_swap,
-        }],
+        funds: swap_funds,
     }));
     return Ok(Response::new()
         .add_attribute("pool_id", pool_id_return.clone())
@@ -716,7 +729,8 @@ pub fn claim_reward(
     // )?;
     let expected_usdc_response: Token2ForToken1PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: config.clone().pool_address.to_string(),
-        msg: to_binary(&FanfuryswapQueryMsg::Token2ForToken1Price { 
+        msg: to_binary(&SwapQueryMsg::Token2ForToken1Price { 
+            pair_id: None,
             token2_amount: user_reward
         } )?,
     }))?;
@@ -864,17 +878,20 @@ pub fn claim_refund(
         //     },
         // )?;
 
-        let swap_config_response: FanfuryswapConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        let swap_config_response: SwapFeeConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: config.clone().pool_address.to_string(),
-            msg: to_binary(&FanfuryswapQueryMsg::Config {  } )?,
+            msg: to_binary(&SwapQueryMsg::Config {  } )?,
         }))?;
     
-        swap_fee = total_refund_amount * Uint128::from(swap_config_response.platform_fee + swap_config_response.tx_fee) / Uint128::from(THOUSAND);
+        if !swap_config_response.fee_in_kind {
+            swap_fee = total_refund_amount * Uint128::from(swap_config_response.platform_fee + swap_config_response.tx_fee) / Uint128::from(THOUSAND);
+        }
     
     }
     let expected_fury_response: Token1ForToken2PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: config.clone().pool_address.to_string(),
-        msg: to_binary(&FanfuryswapQueryMsg::Token1ForToken2Price { 
+        msg: to_binary(&SwapQueryMsg::Token1ForToken2Price { 
+            pair_id: None,
             token1_amount: total_refund_amount
         } )?,
     }))?;
@@ -882,12 +899,14 @@ pub fn claim_refund(
     let final_amount = total_refund_amount.add(swap_fee);
     messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: config.pool_address.to_string(),
-        msg: to_binary(&FanfuryswapExecuteMsg::Swap {
+        msg: to_binary(&SwapExecuteMsg::Swap {
+            pair_id: None,
             input_token: TokenSelect::Token1,
             input_amount: total_refund_amount,
             min_output: expected_fury_response.token2_amount,
             fee_amount: swap_fee,
-            expiration: None
+            expiration: None,
+            recipient: None,
         })?,
         funds: vec![Coin {
             denom: config.usdc_ibc_symbol.clone(),
@@ -923,7 +942,7 @@ pub fn game_pool_reward_distribute(
     ust_for_rake: Uint128,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin_address {
+    if access::check_role(deps.storage, &config.admin_address, &info.sender, Role::Operator).is_err() {
         return Err(ContractError::UnauthorizedInvoker {
             invoker: info.sender.to_string(),
         });
@@ -1151,7 +1170,7 @@ pub fn swap(
     max_spread: Option<Decimal>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin_address {
+    if access::check_role(deps.storage, &config.admin_address, &info.sender, Role::Operator).is_err() {
         return Err(ContractError::UnauthorizedInvoker {
             invoker: info.sender.to_string(),
         });
@@ -1214,19 +1233,24 @@ pub fn swap(
     //     },
     // )?;
 
-    let swap_config_response: FanfuryswapConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+    let swap_config_response: SwapFeeConfigResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: config.clone().pool_address.to_string(),
-        msg: to_binary(&FanfuryswapQueryMsg::Config {  } )?,
+        msg: to_binary(&SwapQueryMsg::Config {  } )?,
     }))?;
 
-    let swap_fee = amount * Uint128::from(swap_config_response.platform_fee + swap_config_response.tx_fee) / Uint128::from(THOUSAND);
+    let swap_fee = if swap_config_response.fee_in_kind {
+        Uint128::zero()
+    } else {
+        amount * Uint128::from(swap_config_response.platform_fee + swap_config_response.tx_fee) / Uint128::from(THOUSAND)
+    };
 
     //let final_amount = ust_asset.amount.clone().add(swap_fee).add(tax);
     let final_amount = amount.add(swap_fee);
 
     let expected_fury_response: Token1ForToken2PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: config.clone().pool_address.to_string(),
-        msg: to_binary(&FanfuryswapQueryMsg::Token1ForToken2Price { 
+        msg: to_binary(&SwapQueryMsg::Token1ForToken2Price { 
+            pair_id: None,
             token1_amount: amount
         } )?,
     }))?;
@@ -1234,12 +1258,14 @@ pub fn swap(
     let submsg = SubMsg::reply_on_success(
         CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: config.pool_address.to_string(),
-            msg: to_binary(&FanfuryswapExecuteMsg::Swap {
+            msg: to_binary(&SwapExecuteMsg::Swap {
+                pair_id: None,
                 input_token: TokenSelect::Token1,
                 input_amount: amount,
                 min_output: expected_fury_response.token2_amount,
                 fee_amount: swap_fee,
-                expiration: None
+                expiration: None,
+                recipient: None,
             })?,
             funds: vec![Coin {
                 denom: config.usdc_ibc_symbol.clone(),
@@ -1,5 +1,4 @@
 pub use crate::error::ContractError;
-mod util;
 pub mod allowances;
 pub mod contract;
 // pub mod enumerable;
@@ -7,6 +7,8 @@ use cw20::{Cw20ReceiveMsg, Logo};
 use crate::ContractError;
 use crate::state::{GameResult, SwapBalanceDetails, WalletPercentage};
 
+pub use fanfury_common::access::{AccessMsg, OwnershipResponse, Role, RoleMembersResponse};
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMarketingInfo {
     pub project: Option<String>,
@@ -28,7 +30,11 @@ pub struct InstantiateMsg {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    /// Records the new contract version, for releases keeping the stored state as it is
+    Upgrade {},
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -79,6 +85,8 @@ pub enum ExecuteMsg {
         pool_id: String,
         max_spread: Option<Decimal>,
     },
+    /// Ownership transfer and role management
+    Access(AccessMsg),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -128,6 +136,14 @@ pub enum QueryMsg {
     GetTotalFees {
         amount: Uint128
     },
+    /// Owner and the pending ownership offer
+    Ownership {},
+    /// Addresses granted the role, the owner not included
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 
@@ -6,9 +6,11 @@ mod tests {
     use cosmwasm_std::Addr;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
-    use crate::contract::{CLAIMED_REWARD, GAME_CANCELLED, GAME_COMPLETED, GAME_POOL_OPEN, INITIAL_REFUND_AMOUNT, instantiate};
-    use crate::execute::{cancel_game, claim_refund, claim_reward, create_pool, game_pool_bid_submit, game_pool_reward_distribute, lock_game, save_team_details, set_platform_fee_wallets, set_pool_type_params};
-    use crate::msg::InstantiateMsg;
+    use crate::contract::{CLAIMED_REWARD, CONTRACT_NAME, execute, GAME_CANCELLED, GAME_COMPLETED, GAME_POOL_OPEN, INITIAL_REFUND_AMOUNT, instantiate, migrate};
+    use crate::error::ContractError;
+    use fanfury_common::CommonError;
+    use crate::execute::{cancel_game, claim_refund, claim_reward, create_pool, game_pool_bid_submit, game_pool_reward_distribute, lock_game, save_team_details, set_platform_fee_wallets, set_pool_type_params, swap};
+    use crate::msg::{AccessMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, Role};
     use crate::query::{get_team_count_for_user_in_pool_type, query_game_details, query_pool_details, query_team_details};
     use crate::state::{GameResult, PLATFORM_WALLET_PERCENTAGES, POOL_TEAM_DETAILS, WalletPercentage};
 
@@ -2758,5 +2760,102 @@ mod tests {
             assert_eq!(wallet.wallet_name, "rake_3".to_string());
         }
     }
-}
 
+    #[test]
+    fn test_roles_run_the_admin_jobs() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            fury_token_address: "cwtoken11111".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            pool_address: "astroport".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            transaction_fee: Uint128::from(100000u128),
+            game_id: "Game001".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let operatorInfo = mock_info("operator001", &[]);
+        let feeManagerInfo = mock_info("feemanager001", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+
+        // only the owner grants roles
+        let grant_operator = ExecuteMsg::Access(AccessMsg::GrantRole {
+            role: Role::Operator,
+            address: "operator001".to_string(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), operatorInfo.clone(), grant_operator.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), adminInfo.clone(), grant_operator).unwrap();
+        execute(deps.as_mut(), mock_env(), adminInfo.clone(), ExecuteMsg::Access(AccessMsg::GrantRole {
+            role: Role::FeeManager,
+            address: "feemanager001".to_string(),
+        })).unwrap();
+
+        // the fee manager sets the fees, the operator cannot
+        let wallets = vec![WalletPercentage {
+            wallet_address: "rake_1".to_string(),
+            wallet_name: "rake_1".to_string(),
+            percentage: 100u32,
+        }];
+        let err = set_platform_fee_wallets(deps.as_mut(), operatorInfo.clone(), wallets.clone()).unwrap_err();
+        assert_eq!(err, ContractError::UnauthorizedInvoker { invoker: "operator001".to_string() });
+        set_platform_fee_wallets(deps.as_mut(), feeManagerInfo.clone(), wallets.clone()).unwrap();
+        let err = set_pool_type_params(deps.as_mut(), mock_env(), operatorInfo.clone(), "oneToOne".to_string(),
+                                       Uint128::from(144262u128), 2, 10, 2, wallets.clone()).unwrap_err();
+        assert_eq!(err, ContractError::UnauthorizedInvoker { invoker: "operator001".to_string() });
+        set_pool_type_params(deps.as_mut(), mock_env(), feeManagerInfo.clone(), "oneToOne".to_string(),
+                             Uint128::from(144262u128), 2, 10, 2, wallets).unwrap();
+
+        // the operator runs the pool and game jobs, the fee manager cannot
+        let err = create_pool(deps.as_mut(), mock_env(), feeManagerInfo.clone(), "oneToOne".to_string()).unwrap_err();
+        assert_eq!(err, ContractError::UnauthorizedInvoker { invoker: "feemanager001".to_string() });
+        let rsp = create_pool(deps.as_mut(), mock_env(), operatorInfo.clone(), "oneToOne".to_string()).unwrap();
+        let poolId = rsp.attributes[0].value.clone();
+
+        // swapping goes past the role check, to the token balance query the mock cannot answer
+        let err = swap(deps.as_mut(), mock_env(), feeManagerInfo.clone(), Uint128::from(100u128), poolId.clone(), None).unwrap_err();
+        assert_eq!(err, ContractError::UnauthorizedInvoker { invoker: "feemanager001".to_string() });
+        let err = swap(deps.as_mut(), mock_env(), operatorInfo.clone(), Uint128::from(100u128), poolId, None).unwrap_err();
+        assert_ne!(err, ContractError::UnauthorizedInvoker { invoker: "operator001".to_string() });
+
+        let err = lock_game(deps.as_mut(), mock_env(), feeManagerInfo).unwrap_err();
+        assert_eq!(err, ContractError::UnauthorizedInvoker { invoker: "feemanager001".to_string() });
+        lock_game(deps.as_mut(), mock_env(), operatorInfo.clone()).unwrap();
+
+    }
+
+    #[test]
+    fn test_migrate_refuses_downgrade() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            fury_token_address: "cwtoken11111".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            pool_address: "astroport".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            transaction_fee: Uint128::from(100000u128),
+            game_id: "Game001".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("admin11111", &[]), instantiate_msg).unwrap();
+
+        cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.9.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::CannotMigrateVersion {
+            previous_version: "99.0.0".to_string(),
+            new_version: env!("CARGO_PKG_VERSION").to_string(),
+        }));
+
+        cw2::set_contract_version(&mut deps.storage, "crates.io:clubstaking", "1.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert_eq!(err, ContractError::Common(CommonError::CannotMigrate {
+            previous_contract: "crates.io:clubstaking".to_string(),
+        }));
+    }
+}
@@ -0,0 +1,363 @@
+use cosmwasm_std::{Addr, BlockInfo, DepsMut, Empty, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint64};
+use cw_storage_plus::{Bound, Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommonError;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Roles the owner grants to other addresses. The owner holds every role.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Runs the batch jobs, such as the reward distribution
+    Operator,
+    /// Pauses the contract
+    Pauser,
+    /// Changes the fees
+    FeeManager,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Operator => "operator",
+            Role::Pauser => "pauser",
+            Role::FeeManager => "fee_manager",
+        }
+    }
+}
+
+/// Ownership and role management, wrapped in `ExecuteMsg::Access` by every contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessMsg {
+    /// Offers the ownership to `new_owner`, who has `expires_in` seconds to accept it. Only callable by the owner.
+    ProposeNewOwner {
+        new_owner: String,
+        expires_in: u64,
+    },
+    /// Only callable by the proposed owner
+    AcceptOwnership {},
+    /// Withdraws the pending ownership offer. Only callable by the owner.
+    CancelOwnershipTransfer {},
+    /// Only callable by the owner
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    /// Only callable by the owner
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipResponse {
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+    pub pending_expiry: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<Addr>,
+}
+
+/// Ownership offered by the owner, to be accepted by `new_owner` before `expiry`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub new_owner: Addr,
+    /// Block time in seconds from which the offer can no longer be accepted
+    pub expiry: u64,
+}
+
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+
+/// Holders of each role besides the owner, keyed by the role name
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+
+/// Checks `sender` is `owner` or was granted `role`. The owner holds every role.
+pub fn check_role(storage: &dyn Storage, owner: &Addr, sender: &Addr, role: Role) -> Result<(), CommonError> {
+    if sender == owner || ROLES.has(storage, (role.as_str(), sender)) {
+        return Ok(());
+    }
+    Err(CommonError::Unauthorized {})
+}
+
+/// Fails while the contract is disabled
+pub fn check_enabled(enabled: bool) -> Result<(), CommonError> {
+    if !enabled {
+        return Err(CommonError::Disabled {});
+    }
+    Ok(())
+}
+
+/// Checks `sender` may switch the contract to `enabled`.
+/// A pauser can only disable, enabling again is left to the owner.
+pub fn check_update_enabled(
+    storage: &dyn Storage,
+    owner: &Addr,
+    sender: &Addr,
+    enabled: bool,
+) -> Result<(), CommonError> {
+    if enabled && sender != owner {
+        return Err(CommonError::Unauthorized {});
+    }
+    check_role(storage, owner, sender, Role::Pauser)
+}
+
+/// Runs `msg` for a contract owned by `owner`. An accepted transfer is handed to `save_owner`,
+/// which stores the new owner in the contract config.
+pub fn execute_access_msg(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    owner: &Addr,
+    msg: AccessMsg,
+    save_owner: impl FnOnce(&mut dyn Storage, &Addr) -> StdResult<()>,
+) -> Result<Response, CommonError> {
+    match msg {
+        AccessMsg::ProposeNewOwner { new_owner, expires_in } => {
+            let new_owner = deps.api.addr_validate(&new_owner)?;
+            propose_new_owner(deps.storage, &env.block, owner, &info.sender, new_owner, expires_in)
+        }
+        AccessMsg::AcceptOwnership {} => {
+            let new_owner = accept_ownership(deps.storage, &env.block, &info.sender)?;
+            save_owner(deps.storage, &new_owner)?;
+            Ok(Response::new()
+                .add_attribute("action", "accept_ownership")
+                .add_attribute("owner", new_owner))
+        }
+        AccessMsg::CancelOwnershipTransfer {} => cancel_ownership_transfer(deps.storage, owner, &info.sender),
+        AccessMsg::GrantRole { role, address } => {
+            let address = deps.api.addr_validate(&address)?;
+            grant_role(deps.storage, owner, &info.sender, role, address)
+        }
+        AccessMsg::RevokeRole { role, address } => {
+            let address = deps.api.addr_validate(&address)?;
+            revoke_role(deps.storage, owner, &info.sender, role, address)
+        }
+    }
+}
+
+/// Offers the ownership to `new_owner` for `expires_in` seconds, replacing any pending offer
+pub fn propose_new_owner(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    owner: &Addr,
+    sender: &Addr,
+    new_owner: Addr,
+    expires_in: u64,
+) -> Result<Response, CommonError> {
+    if sender != owner {
+        return Err(CommonError::Unauthorized {});
+    }
+    let expiry = Uint64::from(block.time.seconds())
+        .checked_add(Uint64::from(expires_in))
+        .map_err(StdError::from)?
+        .u64();
+    PENDING_OWNER.save(storage, &PendingOwner {
+        new_owner: new_owner.clone(),
+        expiry,
+    })?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("new_owner", new_owner)
+        .add_attribute("expiry", expiry.to_string()))
+}
+
+/// Takes the pending offer made to `sender`. Returns the new owner, for the caller to store.
+pub fn accept_ownership(storage: &mut dyn Storage, block: &BlockInfo, sender: &Addr) -> Result<Addr, CommonError> {
+    let pending = PENDING_OWNER
+        .may_load(storage)?
+        .ok_or(CommonError::NoPendingOwner {})?;
+    if pending.new_owner != *sender {
+        return Err(CommonError::Unauthorized {});
+    }
+    if block.time.seconds() >= pending.expiry {
+        return Err(CommonError::OwnershipProposalExpired {});
+    }
+    PENDING_OWNER.remove(storage);
+    Ok(pending.new_owner)
+}
+
+pub fn cancel_ownership_transfer(storage: &mut dyn Storage, owner: &Addr, sender: &Addr) -> Result<Response, CommonError> {
+    if sender != owner {
+        return Err(CommonError::Unauthorized {});
+    }
+    if PENDING_OWNER.may_load(storage)?.is_none() {
+        return Err(CommonError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(storage);
+    Ok(Response::new().add_attribute("action", "cancel_ownership_transfer"))
+}
+
+pub fn grant_role(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    sender: &Addr,
+    role: Role,
+    address: Addr,
+) -> Result<Response, CommonError> {
+    if sender != owner {
+        return Err(CommonError::Unauthorized {});
+    }
+    ROLES.save(storage, (role.as_str(), &address), &Empty {})?;
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn revoke_role(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    sender: &Addr,
+    role: Role,
+    address: Addr,
+) -> Result<Response, CommonError> {
+    if sender != owner {
+        return Err(CommonError::Unauthorized {});
+    }
+    ROLES.remove(storage, (role.as_str(), &address));
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn query_ownership(storage: &dyn Storage, owner: Addr) -> StdResult<OwnershipResponse> {
+    let pending = PENDING_OWNER.may_load(storage)?;
+    Ok(OwnershipResponse {
+        owner,
+        pending_owner: pending.as_ref().map(|pending| pending.new_owner.clone()),
+        pending_expiry: pending.map(|pending| pending.expiry),
+    })
+}
+
+pub fn query_role_members(
+    storage: &dyn Storage,
+    role: Role,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<RoleMembersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let members = ROLES
+        .prefix(role.as_str())
+        .keys(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(RoleMembersResponse { role, members })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    #[test]
+    fn check_role_accepts_owner_and_members() {
+        let mut deps = mock_dependencies();
+        let owner = Addr::unchecked("owner");
+        let operator = Addr::unchecked("operator");
+
+        // the owner holds every role
+        check_role(deps.as_ref().storage, &owner, &owner, Role::Operator).unwrap();
+        check_role(deps.as_ref().storage, &owner, &owner, Role::FeeManager).unwrap();
+
+        let err = check_role(deps.as_ref().storage, &owner, &operator, Role::Operator).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+        let err = grant_role(deps.as_mut().storage, &owner, &operator, Role::Operator, operator.clone()).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+
+        // a role only grants itself, until it is revoked
+        grant_role(deps.as_mut().storage, &owner, &owner, Role::Operator, operator.clone()).unwrap();
+        check_role(deps.as_ref().storage, &owner, &operator, Role::Operator).unwrap();
+        let err = check_role(deps.as_ref().storage, &owner, &operator, Role::FeeManager).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+        revoke_role(deps.as_mut().storage, &owner, &owner, Role::Operator, operator.clone()).unwrap();
+        let err = check_role(deps.as_ref().storage, &owner, &operator, Role::Operator).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+    }
+
+    #[test]
+    fn check_update_enabled_lets_pausers_only_disable() {
+        let mut deps = mock_dependencies();
+        let owner = Addr::unchecked("owner");
+        let pauser = Addr::unchecked("pauser");
+        grant_role(deps.as_mut().storage, &owner, &owner, Role::Pauser, pauser.clone()).unwrap();
+
+        check_update_enabled(deps.as_ref().storage, &owner, &pauser, false).unwrap();
+        let err = check_update_enabled(deps.as_ref().storage, &owner, &pauser, true).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+        check_update_enabled(deps.as_ref().storage, &owner, &owner, true).unwrap();
+    }
+
+    #[test]
+    fn accept_ownership_checks_sender_and_expiry() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let new_owner = Addr::unchecked("new_owner");
+
+        let err = accept_ownership(deps.as_mut().storage, &env.block, &new_owner).unwrap_err();
+        assert_eq!(err, CommonError::NoPendingOwner {});
+
+        let err = propose_new_owner(deps.as_mut().storage, &env.block, &owner, &new_owner, new_owner.clone(), 100).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+        let err = propose_new_owner(deps.as_mut().storage, &env.block, &owner, &owner, new_owner.clone(), u64::MAX).unwrap_err();
+        assert!(matches!(err, CommonError::Std(StdError::Overflow { .. })));
+        propose_new_owner(deps.as_mut().storage, &env.block, &owner, &owner, new_owner.clone(), 100).unwrap();
+
+        // only the proposed owner accepts, and only before the expiry
+        let err = accept_ownership(deps.as_mut().storage, &env.block, &owner).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+        env.block.time = env.block.time.plus_seconds(100);
+        let err = accept_ownership(deps.as_mut().storage, &env.block, &new_owner).unwrap_err();
+        assert_eq!(err, CommonError::OwnershipProposalExpired {});
+
+        // a new offer replaces the expired one and is taken once
+        propose_new_owner(deps.as_mut().storage, &env.block, &owner, &owner, new_owner.clone(), 100).unwrap();
+        let accepted = accept_ownership(deps.as_mut().storage, &env.block, &new_owner).unwrap();
+        assert_eq!(accepted, new_owner);
+        let ownership = query_ownership(deps.as_ref().storage, accepted).unwrap();
+        assert_eq!(ownership.pending_owner, None);
+        let err = accept_ownership(deps.as_mut().storage, &env.block, &new_owner).unwrap_err();
+        assert_eq!(err, CommonError::NoPendingOwner {});
+    }
+
+    #[test]
+    fn execute_access_msg_hands_the_accepted_owner_over() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let new_owner = Addr::unchecked("new_owner");
+        let propose = AccessMsg::ProposeNewOwner {
+            new_owner: new_owner.to_string(),
+            expires_in: 100,
+        };
+
+        let err = execute_access_msg(deps.as_mut(), &env, &mock_info("new_owner", &[]), &owner, propose.clone(), |_, _| {
+            panic!("nothing to accept")
+        }).unwrap_err();
+        assert_eq!(err, CommonError::Unauthorized {});
+        execute_access_msg(deps.as_mut(), &env, &mock_info("owner", &[]), &owner, propose, |_, _| {
+            panic!("nothing to accept")
+        }).unwrap();
+
+        let mut saved = None;
+        let res = execute_access_msg(deps.as_mut(), &env, &mock_info("new_owner", &[]), &owner, AccessMsg::AcceptOwnership {}, |_, accepted| {
+            saved = Some(accepted.clone());
+            Ok(())
+        }).unwrap();
+        assert_eq!(saved, Some(new_owner.clone()));
+        assert_eq!(res.attributes[1].value, new_owner.to_string());
+    }
+}
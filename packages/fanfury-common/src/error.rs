@@ -0,0 +1,48 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+/// Errors of the shared helpers. Each contract wraps them in its `ContractError::Common`.
+#[derive(Error, Debug, PartialEq)]
+pub enum CommonError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Disabled")]
+    Disabled {},
+
+    #[error("Amount of the native coin inputed is zero")]
+    NativeInputZero {},
+
+    #[error("Amount of the cw20 coin inputed is zero")]
+    Cw20InputZero {},
+
+    #[error("Token type mismatch")]
+    TokenTypeMismatch {},
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("The ownership offer has expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Cannot migrate from different contract type: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from newer version {previous_version} to {new_version}")]
+    CannotMigrateVersion {
+        previous_version: String,
+        new_version: String,
+    },
+
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+}
+
+impl From<semver::Error> for CommonError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
+}
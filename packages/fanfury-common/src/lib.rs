@@ -0,0 +1,7 @@
+pub mod access;
+pub mod error;
+pub mod migrate;
+pub mod swap;
+pub mod token;
+
+pub use crate::error::CommonError;
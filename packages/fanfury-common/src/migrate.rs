@@ -0,0 +1,67 @@
+use cosmwasm_std::Storage;
+use cw2::get_contract_version;
+use semver::Version;
+
+use crate::error::CommonError;
+
+/// Checks the stored cw2 info belongs to `contract_name` and is not newer than `contract_version`.
+/// Returns the version the contract is migrated from.
+pub fn check_migration_version(
+    storage: &dyn Storage,
+    contract_name: &str,
+    contract_version: &str,
+) -> Result<Version, CommonError> {
+    let stored = get_contract_version(storage)?;
+    if stored.contract != contract_name {
+        return Err(CommonError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+    let previous_version: Version = stored.version.parse()?;
+    let new_version: Version = contract_version.parse()?;
+    if previous_version > new_version {
+        return Err(CommonError::CannotMigrateVersion {
+            previous_version: stored.version,
+            new_version: contract_version.to_string(),
+        });
+    }
+    Ok(previous_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cw2::set_contract_version;
+
+    #[test]
+    fn check_migration_version_returns_previous_version() {
+        let mut deps = mock_dependencies();
+        set_contract_version(deps.as_mut().storage, "crates.io:pool", "1.0.0").unwrap();
+        let previous_version = check_migration_version(deps.as_ref().storage, "crates.io:pool", "1.1.0").unwrap();
+        assert_eq!(previous_version, Version::new(1, 0, 0));
+        // migrating to the same version is allowed
+        check_migration_version(deps.as_ref().storage, "crates.io:pool", "1.0.0").unwrap();
+    }
+
+    #[test]
+    fn check_migration_version_refuses_downgrade() {
+        let mut deps = mock_dependencies();
+        set_contract_version(deps.as_mut().storage, "crates.io:pool", "1.2.0").unwrap();
+        let err = check_migration_version(deps.as_ref().storage, "crates.io:pool", "1.1.0").unwrap_err();
+        assert_eq!(err, CommonError::CannotMigrateVersion {
+            previous_version: "1.2.0".to_string(),
+            new_version: "1.1.0".to_string(),
+        });
+    }
+
+    #[test]
+    fn check_migration_version_refuses_other_contract() {
+        let mut deps = mock_dependencies();
+        set_contract_version(deps.as_mut().storage, "crates.io:staking", "1.0.0").unwrap();
+        let err = check_migration_version(deps.as_ref().storage, "crates.io:pool", "1.1.0").unwrap_err();
+        assert_eq!(err, CommonError::CannotMigrate {
+            previous_contract: "crates.io:staking".to_string(),
+        });
+    }
+}
@@ -0,0 +1,61 @@
+//! Messages the other contracts send to the FanFury AMM. They are the subset of its
+//! `ExecuteMsg`, `QueryMsg` and `ConfigResponse` those contracts use, so they do not
+//! depend on the AMM crate.
+
+use cosmwasm_std::Uint128;
+use cw20::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum TokenSelect {
+    Token1,
+    Token2,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapExecuteMsg {
+    Swap {
+        pair_id: Option<u64>,
+        input_token: TokenSelect,
+        input_amount: Uint128,
+        min_output: Uint128,
+        fee_amount: Uint128,
+        expiration: Option<Expiration>,
+        /// Receiver of the output, the sender when not set
+        recipient: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapQueryMsg {
+    Config {},
+    Token1ForToken2Price {
+        pair_id: Option<u64>,
+        token1_amount: Uint128,
+    },
+    Token2ForToken1Price {
+        pair_id: Option<u64>,
+        token2_amount: Uint128,
+    },
+}
+
+/// Fee settings of the AMM config, the only part of it the other contracts read
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapFeeConfigResponse {
+    pub tx_fee: u64,
+    pub platform_fee: u64,
+    pub fee_in_kind: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Token1ForToken2PriceResponse {
+    pub token2_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Token2ForToken1PriceResponse {
+    pub token1_amount: Uint128,
+}
@@ -0,0 +1,121 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, BalanceResponse as NativeBalanceResponse, BankMsg, BankQuery, Coin, CosmosMsg, QuerierWrapper,
+    QueryRequest, Uint128, WasmMsg, WasmQuery,
+};
+use cw20::{Balance, BalanceResponse as CW20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Denom};
+
+use crate::error::CommonError;
+
+/// One token with 6 decimals
+pub const NORMAL_DECIMAL: u128 = 1000000u128;
+/// Denominator of the fees, discounts and other ratios given in permille
+pub const THOUSAND: u64 = 1000u64;
+
+/// Amount of `denom` in the funds sent or the cw20 received
+pub fn get_amount_of_denom(balance: Balance, denom: Denom) -> Result<Uint128, CommonError> {
+    match denom {
+        Denom::Native(native_str) => match balance {
+            Balance::Native(native_balance) => {
+                let amount = native_balance
+                    .0
+                    .iter()
+                    .find(|coin| coin.denom == native_str)
+                    .map(|coin| coin.amount)
+                    .unwrap_or_default();
+                if amount.is_zero() {
+                    return Err(CommonError::NativeInputZero {});
+                }
+                Ok(amount)
+            }
+            Balance::Cw20(_) => Err(CommonError::TokenTypeMismatch {}),
+        },
+        Denom::Cw20(cw20_address) => match balance {
+            Balance::Native(_) => Err(CommonError::TokenTypeMismatch {}),
+            Balance::Cw20(token) => {
+                if cw20_address != token.address {
+                    return Err(CommonError::TokenTypeMismatch {});
+                }
+                if token.amount.is_zero() {
+                    return Err(CommonError::Cw20InputZero {});
+                }
+                Ok(token.amount)
+            }
+        },
+    }
+}
+
+/// Bank send or cw20 transfer of `amount` to `receiver`
+pub fn transfer_token_message(denom: Denom, amount: Uint128, receiver: Addr) -> Result<CosmosMsg, CommonError> {
+    match denom {
+        Denom::Native(native_str) => Ok(BankMsg::Send {
+            to_address: receiver.into(),
+            amount: vec![Coin {
+                denom: native_str,
+                amount,
+            }],
+        }
+        .into()),
+        Denom::Cw20(cw20_address) => Ok(WasmMsg::Execute {
+            contract_addr: cw20_address.into(),
+            funds: vec![],
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: receiver.into(),
+                amount,
+            })?,
+        }
+        .into()),
+    }
+}
+
+/// Balance of `denom` held by `contract_addr`
+pub fn get_token_amount(querier: QuerierWrapper, denom: Denom, contract_addr: Addr) -> Result<Uint128, CommonError> {
+    match denom {
+        Denom::Native(native_str) => {
+            let native_response: NativeBalanceResponse = querier.query(&QueryRequest::Bank(BankQuery::Balance {
+                address: contract_addr.into(),
+                denom: native_str,
+            }))?;
+            Ok(native_response.amount.amount)
+        }
+        Denom::Cw20(cw20_address) => {
+            let balance_response: CW20BalanceResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: cw20_address.into(),
+                msg: to_json_binary(&Cw20QueryMsg::Balance {
+                    address: contract_addr.into(),
+                })?,
+            }))?;
+            Ok(balance_response.balance)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coins;
+    use cw20::Cw20CoinVerified;
+
+    #[test]
+    fn get_amount_of_denom_matches_the_funds() {
+        let usdc = Denom::Native("usdc".to_string());
+        let fury = Denom::Cw20(Addr::unchecked("fury"));
+
+        let amount = get_amount_of_denom(Balance::from(coins(100, "usdc")), usdc.clone()).unwrap();
+        assert_eq!(amount, Uint128::new(100));
+        let err = get_amount_of_denom(Balance::from(coins(100, "uatom")), usdc.clone()).unwrap_err();
+        assert_eq!(err, CommonError::NativeInputZero {});
+
+        let received = Cw20CoinVerified { address: Addr::unchecked("fury"), amount: Uint128::new(50) };
+        let amount = get_amount_of_denom(Balance::Cw20(received.clone()), fury.clone()).unwrap();
+        assert_eq!(amount, Uint128::new(50));
+        let err = get_amount_of_denom(Balance::Cw20(received), usdc).unwrap_err();
+        assert_eq!(err, CommonError::TokenTypeMismatch {});
+
+        let other = Cw20CoinVerified { address: Addr::unchecked("other"), amount: Uint128::new(50) };
+        let err = get_amount_of_denom(Balance::Cw20(other), fury.clone()).unwrap_err();
+        assert_eq!(err, CommonError::TokenTypeMismatch {});
+        let empty = Cw20CoinVerified { address: Addr::unchecked("fury"), amount: Uint128::zero() };
+        let err = get_amount_of_denom(Balance::Cw20(empty), fury).unwrap_err();
+        assert_eq!(err, CommonError::Cw20InputZero {});
+    }
+}